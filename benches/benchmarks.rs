@@ -1,6 +1,17 @@
+use std::cmp::Ordering;
+use std::convert::Infallible;
+use std::fmt::Write as _;
+use std::future::{self, Pending};
+use std::ops::Range;
+
 use criterion::{black_box, BatchSize, Criterion};
 
-use toon::Grid;
+use toon::backend::{Backend, Bound, ReadEvents, TerminalEvent, Tty};
+use toon::output::Ext as _;
+use toon::{
+    Attributes, Capabilities, CellKind, Color, CursorShape, Element, Events, Grid, Input,
+    Intensity, Output, Style, Terminal, Vec2,
+};
 
 fn main() {
     let mut c = Criterion::default().configure_from_args();
@@ -15,4 +26,330 @@ fn main() {
             BatchSize::LargeInput,
         );
     });
+
+    for (name, old, new) in frames() {
+        println!("{}: {} bytes", name, emitted(&old, &new).len());
+
+        c.bench_function(&format!("Terminal::render ({})", name), |b| {
+            b.iter_batched(
+                || {
+                    let mut terminal = Terminal::new(Bytes::new(old.size())).unwrap();
+                    terminal.render(Frame(&old)).unwrap();
+                    terminal
+                },
+                |mut terminal| {
+                    terminal.render(Frame(&new)).unwrap();
+                    black_box(terminal)
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+}
+
+/// Typical pairs of consecutive frames, with names.
+fn frames() -> Vec<(&'static str, Grid, Grid)> {
+    let size = Vec2::new(120, 40);
+    let text = editor(size, 0, "12:00");
+
+    let mut edited = text.clone();
+    edited.write((14, 20), "edited", Style::default());
+
+    vec![
+        ("first frame", Grid::new(size), text.clone()),
+        ("edit", text.clone(), edited),
+        ("scroll", text.clone(), editor(size, 1, "12:00")),
+        ("status bar", text, editor(size, 0, "12:01")),
+    ]
+}
+
+/// Draw a text editor-like screen, with a title bar, numbered lines of text and a status bar.
+fn editor(size: Vec2<u16>, first_line: u16, time: &str) -> Grid {
+    let mut grid = Grid::new(size);
+
+    let bar = Style::new(Color::White, Color::Blue, Attributes::default());
+    let title = format!("{:^width$}", "file.txt", width = usize::from(size.x));
+    grid.write((0, 0), &title, bar.clone());
+
+    let mut bold = Style::default();
+    bold.attributes.intensity = Intensity::Bold;
+    for y in 1..size.y - 1 {
+        let number = first_line + y;
+        grid.write((0, y), format!("{:>4} ", number), bold.clone());
+        let line = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. "
+            .repeat(usize::from(number % 3));
+        grid.write((6, y), &line, Style::default());
+    }
+
+    let status = format!("{:<width$}", time, width = usize::from(size.x));
+    grid.write((0, size.y - 1), &status, bar);
+
+    grid
+}
+
+/// Get the bytes emitted when drawing `new` after `old`.
+fn emitted(old: &Grid, new: &Grid) -> Vec<u8> {
+    let mut terminal = Terminal::new(Bytes::new(old.size())).unwrap();
+    terminal.render(Frame(old)).unwrap();
+    terminal.backend_mut().out.clear();
+    terminal.render(Frame(new)).unwrap();
+    std::mem::take(&mut terminal.backend_mut().out)
+}
+
+/// An element that draws a grid.
+struct Frame<'a>(&'a Grid);
+
+impl Element for Frame<'_> {
+    type Event = Infallible;
+
+    fn draw(&self, output: &mut dyn Output) {
+        for (y, line) in self.0.lines().iter().enumerate() {
+            for (x, cell) in line.cells().iter().enumerate() {
+                if let CellKind::Char {
+                    contents, style, ..
+                } = cell.kind()
+                {
+                    output.write((x as u16, y as u16), contents, style.clone());
+                }
+            }
+        }
+    }
+    fn ideal_width(&self, _height: u16, _max_width: Option<u16>) -> u16 {
+        self.0.width()
+    }
+    fn ideal_height(&self, _width: u16, _max_height: Option<u16>) -> u16 {
+        self.0.height()
+    }
+    fn ideal_size(&self, _maximum: Vec2<Option<u16>>) -> Vec2<u16> {
+        self.0.size()
+    }
+    fn handle(&self, _input: Input, _events: &mut dyn Events<Self::Event>) {}
+}
+
+/// A backend that records the bytes the ANSI backend would write, without needing a terminal.
+struct Bytes {
+    size: Vec2<u16>,
+    out: Vec<u8>,
+    tty: Option<Tty>,
+}
+
+impl Bytes {
+    fn new(size: Vec2<u16>) -> Self {
+        Self {
+            size,
+            out: Vec::new(),
+            tty: None,
+        }
+    }
+
+    fn push(&mut self, s: &str) -> Result<(), Infallible> {
+        self.out.extend_from_slice(s.as_bytes());
+        Ok(())
+    }
+
+    fn set_color(&mut self, color: Color, background: bool) -> Result<(), Infallible> {
+        let offset = if background { 10 } else { 0 };
+        let code = match color {
+            Color::Default => 39,
+            Color::Black => 30,
+            Color::DarkRed => 31,
+            Color::DarkGreen => 32,
+            Color::DarkYellow => 33,
+            Color::DarkBlue => 34,
+            Color::DarkMagenta => 35,
+            Color::DarkCyan => 36,
+            Color::LightGray => 37,
+            Color::DarkGray => 90,
+            Color::Red => 91,
+            Color::Green => 92,
+            Color::Yellow => 93,
+            Color::Blue => 94,
+            Color::Magenta => 95,
+            Color::Cyan => 96,
+            Color::White => 97,
+            Color::AnsiValue(v) => {
+                return self.push(&format!("\x1B[{};5;{}m", 38 + offset, v.get()));
+            }
+            Color::Rgb(rgb) => {
+                let (r, g, b) = (rgb.r, rgb.g, rgb.b);
+                return self.push(&format!("\x1B[{};2;{};{};{}m", 38 + offset, r, g, b));
+            }
+        };
+        self.push(&format!("\x1B[{}m", code + offset))
+    }
+
+    fn set_attribute(&mut self, on: bool, set: u8, unset: u8) -> Result<(), Infallible> {
+        self.push(&format!("\x1B[{}m", if on { set } else { unset }))
+    }
+}
+
+impl Backend for Bytes {
+    type Error = Infallible;
+    type Bound = Self;
+
+    fn is_dummy() -> bool {
+        true
+    }
+
+    fn bind(mut self, tty: Tty) -> Result<Self, Infallible> {
+        self.tty = Some(tty);
+        Ok(self)
+    }
+}
+
+impl Bound for Bytes {
+    type Error = Infallible;
+
+    fn size(&mut self) -> Result<Vec2<u16>, Self::Error> {
+        Ok(self.size)
+    }
+    fn capabilities(&mut self) -> Result<Capabilities, Self::Error> {
+        Ok(Capabilities::all())
+    }
+    fn set_title(&mut self, title: &str) -> Result<(), Self::Error> {
+        self.push(&format!("\x1B]0;{}\x07", title))
+    }
+    fn enter_alternate_screen(&mut self) -> Result<(), Self::Error> {
+        self.push("\x1B[?1049h\x1B[2J")
+    }
+    fn begin_synchronized_update(&mut self) -> Result<(), Self::Error> {
+        self.push("\x1B[?2026h")
+    }
+    fn end_synchronized_update(&mut self) -> Result<(), Self::Error> {
+        self.push("\x1B[?2026l")
+    }
+
+    fn hide_cursor(&mut self) -> Result<(), Self::Error> {
+        self.push("\x1B[?25l")
+    }
+    fn show_cursor(&mut self) -> Result<(), Self::Error> {
+        self.push("\x1B[?25h")
+    }
+    fn set_cursor_shape(&mut self, _shape: CursorShape) -> Result<(), Self::Error> {
+        self.push("\x1B[1 q")
+    }
+    fn set_cursor_blinking(&mut self, _blinking: bool) -> Result<(), Self::Error> {
+        self.push("\x1B[1 q")
+    }
+    fn set_cursor_pos(&mut self, pos: Vec2<u16>) -> Result<(), Self::Error> {
+        self.push(&format!("\x1B[{};{}H", pos.y + 1, pos.x + 1))
+    }
+    fn move_cursor(&mut self, from: Vec2<u16>, to: Vec2<u16>) -> Result<(), Self::Error> {
+        // The shortest of an absolute move and relative moves, like the ANSI backend.
+        fn shortest(a: String, b: String) -> String {
+            if a.len() <= b.len() {
+                a
+            } else {
+                b
+            }
+        }
+        fn horizontal(from: u16, to: u16) -> String {
+            match to.cmp(&from) {
+                Ordering::Equal => String::new(),
+                Ordering::Greater => format!("\x1B[{}C", to - from),
+                Ordering::Less => shortest(
+                    "\x08".repeat(usize::from(from - to)),
+                    format!("\x1B[{}D", from - to),
+                ),
+            }
+        }
+
+        let vertical = match to.y.cmp(&from.y) {
+            Ordering::Equal => String::new(),
+            Ordering::Greater => shortest(
+                "\n".repeat(usize::from(to.y - from.y)),
+                format!("\x1B[{}B", to.y - from.y),
+            ),
+            Ordering::Less => format!("\x1B[{}A", from.y - to.y),
+        };
+
+        let absolute = format!("\x1B[{};{}H", to.y + 1, to.x + 1);
+        let relative = format!("{}{}", vertical, horizontal(from.x, to.x));
+        let carriage_return = format!("{}\r{}", vertical, horizontal(0, to.x));
+
+        let movement = shortest(absolute, shortest(relative, carriage_return));
+        self.push(&movement)
+    }
+    fn cursor_pos(&mut self) -> Result<Vec2<u16>, Self::Error> {
+        Ok(Vec2::default())
+    }
+
+    fn set_foreground(&mut self, foreground: Color) -> Result<(), Self::Error> {
+        self.set_color(foreground, false)
+    }
+    fn set_background(&mut self, background: Color) -> Result<(), Self::Error> {
+        self.set_color(background, true)
+    }
+    fn set_intensity(&mut self, intensity: Intensity) -> Result<(), Self::Error> {
+        let code = match intensity {
+            Intensity::Dim => 2,
+            Intensity::Normal => 22,
+            Intensity::Bold => 1,
+        };
+        self.push(&format!("\x1B[{}m", code))
+    }
+    fn set_italic(&mut self, italic: bool) -> Result<(), Self::Error> {
+        self.set_attribute(italic, 3, 23)
+    }
+    fn set_underlined(&mut self, underlined: bool) -> Result<(), Self::Error> {
+        self.set_attribute(underlined, 4, 24)
+    }
+    fn set_blinking(&mut self, blinking: bool) -> Result<(), Self::Error> {
+        self.set_attribute(blinking, 5, 25)
+    }
+    fn set_crossed_out(&mut self, crossed_out: bool) -> Result<(), Self::Error> {
+        self.set_attribute(crossed_out, 9, 29)
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), Self::Error> {
+        self.push(text)
+    }
+    fn write_repeated(&mut self, c: char, count: u16) -> Result<(), Self::Error> {
+        let mut s = c.to_string();
+        if count > 1 {
+            write!(s, "\x1B[{}b", count - 1).unwrap();
+        }
+        self.push(&s)
+    }
+    fn erase_chars(&mut self, count: u16) -> Result<bool, Self::Error> {
+        self.push(&format!("\x1B[{}X", count))?;
+        Ok(true)
+    }
+    fn new_lines(&mut self, lines: u16) -> Result<(), Self::Error> {
+        self.push(&"\r\n".repeat(usize::from(lines)))
+    }
+    fn scroll(&mut self, region: Range<u16>, lines: i16) -> Result<bool, Self::Error> {
+        let direction = if lines > 0 { 'S' } else { 'T' };
+        self.push(&format!(
+            "\x1B[{};{}r\x1B[{}{}\x1B[r",
+            region.start + 1,
+            region.end,
+            lines.unsigned_abs(),
+            direction
+        ))?;
+        Ok(true)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn suspend(&mut self) -> Result<Tty, Self::Error> {
+        Ok(self.tty.take().unwrap())
+    }
+    fn resume(&mut self, tty: Tty) -> Result<(), Self::Error> {
+        self.tty = Some(tty);
+        Ok(())
+    }
+    fn reset(self) -> Result<Tty, Self::Error> {
+        Ok(self.tty.unwrap())
+    }
+}
+
+impl<'a> ReadEvents<'a> for Bytes {
+    type EventError = Infallible;
+    type EventFuture = Pending<Result<TerminalEvent, Infallible>>;
+
+    fn read_event(&'a mut self) -> Self::EventFuture {
+        future::pending()
+    }
 }
@@ -2,9 +2,7 @@
 
 use std::time::{Duration, Instant};
 
-use async_io::Timer;
-use futures_lite::future;
-use toon::{Crossterm, ElementExt, Styled, Terminal};
+use toon::{Crossterm, Element, ElementExt, Styled, Terminal};
 
 /// The state of the stopwatch.
 enum Stopwatch {
@@ -24,11 +22,31 @@ enum Event {
     Quit,
 }
 
+/// Add the controls of the stopwatch to an element.
+fn controls<E: Element<Event = Event>>(element: E) -> impl Element<Event = Event> {
+    element
+        .on('q', |_| Event::Quit)
+        .label("Quit")
+        .on('r', |_| Event::Reset)
+        .label("Reset")
+        .on(' ', |_| Event::Toggle)
+        .label("Start/Stop")
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     async_io::block_on(async {
         // The stopwatch starts in a stopped state with 0 seconds.
         let mut stopwatch = Stopwatch::Stopped(Duration::default());
 
+        // Generate the line of hints at the bottom from the bindings of the controls.
+        let mut bindings = Vec::new();
+        controls(toon::empty()).bindings(&mut bindings);
+        let hints = bindings
+            .iter()
+            .map(|binding| format!("[{}]: {}", binding.input.as_deref().unwrap(), binding.label))
+            .collect::<Vec<_>>()
+            .join("      ");
+
         let mut terminal = Terminal::new(Crossterm::default())?;
 
         'outer: loop {
@@ -38,10 +56,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 Stopwatch::Stopped(duration) => duration,
             };
 
-            // Wait for the first of two events:
-            let events = future::race(
-                // - The user causing some events to occur.
-                terminal.draw(
+            // While the stopwatch is running, draw it again every 15 milliseconds. When the
+            // frame is due `draw` returns no events, and the loop comes back here with the new
+            // duration.
+            let interval = match stopwatch {
+                Stopwatch::Running(_) => Some(Duration::from_millis(15)),
+                Stopwatch::Stopped(_) => None,
+            };
+
+            let events = terminal
+                .draw(controls(
                     toon::column(
                         toon::stretch(0),
                         (
@@ -52,25 +76,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             ))
                             .bold()
                             .float((toon::Alignment::Middle, toon::Alignment::Middle)),
-                            toon::span("[Space] Start/Stop      [R]: Reset           [Q]: Quit"),
+                            toon::span(&hints),
                         ),
                     )
-                    .on(' ', |_| Event::Toggle)
-                    .on('r', |_| Event::Reset)
-                    .on('q', |_| Event::Quit),
-                ),
-                // - The timer, while running, hasn't been updated in a while.
-                async {
-                    if let Stopwatch::Stopped(_) = stopwatch {
-                        // Don't fire useless events if the timer is stopped.
-                        future::pending::<()>().await;
-                    } else {
-                        Timer::after(Duration::from_millis(15)).await;
-                    }
-                    Ok(Vec::new())
-                },
-            )
-            .await?;
+                    .animate(interval),
+                ))
+                .await?;
 
             for event in events {
                 match event {
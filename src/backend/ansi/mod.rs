@@ -0,0 +1,692 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io::{self, Write};
+use std::mem;
+use std::ops::Range;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::style::{Color, Intensity, Link, Palette, Rgb, UnderlineStyle};
+use crate::{Capabilities, CursorShape, KeyboardEnhancements, Vec2, WidthTable};
+
+use super::{escape, Backend, ReadEvents, TerminalEvent, Tty};
+
+mod parse;
+mod sys;
+
+/// How long to wait for the rest of an escape sequence before treating a lone escape byte as a
+/// press of the escape key, in milliseconds.
+const ESCAPE_TIMEOUT: u16 = 50;
+
+/// How long to wait for the terminal to respond to a query.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The byte written to the wake pipe to stop the reading thread.
+const STOP_BYTE: u8 = b'q';
+
+/// Native ANSI backend.
+///
+/// This backend writes ECMA-48 escape sequences directly to the terminal and parses the input
+/// itself, so it does not depend on any terminal library. It is only available on Unix.
+///
+/// Currently there is no configuration here.
+#[derive(Debug, Default, Clone)]
+#[non_exhaustive]
+pub struct Ansi {}
+
+impl Backend for Ansi {
+    type Error = io::Error;
+    type Bound = Bound;
+
+    fn bind(self, mut io: Tty) -> Result<Self::Bound, Self::Error> {
+        let fd = io.as_raw_fd();
+
+        let raw_mode = sys::RawMode::enable(fd)?;
+        io.write_all(SET_UP.as_bytes())?;
+        io.write_all(escape::PUSH_TITLE.as_bytes())?;
+        io.flush()?;
+
+        Ok(Bound {
+            io,
+            raw_mode: Some(raw_mode),
+            reader: Reader::spawn(fd)?,
+            cursor_shape: CursorShape::Block,
+            cursor_blinking: true,
+            underlined: false,
+            underline_style: UnderlineStyle::Straight,
+            alternate_screen: false,
+            keyboard_enhancements: KeyboardEnhancements::default(),
+            repeat: Capabilities::detect().repeat,
+        })
+    }
+}
+
+/// The sequence written to set up the terminal after enabling raw mode, followed by
+/// [`escape::PUSH_TITLE`].
+const SET_UP: &str = concat!(
+    // Disable line wrap
+    "\x1B[?7l",
+    // Enable mouse capture: clicks, drags, all motion and SGR encoding
+    "\x1B[?1000h\x1B[?1002h\x1B[?1003h\x1B[?1006h",
+    // Enable bracketed paste
+    "\x1B[?2004h",
+    // Enable focus reporting
+    "\x1B[?1004h",
+);
+
+/// The sequence written to undo [`SET_UP`] and any changes made while bound, followed by
+/// [`escape::POP_TITLE`].
+const RESET: &str = concat!(
+    // Disable mouse capture
+    "\x1B[?1006l\x1B[?1003l\x1B[?1002l\x1B[?1000l",
+    // Disable bracketed paste
+    "\x1B[?2004l",
+    // Disable focus reporting
+    "\x1B[?1004l",
+    // Enable line wrap
+    "\x1B[?7h",
+    // Reset the style, cursor shape and cursor color
+    "\x1B[0m\x1B[0 q\x1B]112\x1B\\",
+    // Show the cursor
+    "\x1B[?25h",
+);
+
+#[derive(Debug)]
+pub struct Bound {
+    io: Tty,
+    /// Only [`None`] while suspended.
+    raw_mode: Option<sys::RawMode>,
+    reader: Reader,
+    /// The shape of the cursor; DECSCUSR sets both the shape and blinking at once.
+    cursor_shape: CursorShape,
+    /// Whether the cursor blinks.
+    cursor_blinking: bool,
+    /// Whether the text is underlined; the underline style is set with the same sequence.
+    underlined: bool,
+    /// The style of underlines.
+    underline_style: UnderlineStyle,
+    /// Whether the alternate screen has been entered.
+    alternate_screen: bool,
+    /// The keyboard enhancements, which are pushed onto the terminal's stack of them when they
+    /// aren't empty.
+    keyboard_enhancements: KeyboardEnhancements,
+    /// Whether the terminal supports REP and ECH.
+    repeat: bool,
+}
+
+impl Bound {
+    fn set_color(&mut self, color: Color, background: bool) -> io::Result<()> {
+        let offset = if background { 10 } else { 0 };
+        let code = match color {
+            Color::Default => 39,
+            Color::Black => 30,
+            Color::DarkRed => 31,
+            Color::DarkGreen => 32,
+            Color::DarkYellow => 33,
+            Color::DarkBlue => 34,
+            Color::DarkMagenta => 35,
+            Color::DarkCyan => 36,
+            Color::LightGray => 37,
+            Color::DarkGray => 90,
+            Color::Red => 91,
+            Color::Green => 92,
+            Color::Yellow => 93,
+            Color::Blue => 94,
+            Color::Magenta => 95,
+            Color::Cyan => 96,
+            Color::White => 97,
+            Color::AnsiValue(v) => {
+                return write!(self.io, "\x1B[{};5;{}m", 38 + offset, v.get());
+            }
+            Color::Rgb(Rgb { r, g, b }) => {
+                return write!(self.io, "\x1B[{};2;{};{};{}m", 38 + offset, r, g, b);
+            }
+        };
+        write!(self.io, "\x1B[{}m", code + offset)
+    }
+
+    fn set_attribute(&mut self, on: bool, set: u8, unset: u8) -> io::Result<()> {
+        write!(self.io, "\x1B[{}m", if on { set } else { unset })
+    }
+
+    /// Write the SGR sequence for whether the text is underlined and the underline style.
+    fn write_underline(&mut self) -> io::Result<()> {
+        let sequence: &[u8] = match (self.underlined, self.underline_style) {
+            (false, _) => b"\x1B[24m",
+            (true, UnderlineStyle::Straight) => b"\x1B[4m",
+            (true, UnderlineStyle::Double) => b"\x1B[4:2m",
+            (true, UnderlineStyle::Curly) => b"\x1B[4:3m",
+            (true, UnderlineStyle::Dotted) => b"\x1B[4:4m",
+            (true, UnderlineStyle::Dashed) => b"\x1B[4:5m",
+        };
+        self.io.write_all(sequence)
+    }
+
+    /// Stop reading input and restore the terminal's original state.
+    fn release(&mut self) -> io::Result<()> {
+        self.reader.stop()?;
+
+        if self.keyboard_enhancements.flags() != 0 {
+            self.io.write_all(b"\x1B[<u")?;
+        }
+        self.io.write_all(RESET.as_bytes())?;
+        self.io.write_all(escape::POP_TITLE.as_bytes())?;
+        if self.alternate_screen {
+            self.io.write_all(b"\x1B[?1049l")?;
+            self.alternate_screen = false;
+        }
+        self.io.flush()?;
+        if let Some(raw_mode) = self.raw_mode.take() {
+            raw_mode.disable()?;
+        }
+
+        self.cursor_shape = CursorShape::Block;
+        self.cursor_blinking = true;
+        self.underlined = false;
+        self.underline_style = UnderlineStyle::Straight;
+
+        Ok(())
+    }
+}
+
+impl super::Bound for Bound {
+    type Error = io::Error;
+
+    // General functions
+
+    fn size(&mut self) -> Result<Vec2<u16>, Self::Error> {
+        sys::size(self.io.as_raw_fd())
+    }
+    fn set_title(&mut self, title: &str) -> Result<(), Self::Error> {
+        escape::set_title(&mut self.io, false, title)
+    }
+    fn set_icon_name(&mut self, icon_name: &str) -> Result<(), Self::Error> {
+        escape::set_title(&mut self.io, true, icon_name)
+    }
+    fn enter_alternate_screen(&mut self) -> Result<(), Self::Error> {
+        self.alternate_screen = true;
+        self.io.write_all(b"\x1B[?1049h\x1B[2J")
+    }
+    fn begin_synchronized_update(&mut self) -> Result<(), Self::Error> {
+        self.io.write_all(b"\x1B[?2026h")
+    }
+    fn end_synchronized_update(&mut self) -> Result<(), Self::Error> {
+        self.io.write_all(b"\x1B[?2026l")
+    }
+    fn measure_widths(&mut self, probes: &[&str]) -> Result<WidthTable, Self::Error> {
+        let shared = &*self.reader.shared;
+
+        let mut state = shared.state.lock().unwrap();
+        for probe in probes {
+            write!(self.io, "\r{probe}\x1B[6n")?;
+        }
+        // Like with the palette, the device attributes tell us when a terminal that doesn't report
+        // the cursor position has ignored the queries, so we don't wait for each of them.
+        self.io.write_all(b"\r\x1B[2K\x1B[c")?;
+        self.io.flush()?;
+        state.measured = Some(Vec::new());
+        state.awaiting_device_attributes = true;
+
+        let (mut state, _) = shared
+            .response
+            .wait_timeout_while(state, RESPONSE_TIMEOUT, |state| {
+                state.awaiting_device_attributes
+            })
+            .unwrap();
+        state.awaiting_device_attributes = false;
+
+        let mut widths = WidthTable::new();
+        for (probe, pos) in probes.iter().zip(state.measured.take().unwrap_or_default()) {
+            widths.insert(*probe, pos.x);
+        }
+        Ok(widths)
+    }
+    fn query_palette(&mut self) -> Result<Palette, Self::Error> {
+        let shared = &*self.reader.shared;
+
+        let mut state = shared.state.lock().unwrap();
+        self.io.write_all(b"\x1B]10;?\x1B\\\x1B]11;?\x1B\\")?;
+        for value in 0..16 {
+            write!(self.io, "\x1B]4;{value};?\x1B\\")?;
+        }
+        // Terminals respond to queries in order and almost all of them report their device
+        // attributes, so once they have every color they support has been reported.
+        self.io.write_all(b"\x1B[c")?;
+        self.io.flush()?;
+        state.palette = Some(Palette::default());
+        state.awaiting_device_attributes = true;
+
+        let (mut state, _) = shared
+            .response
+            .wait_timeout_while(state, RESPONSE_TIMEOUT, |state| {
+                state.awaiting_device_attributes
+            })
+            .unwrap();
+        state.awaiting_device_attributes = false;
+        Ok(state.palette.take().unwrap_or_default())
+    }
+    fn bell(&mut self) -> Result<(), Self::Error> {
+        self.io.write_all(b"\x07")
+    }
+    fn notify(&mut self, title: &str, body: &str) -> Result<(), Self::Error> {
+        escape::notify(&mut self.io, title, body)
+    }
+    fn set_clipboard(&mut self, text: &str) -> Result<(), Self::Error> {
+        escape::set_clipboard(&mut self.io, text)
+    }
+    fn clipboard(&mut self) -> Result<Option<String>, Self::Error> {
+        let shared = &*self.reader.shared;
+
+        let mut state = shared.state.lock().unwrap();
+        // Like with the palette, the device attributes tell us when a terminal that doesn't allow
+        // reading the clipboard has ignored the query.
+        self.io.write_all(b"\x1B]52;c;?\x1B\\\x1B[c")?;
+        self.io.flush()?;
+        state.awaiting_clipboard = true;
+        state.clipboard = None;
+        state.awaiting_device_attributes = true;
+
+        let (mut state, _) = shared
+            .response
+            .wait_timeout_while(state, RESPONSE_TIMEOUT, |state| {
+                state.awaiting_device_attributes
+            })
+            .unwrap();
+        state.awaiting_clipboard = false;
+        state.awaiting_device_attributes = false;
+        Ok(state.clipboard.take())
+    }
+    fn set_keyboard_enhancements(
+        &mut self,
+        enhancements: KeyboardEnhancements,
+    ) -> Result<(), Self::Error> {
+        if self.keyboard_enhancements.flags() != 0 {
+            self.io.write_all(b"\x1B[<u")?;
+        }
+        match enhancements.flags() {
+            0 => {}
+            flags => write!(self.io, "\x1B[>{flags}u")?,
+        }
+        self.keyboard_enhancements = enhancements;
+        Ok(())
+    }
+
+    // Cursor functions
+
+    fn hide_cursor(&mut self) -> Result<(), Self::Error> {
+        self.io.write_all(b"\x1B[?25l")
+    }
+    fn show_cursor(&mut self) -> Result<(), Self::Error> {
+        self.io.write_all(b"\x1B[?25h")
+    }
+    fn set_cursor_shape(&mut self, shape: CursorShape) -> Result<(), Self::Error> {
+        self.cursor_shape = shape;
+        escape::set_cursor_style(&mut self.io, shape, self.cursor_blinking)
+    }
+    fn set_cursor_blinking(&mut self, blinking: bool) -> Result<(), Self::Error> {
+        self.cursor_blinking = blinking;
+        escape::set_cursor_style(&mut self.io, self.cursor_shape, blinking)
+    }
+    fn set_cursor_color(&mut self, color: Color) -> Result<(), Self::Error> {
+        escape::set_cursor_color(&mut self.io, color)
+    }
+    fn set_cursor_pos(&mut self, pos: Vec2<u16>) -> Result<(), Self::Error> {
+        write!(self.io, "\x1B[{};{}H", pos.y + 1, pos.x + 1)
+    }
+    fn move_cursor(&mut self, from: Vec2<u16>, to: Vec2<u16>) -> Result<(), Self::Error> {
+        escape::move_cursor(&mut self.io, from, to)
+    }
+    fn cursor_pos(&mut self) -> Result<Vec2<u16>, Self::Error> {
+        let shared = &*self.reader.shared;
+
+        let mut state = shared.state.lock().unwrap();
+        self.io.write_all(b"\x1B[6n")?;
+        self.io.flush()?;
+        state.cursor_pos = None;
+        state.awaiting_cursor_pos = true;
+
+        let (mut state, _) = shared
+            .response
+            .wait_timeout_while(state, RESPONSE_TIMEOUT, |state| state.cursor_pos.is_none())
+            .unwrap();
+        state.awaiting_cursor_pos = false;
+        state.cursor_pos.take().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::TimedOut,
+                "terminal did not report the cursor position",
+            )
+        })
+    }
+
+    // Style functions
+
+    fn set_foreground(&mut self, foreground: Color) -> Result<(), Self::Error> {
+        self.set_color(foreground, false)
+    }
+    fn set_background(&mut self, background: Color) -> Result<(), Self::Error> {
+        self.set_color(background, true)
+    }
+    fn set_intensity(&mut self, intensity: Intensity) -> Result<(), Self::Error> {
+        let code = match intensity {
+            Intensity::Dim => 2,
+            Intensity::Normal => 22,
+            Intensity::Bold => 1,
+        };
+        write!(self.io, "\x1B[{code}m")
+    }
+    fn set_italic(&mut self, italic: bool) -> Result<(), Self::Error> {
+        self.set_attribute(italic, 3, 23)
+    }
+    fn set_underlined(&mut self, underlined: bool) -> Result<(), Self::Error> {
+        self.underlined = underlined;
+        self.write_underline()
+    }
+    fn set_blinking(&mut self, blinking: bool) -> Result<(), Self::Error> {
+        self.set_attribute(blinking, 5, 25)
+    }
+    fn set_crossed_out(&mut self, crossed_out: bool) -> Result<(), Self::Error> {
+        self.set_attribute(crossed_out, 9, 29)
+    }
+    fn set_underline_style(&mut self, style: UnderlineStyle) -> Result<(), Self::Error> {
+        self.underline_style = style;
+        if self.underlined {
+            self.write_underline()?;
+        }
+        Ok(())
+    }
+    fn set_underline_color(&mut self, color: Color) -> Result<(), Self::Error> {
+        match (color, color.ansi_value()) {
+            (Color::Rgb(Rgb { r, g, b }), _) => write!(self.io, "\x1B[58;2;{r};{g};{b}m"),
+            (_, Some(value)) => write!(self.io, "\x1B[58;5;{value}m"),
+            (_, None) => self.io.write_all(b"\x1B[59m"),
+        }
+    }
+    fn set_reversed(&mut self, reversed: bool) -> Result<(), Self::Error> {
+        self.set_attribute(reversed, 7, 27)
+    }
+    fn set_hidden(&mut self, hidden: bool) -> Result<(), Self::Error> {
+        self.set_attribute(hidden, 8, 28)
+    }
+    fn set_overlined(&mut self, overlined: bool) -> Result<(), Self::Error> {
+        self.set_attribute(overlined, 53, 55)
+    }
+    fn set_link(&mut self, link: Option<Link>) -> Result<(), Self::Error> {
+        escape::set_link(&mut self.io, link)
+    }
+
+    // Writing
+
+    fn write(&mut self, text: &str) -> Result<(), Self::Error> {
+        self.io.write_all(text.as_bytes())
+    }
+    fn write_repeated(&mut self, c: char, count: u16) -> Result<(), Self::Error> {
+        if !self.repeat {
+            for _ in 0..count {
+                write!(self.io, "{c}")?;
+            }
+            return Ok(());
+        }
+        write!(self.io, "{c}")?;
+        if count > 1 {
+            write!(self.io, "\x1B[{}b", count - 1)?;
+        }
+        Ok(())
+    }
+    fn erase_chars(&mut self, count: u16) -> Result<bool, Self::Error> {
+        if !self.repeat {
+            return Ok(false);
+        }
+        write!(self.io, "\x1B[{count}X")?;
+        Ok(true)
+    }
+    fn new_lines(&mut self, lines: u16) -> Result<(), Self::Error> {
+        for _ in 0..lines {
+            self.io.write_all(b"\r\n")?;
+        }
+        Ok(())
+    }
+    fn scroll(&mut self, region: Range<u16>, lines: i16) -> Result<bool, Self::Error> {
+        // Set the scroll region, scroll it and then reset it
+        write!(self.io, "\x1B[{};{}r", region.start + 1, region.end)?;
+        let direction = if lines > 0 { 'S' } else { 'T' };
+        write!(self.io, "\x1B[{}{}", lines.unsigned_abs(), direction)?;
+        self.io.write_all(b"\x1B[r")?;
+        Ok(true)
+    }
+
+    // Finalizing functions
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.io.flush()
+    }
+    fn suspend(&mut self) -> Result<Tty, Self::Error> {
+        self.release()?;
+        Ok(mem::replace(&mut self.io, Tty::dummy()))
+    }
+    fn resume(&mut self, io: Tty) -> Result<(), Self::Error> {
+        self.io = io;
+        let fd = self.io.as_raw_fd();
+
+        self.raw_mode = Some(sys::RawMode::enable(fd)?);
+        self.io.write_all(SET_UP.as_bytes())?;
+        self.io.write_all(escape::PUSH_TITLE.as_bytes())?;
+        match self.keyboard_enhancements.flags() {
+            0 => {}
+            flags => write!(self.io, "\x1B[>{flags}u")?,
+        }
+        self.io.flush()?;
+
+        self.reader = Reader::spawn(fd)?;
+        Ok(())
+    }
+    fn reset(mut self) -> Result<Tty, Self::Error> {
+        self.release()?;
+        Ok(self.io)
+    }
+}
+
+impl<'a> ReadEvents<'a> for Bound {
+    type EventError = io::Error;
+    type EventFuture = EventFuture<'a>;
+
+    fn read_event(&'a mut self) -> Self::EventFuture {
+        EventFuture {
+            shared: &self.reader.shared,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct EventFuture<'a> {
+    shared: &'a Shared,
+}
+
+impl Future for EventFuture<'_> {
+    type Output = io::Result<TerminalEvent>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(event) = state.events.pop_front() {
+            Poll::Ready(event)
+        } else {
+            state.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+/// Data shared between the reading thread and the bound backend.
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    /// Notified when the terminal responds to a query.
+    response: Condvar,
+}
+
+impl Shared {
+    /// Handle an event parsed by the reading thread.
+    fn receive(&self, state: &mut State, event: parse::Event) {
+        match event {
+            parse::Event::CursorPos(pos) if state.awaiting_cursor_pos => {
+                state.cursor_pos = Some(pos);
+                self.response.notify_all();
+            }
+            parse::Event::CursorPos(pos) if state.measured.is_some() => {
+                if let Some(measured) = &mut state.measured {
+                    measured.push(pos);
+                }
+            }
+            parse::Event::Color(color, rgb) => {
+                if let Some(palette) = &mut state.palette {
+                    match color {
+                        parse::PaletteColor::Foreground => palette.foreground = Some(rgb),
+                        parse::PaletteColor::Background => palette.background = Some(rgb),
+                        parse::PaletteColor::Ansi(value) => {
+                            palette.ansi[usize::from(value)] = Some(rgb);
+                        }
+                    }
+                }
+            }
+            parse::Event::Clipboard(text) => {
+                if state.awaiting_clipboard {
+                    state.clipboard = Some(text);
+                }
+            }
+            parse::Event::DeviceAttributes if state.awaiting_device_attributes => {
+                state.awaiting_device_attributes = false;
+                self.response.notify_all();
+            }
+            event => {
+                if let Some(event) = event.into_terminal_event() {
+                    state.push(Ok(event));
+                }
+            }
+        }
+    }
+}
+
+/// The state protected by the mutex in [`Shared`].
+#[derive(Debug, Default)]
+struct State {
+    /// The events that have been read but not yet consumed.
+    events: VecDeque<io::Result<TerminalEvent>>,
+    /// The waker of the task currently waiting for an event.
+    waker: Option<Waker>,
+    /// Whether the cursor position has been queried and not yet reported.
+    awaiting_cursor_pos: bool,
+    /// The reported cursor position.
+    cursor_pos: Option<Vec2<u16>>,
+    /// The cursor positions reported so far while widths are being measured, or `None` if they
+    /// aren't.
+    measured: Option<Vec<Vec2<u16>>>,
+    /// The colors reported so far while the palette is being queried, or `None` if it isn't.
+    palette: Option<Palette>,
+    /// Whether the clipboard has been queried and the query hasn't finished.
+    awaiting_clipboard: bool,
+    /// The reported contents of the clipboard.
+    clipboard: Option<String>,
+    /// Whether the device attributes have been queried to find out when the terminal has
+    /// finished responding to the queries before them, and not yet reported.
+    awaiting_device_attributes: bool,
+}
+
+impl State {
+    fn push(&mut self, event: io::Result<TerminalEvent>) {
+        self.events.push_back(event);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// A thread that reads and parses input from the terminal in the background.
+#[derive(Debug)]
+struct Reader {
+    shared: Arc<Shared>,
+    /// The read and write ends of the pipe used to wake the thread, either due to a resize or to
+    /// stop it.
+    wake: (RawFd, RawFd),
+    sigwinch: Option<sys::SigwinchHandler>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Reader {
+    fn spawn(tty: RawFd) -> io::Result<Self> {
+        let wake = sys::pipe()?;
+        let sigwinch = sys::SigwinchHandler::install(wake.1)?;
+
+        let shared = Arc::new(Shared::default());
+        let thread_shared = Arc::clone(&shared);
+        let thread = thread::spawn(move || {
+            if let Err(e) = read_input(tty, wake.0, &thread_shared) {
+                thread_shared.state.lock().unwrap().push(Err(e));
+            }
+        });
+
+        Ok(Self {
+            shared,
+            wake,
+            sigwinch: Some(sigwinch),
+            thread: Some(thread),
+        })
+    }
+
+    fn stop(&mut self) -> io::Result<()> {
+        if let Some(sigwinch) = self.sigwinch.take() {
+            sigwinch.uninstall()?;
+        }
+        if let Some(thread) = self.thread.take() {
+            sys::write_byte(self.wake.1, STOP_BYTE)?;
+            let _ = thread.join();
+            sys::close(self.wake.0)?;
+            sys::close(self.wake.1)?;
+        }
+        Ok(())
+    }
+}
+
+/// The body of the reading thread.
+fn read_input(tty: RawFd, wake: RawFd, shared: &Shared) -> io::Result<()> {
+    let mut parser = parse::Parser::default();
+    let mut buf = [0; 1024];
+
+    loop {
+        let timeout = if parser.is_pending() {
+            Some(ESCAPE_TIMEOUT)
+        } else {
+            None
+        };
+        let (tty_ready, wake_ready) = sys::poll(tty, wake, timeout)?;
+
+        if !tty_ready && !wake_ready {
+            let mut state = shared.state.lock().unwrap();
+            parser.timeout(|event| shared.receive(&mut state, event));
+        }
+
+        if wake_ready {
+            sys::read(wake, &mut buf[..1])?;
+            match buf[0] {
+                STOP_BYTE => return Ok(()),
+                sys::SIGWINCH_BYTE => {
+                    let size = sys::size(tty)?;
+                    let event = TerminalEvent::Resize(size);
+                    shared.state.lock().unwrap().push(Ok(event));
+                }
+                _ => {}
+            }
+        }
+
+        if tty_ready {
+            let n = sys::read(tty, &mut buf)?;
+            if n == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let mut state = shared.state.lock().unwrap();
+            parser.advance(&buf[..n], |event| shared.receive(&mut state, event));
+        }
+    }
+}
@@ -0,0 +1,847 @@
+//! Parsing of the bytes the terminal sends as input.
+
+use std::str;
+
+use crate::input::{Key, KeyKind, KeyPress, Modifiers, MouseButton};
+use crate::{Rgb, Vec2};
+
+use super::super::escape;
+use super::super::{TerminalEvent, TerminalMouse, TerminalMouseKind};
+
+/// The sequence sent before pasted text when bracketed paste is enabled.
+const PASTE_START: &[u8] = b"\x1B[200~";
+/// The sequence sent after pasted text when bracketed paste is enabled.
+const PASTE_END: &[u8] = b"\x1B[201~";
+
+/// An event parsed from the terminal's input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Event {
+    /// An event to give to the user.
+    Terminal(TerminalEvent),
+    /// A report of the zero-indexed cursor position, sent in response to a query.
+    CursorPos(Vec2<u16>),
+    /// A report of one of the terminal's colors, sent in response to a query.
+    Color(PaletteColor, Rgb),
+    /// The contents of the clipboard, sent in response to a query.
+    Clipboard(String),
+    /// A report of the terminal's primary device attributes, sent in response to a query.
+    DeviceAttributes,
+}
+
+/// One of the colors of the terminal's palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum PaletteColor {
+    /// The default foreground color.
+    Foreground,
+    /// The default background color.
+    Background,
+    /// One of the 16 named colors, by its ANSI value.
+    Ansi(u8),
+}
+
+impl Event {
+    /// Interpret the event as if no query was made.
+    ///
+    /// A cursor position report on the first row is indistinguishable from F3 pressed with
+    /// modifiers, so it is treated as that.
+    pub(super) fn into_terminal_event(self) -> Option<TerminalEvent> {
+        match self {
+            Self::Terminal(event) => Some(event),
+            Self::CursorPos(Vec2 { x, y: 0 }) => Some(TerminalEvent::Key(KeyPress::new(
+                Key::F(3),
+                parse_modifiers(u32::from(x) + 1),
+            ))),
+            Self::CursorPos(_) | Self::Color(..) | Self::Clipboard(_) | Self::DeviceAttributes => {
+                None
+            }
+        }
+    }
+}
+
+/// An incremental parser of terminal input.
+#[derive(Debug, Default)]
+pub(super) struct Parser {
+    /// Bytes that have been received but not yet parsed because they form an incomplete sequence.
+    buffer: Vec<u8>,
+}
+
+impl Parser {
+    /// Feed the parser some bytes, calling the callback for each event parsed.
+    ///
+    /// Incomplete sequences at the end of the bytes are kept until more bytes arrive or
+    /// [`timeout`](Self::timeout) is called.
+    pub(super) fn advance(&mut self, bytes: &[u8], f: impl FnMut(Event)) {
+        self.buffer.extend_from_slice(bytes);
+        self.parse_buffer(false, f);
+    }
+
+    /// Whether the parser holds an incomplete sequence.
+    pub(super) fn is_pending(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    /// Inform the parser that no more bytes have arrived in a while, so the sequence it holds is
+    /// complete. This is what distinguishes a press of the escape key from the start of an escape
+    /// sequence.
+    ///
+    /// Unfinished pastes are kept, since large pastes can arrive slowly.
+    pub(super) fn timeout(&mut self, f: impl FnMut(Event)) {
+        self.parse_buffer(true, f);
+        if !self.buffer.starts_with(PASTE_START) {
+            self.buffer.clear();
+        }
+    }
+
+    fn parse_buffer(&mut self, complete: bool, mut f: impl FnMut(Event)) {
+        let mut start = 0;
+        while start < self.buffer.len() {
+            match parse(&self.buffer[start..], complete) {
+                Parsed::Event(event, len) => {
+                    f(event);
+                    start += len;
+                }
+                Parsed::Skip(len) => start += len,
+                Parsed::Incomplete => break,
+            }
+        }
+        self.buffer.drain(..start);
+    }
+}
+
+/// The result of parsing a sequence of bytes.
+#[derive(Debug, PartialEq, Eq)]
+enum Parsed {
+    /// An event was parsed, taking up the given number of bytes.
+    Event(Event, usize),
+    /// The given number of bytes were not understood and should be skipped.
+    Skip(usize),
+    /// More bytes are needed to parse the event.
+    Incomplete,
+}
+
+impl Parsed {
+    /// Add a number of bytes to the length of the parsed sequence.
+    fn offset(self, by: usize) -> Self {
+        match self {
+            Self::Event(event, len) => Self::Event(event, len + by),
+            Self::Skip(len) => Self::Skip(len + by),
+            Self::Incomplete => Self::Incomplete,
+        }
+    }
+
+    /// Add modifiers to the parsed event if it is a key press.
+    fn with_modifiers(self, modifiers: Modifiers) -> Self {
+        match self {
+            Self::Event(Event::Terminal(TerminalEvent::Key(mut press)), len) => {
+                press.modifiers |= modifiers;
+                Self::Event(Event::Terminal(TerminalEvent::Key(press)), len)
+            }
+            other => other,
+        }
+    }
+}
+
+/// Parse a single event from the start of the bytes. If `complete` is set, the bytes will not be
+/// followed by any more.
+fn parse(bytes: &[u8], complete: bool) -> Parsed {
+    match bytes {
+        [0x1B] if complete => key(Key::Escape, Modifiers::default(), 1),
+        [] | [0x1B] => Parsed::Incomplete,
+        _ if bytes.starts_with(PASTE_START) => {
+            parse_paste(&bytes[PASTE_START.len()..]).offset(PASTE_START.len())
+        }
+        // Alt and the key that starts a sequence, when nothing follows it.
+        [0x1B, b'[' | b']' | b'O'] if complete => parse_char(&bytes[1..], complete)
+            .with_modifiers(Modifiers::ALT)
+            .offset(1),
+        [0x1B, b'[', rest @ ..] => match parse_csi(rest) {
+            Parsed::Incomplete if complete => Parsed::Skip(bytes.len()),
+            parsed => parsed.offset(2),
+        },
+        [0x1B, b']', rest @ ..] => match parse_osc(rest) {
+            Parsed::Incomplete if complete => Parsed::Skip(bytes.len()),
+            parsed => parsed.offset(2),
+        },
+        [0x1B, b'O', rest @ ..] => match parse_ss3(rest) {
+            Parsed::Incomplete if complete => Parsed::Skip(bytes.len()),
+            parsed => parsed.offset(2),
+        },
+        [0x1B, 0x1B, ..] => key(Key::Escape, Modifiers::default(), 1),
+        [0x1B, rest @ ..] => parse_char(rest, complete)
+            .with_modifiers(Modifiers::ALT)
+            .offset(1),
+        _ => parse_char(bytes, complete),
+    }
+}
+
+fn key(key: Key, modifiers: Modifiers, len: usize) -> Parsed {
+    Parsed::Event(
+        Event::Terminal(TerminalEvent::Key(KeyPress::new(key, modifiers))),
+        len,
+    )
+}
+
+/// Parse a single character, which may be a control character.
+fn parse_char(bytes: &[u8], complete: bool) -> Parsed {
+    let first = bytes[0];
+
+    match first {
+        b'\r' | b'\n' => return key(Key::Enter, Modifiers::default(), 1),
+        b'\t' => return key(Key::Tab, Modifiers::default(), 1),
+        0x7F => return key(Key::Backspace, Modifiers::default(), 1),
+        0x00 => return key(Key::Char('\0'), Modifiers::default(), 1),
+        0x01..=0x1A => return key(Key::Char((first - 1 + b'a') as char), Modifiers::CONTROL, 1),
+        0x1C..=0x1F => {
+            return key(
+                Key::Char((first - 0x1C + b'4') as char),
+                Modifiers::CONTROL,
+                1,
+            )
+        }
+        _ => {}
+    }
+
+    let len = match first {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => return Parsed::Skip(1),
+    };
+    if bytes.len() < len {
+        return if complete {
+            Parsed::Skip(bytes.len())
+        } else {
+            Parsed::Incomplete
+        };
+    }
+
+    match str::from_utf8(&bytes[..len]) {
+        Ok(s) => key(Key::Char(s.chars().next().unwrap()), Modifiers::NONE, len),
+        Err(_) => Parsed::Skip(1),
+    }
+}
+
+/// Parse the modifiers parameter used by xterm-style key sequences and the kitty keyboard
+/// protocol. The caps lock and num lock bits are ignored.
+fn parse_modifiers(param: u32) -> Modifiers {
+    let bits = param.saturating_sub(1);
+    Modifiers {
+        shift: bits & 1 != 0,
+        alt: bits & 2 != 0,
+        control: bits & 4 != 0,
+        super_key: bits & 8 != 0,
+        hyper: bits & 16 != 0,
+        meta: bits & 32 != 0,
+    }
+}
+
+/// Parse the event type sub-parameter of the kitty keyboard protocol.
+fn parse_key_kind(param: u32) -> KeyKind {
+    match param {
+        2 => KeyKind::Repeat,
+        3 => KeyKind::Release,
+        _ => KeyKind::Press,
+    }
+}
+
+/// Get the key of a key code of the kitty keyboard protocol, and whether it is on the keypad.
+///
+/// Keys that can't be represented, such as lock, modifier and media keys, give `None`.
+fn kitty_key(code: u32) -> Option<(Key, bool)> {
+    let keypad_char = |c| Some((Key::Char(c), true));
+    let key = match code {
+        9 => Key::Tab,
+        13 => Key::Enter,
+        27 => Key::Escape,
+        127 => Key::Backspace,
+        // F13 to F35.
+        57376..=57398 => Key::F((code - 57376 + 13) as u8),
+        57399..=57408 => return keypad_char(char::from(b'0' + (code - 57399) as u8)),
+        57409 => return keypad_char('.'),
+        57410 => return keypad_char('/'),
+        57411 => return keypad_char('*'),
+        57412 => return keypad_char('-'),
+        57413 => return keypad_char('+'),
+        57414 => return Some((Key::Enter, true)),
+        57415 => return keypad_char('='),
+        57416 => return keypad_char(','),
+        57417..=57426 => {
+            let key = [
+                Key::Left,
+                Key::Right,
+                Key::Up,
+                Key::Down,
+                Key::PageUp,
+                Key::PageDown,
+                Key::Home,
+                Key::End,
+                Key::Insert,
+                Key::Delete,
+            ][(code - 57417) as usize];
+            return Some((key, true));
+        }
+        // The rest of the private use area is used for other functional keys.
+        0xE000..=0xF8FF => return None,
+        _ => Key::Char(char::from_u32(code)?),
+    };
+    Some((key, false))
+}
+
+/// Parse the part of an SS3 sequence after `ESC O`.
+fn parse_ss3(bytes: &[u8]) -> Parsed {
+    let key = match bytes.first() {
+        None => return Parsed::Incomplete,
+        Some(b'A') => Key::Up,
+        Some(b'B') => Key::Down,
+        Some(b'C') => Key::Right,
+        Some(b'D') => Key::Left,
+        Some(b'H') => Key::Home,
+        Some(b'F') => Key::End,
+        Some(b'P') => Key::F(1),
+        Some(b'Q') => Key::F(2),
+        Some(b'R') => Key::F(3),
+        Some(b'S') => Key::F(4),
+        Some(_) => return Parsed::Skip(1),
+    };
+    self::key(key, Modifiers::default(), 1)
+}
+
+/// Parse the part of a CSI sequence after `ESC [`.
+fn parse_csi(bytes: &[u8]) -> Parsed {
+    match bytes {
+        [] => return Parsed::Incomplete,
+        [b'<', rest @ ..] => return parse_sgr_mouse(rest).offset(1),
+        [b'M', rest @ ..] => return parse_normal_mouse(rest).offset(1),
+        _ => {}
+    }
+
+    // Find the final byte of the sequence.
+    let end = match bytes.iter().position(|&b| !(0x20..=0x3F).contains(&b)) {
+        Some(end) => end,
+        None => return Parsed::Incomplete,
+    };
+    let len = end + 1;
+    if !(0x40..=0x7E).contains(&bytes[end]) {
+        return Parsed::Skip(len);
+    }
+
+    if let (b'?', b'c') = (bytes[0], bytes[end]) {
+        return Parsed::Event(Event::DeviceAttributes, len);
+    }
+
+    if bytes[end] == b'R' {
+        if let Some(&[row, column]) = parse_params(&bytes[..end]).as_deref() {
+            let pos = Vec2::new(column.saturating_sub(1), row.saturating_sub(1));
+            return Parsed::Event(Event::CursorPos(pos), len);
+        }
+    }
+
+    let params = match parse_key_params(&bytes[..end]) {
+        Some(params) => params,
+        None => return Parsed::Skip(len),
+    };
+    let param = |i: usize, j: usize| {
+        let param = params.get(i).and_then(|param| param.get(j)).copied();
+        param.filter(|&param| param != 0)
+    };
+
+    let modifiers = parse_modifiers(param(1, 0).unwrap_or(1));
+    let kind = parse_key_kind(param(1, 1).unwrap_or(1));
+
+    let key = match bytes[end] {
+        b'A' => Key::Up,
+        b'B' => Key::Down,
+        b'C' => Key::Right,
+        b'D' => Key::Left,
+        b'H' => Key::Home,
+        b'F' => Key::End,
+        b'P' => Key::F(1),
+        b'Q' => Key::F(2),
+        b'R' => Key::F(3),
+        b'S' => Key::F(4),
+        b'Z' => return key(Key::Tab, Modifiers::SHIFT, len),
+        b'I' => return Parsed::Event(Event::Terminal(TerminalEvent::Focus(true)), len),
+        b'O' => return Parsed::Event(Event::Terminal(TerminalEvent::Focus(false)), len),
+        b'u' => {
+            let (key, keypad) = match param(0, 0).and_then(kitty_key) {
+                Some(key) => key,
+                None => return Parsed::Skip(len),
+            };
+            // Use the shifted key when there is one, so that the character is the one typed like
+            // in legacy input. Shift stays in the modifiers as the terminal reported it.
+            let key = match param(0, 1).and_then(char::from_u32) {
+                Some(c) if modifiers.shift && !keypad => Key::Char(c),
+                _ => key,
+            };
+            let press = KeyPress {
+                key,
+                modifiers,
+                kind,
+                keypad,
+            };
+            return Parsed::Event(Event::Terminal(TerminalEvent::Key(press)), len);
+        }
+        b'~' => match param(0, 0) {
+            Some(1 | 7) => Key::Home,
+            Some(2) => Key::Insert,
+            Some(3) => Key::Delete,
+            Some(4 | 8) => Key::End,
+            Some(5) => Key::PageUp,
+            Some(6) => Key::PageDown,
+            Some(n @ 11..=15) => Key::F((n - 10) as u8),
+            Some(n @ 17..=21) => Key::F((n - 11) as u8),
+            Some(n @ 23..=26) => Key::F((n - 12) as u8),
+            Some(n @ 28..=29) => Key::F((n - 13) as u8),
+            Some(n @ 31..=34) => Key::F((n - 14) as u8),
+            _ => return Parsed::Skip(len),
+        },
+        _ => return Parsed::Skip(len),
+    };
+    let press = KeyPress {
+        kind,
+        ..KeyPress::new(key, modifiers)
+    };
+    Parsed::Event(Event::Terminal(TerminalEvent::Key(press)), len)
+}
+
+/// Parse an operating system command after `ESC ]`, up to and including the BEL or `ESC \` that
+/// ends it. Only reports of the terminal's colors and clipboard are understood.
+fn parse_osc(bytes: &[u8]) -> Parsed {
+    let (end, len) = match bytes.iter().position(|&b| b == 0x07 || b == 0x1B) {
+        Some(end) if bytes[end] == 0x07 => (end, end + 1),
+        Some(end) => match bytes.get(end + 1) {
+            Some(b'\\') => (end, end + 2),
+            // The command was cut off by another escape sequence.
+            Some(_) => return Parsed::Skip(end),
+            None => return Parsed::Incomplete,
+        },
+        None => return Parsed::Incomplete,
+    };
+
+    let params: Vec<&[u8]> = bytes[..end].split(|&b| b == b';').collect();
+    let (color, spec) = match params.as_slice() {
+        [b"10", spec] => (PaletteColor::Foreground, spec),
+        [b"11", spec] => (PaletteColor::Background, spec),
+        [b"4", index, spec] => match str::from_utf8(index).ok().and_then(|i| i.parse().ok()) {
+            Some(index @ 0..=15) => (PaletteColor::Ansi(index), spec),
+            _ => return Parsed::Skip(len),
+        },
+        [b"52", _, data] => {
+            return match escape::decode_base64(data).and_then(|data| String::from_utf8(data).ok()) {
+                Some(text) => Parsed::Event(Event::Clipboard(text), len),
+                None => Parsed::Skip(len),
+            };
+        }
+        _ => return Parsed::Skip(len),
+    };
+
+    match parse_rgb(spec) {
+        Some(rgb) => Parsed::Event(Event::Color(color, rgb), len),
+        None => Parsed::Skip(len),
+    }
+}
+
+/// Parse an X11 color specification of the form `rgb:R/G/B`, where each component is 1-4
+/// hexadecimal digits.
+fn parse_rgb(spec: &[u8]) -> Option<Rgb> {
+    let spec = str::from_utf8(spec.strip_prefix(b"rgb:")?).ok()?;
+    let mut components = spec.split('/').map(|component| {
+        if component.is_empty() || component.len() > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(component, 16).ok()?;
+        let max = (1 << (4 * component.len())) - 1;
+        Some(((value * 255 + max / 2) / max) as u8)
+    });
+    let rgb = Rgb::new(
+        components.next()??,
+        components.next()??,
+        components.next()??,
+    );
+    match components.next() {
+        Some(_) => None,
+        None => Some(rgb),
+    }
+}
+
+/// Parse the text of a bracketed paste up to and including the sequence that ends it.
+fn parse_paste(bytes: &[u8]) -> Parsed {
+    match bytes
+        .windows(PASTE_END.len())
+        .position(|window| window == PASTE_END)
+    {
+        Some(end) => {
+            let text = String::from_utf8_lossy(&bytes[..end])
+                .replace("\r\n", "\n")
+                .replace('\r', "\n");
+            Parsed::Event(
+                Event::Terminal(TerminalEvent::Paste(text)),
+                end + PASTE_END.len(),
+            )
+        }
+        None => Parsed::Incomplete,
+    }
+}
+
+/// Parse semicolon-separated numerical parameters. Empty parameters are treated as 1.
+fn parse_params(bytes: &[u8]) -> Option<Vec<u16>> {
+    if bytes.is_empty() {
+        return Some(Vec::new());
+    }
+    bytes
+        .split(|&b| b == b';')
+        .map(|param| {
+            if param.is_empty() {
+                Some(1)
+            } else {
+                str::from_utf8(param).ok()?.parse().ok()
+            }
+        })
+        .collect()
+}
+
+/// Parse the parameters of a key sequence, each of which can have sub-parameters separated by
+/// colons, as in the kitty keyboard protocol. Empty sub-parameters are 0.
+fn parse_key_params(bytes: &[u8]) -> Option<Vec<Vec<u32>>> {
+    bytes
+        .split(|&b| b == b';')
+        .map(|param| {
+            param
+                .split(|&b| b == b':')
+                .map(|sub_param| {
+                    if sub_param.is_empty() {
+                        Some(0)
+                    } else {
+                        str::from_utf8(sub_param).ok()?.parse().ok()
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Parse an SGR mouse sequence after `ESC [ <`.
+fn parse_sgr_mouse(bytes: &[u8]) -> Parsed {
+    let end = match bytes.iter().position(|&b| b == b'M' || b == b'm') {
+        Some(end) => end,
+        None if bytes.iter().all(|&b| b.is_ascii_digit() || b == b';') => {
+            return Parsed::Incomplete
+        }
+        None => return Parsed::Skip(bytes.len()),
+    };
+    let len = end + 1;
+
+    match parse_params(&bytes[..end]).as_deref() {
+        Some(&[code, x, y]) => match mouse(
+            code,
+            x.saturating_sub(1),
+            y.saturating_sub(1),
+            bytes[end] == b'm',
+        ) {
+            Some(mouse) => Parsed::Event(Event::Terminal(TerminalEvent::Mouse(mouse)), len),
+            None => Parsed::Skip(len),
+        },
+        _ => Parsed::Skip(len),
+    }
+}
+
+/// Parse a normal (X10-compatible) mouse sequence after `ESC [ M`.
+fn parse_normal_mouse(bytes: &[u8]) -> Parsed {
+    match *bytes {
+        [code, x, y, ..] => {
+            let code = u16::from(code.saturating_sub(32));
+            let x = u16::from(x.saturating_sub(33));
+            let y = u16::from(y.saturating_sub(33));
+            match mouse(code, x, y, false) {
+                Some(mouse) => Parsed::Event(Event::Terminal(TerminalEvent::Mouse(mouse)), 3),
+                None => Parsed::Skip(3),
+            }
+        }
+        _ => Parsed::Incomplete,
+    }
+}
+
+/// Decode a mouse event from its button code and zero-indexed position.
+fn mouse(code: u16, x: u16, y: u16, release: bool) -> Option<TerminalMouse> {
+    let button = match code & 0b11 {
+        0 => Some(MouseButton::Left),
+        1 => Some(MouseButton::Middle),
+        2 => Some(MouseButton::Right),
+        _ => None,
+    };
+
+    let kind = if code & 64 != 0 {
+        match code & 0b11 {
+            0 => TerminalMouseKind::ScrollUp,
+            1 => TerminalMouseKind::ScrollDown,
+            _ => return None,
+        }
+    } else if code & 32 != 0 {
+        TerminalMouseKind::Move
+    } else if release {
+        TerminalMouseKind::Release
+    } else {
+        button.map_or(TerminalMouseKind::Release, TerminalMouseKind::Press)
+    };
+
+    Some(TerminalMouse {
+        kind,
+        at: Vec2::new(x, y),
+        modifiers: Modifiers {
+            shift: code & 4 != 0,
+            alt: code & 8 != 0,
+            control: code & 16 != 0,
+            ..Modifiers::NONE
+        },
+    })
+}
+
+#[cfg(test)]
+fn parse_all(bytes: &[u8]) -> Vec<Event> {
+    let mut parser = Parser::default();
+    let mut events = Vec::new();
+    parser.advance(bytes, |event| events.push(event));
+    parser.timeout(|event| events.push(event));
+    events
+}
+
+#[test]
+fn test_parse_keys() {
+    let press = |key, modifiers| Event::Terminal(TerminalEvent::Key(KeyPress::new(key, modifiers)));
+    let none = Modifiers::default();
+
+    assert_eq!(
+        parse_all(b"aB\r\t\x7F"),
+        [
+            press(Key::Char('a'), none),
+            press(Key::Char('B'), none),
+            press(Key::Enter, none),
+            press(Key::Tab, none),
+            press(Key::Backspace, none),
+        ]
+    );
+    assert_eq!(
+        parse_all("é😊".as_bytes()),
+        [press(Key::Char('é'), none), press(Key::Char('😊'), none)]
+    );
+    assert_eq!(
+        parse_all(b"\x03\x1Bx\x1B"),
+        [
+            press(Key::Char('c'), Modifiers::CONTROL),
+            press(Key::Char('x'), Modifiers::ALT),
+            press(Key::Escape, none),
+        ]
+    );
+    assert_eq!(
+        parse_all(b"\x1B[A\x1B[1;5D\x1BOP\x1B[15~\x1B[3;2~\x1B[Z"),
+        [
+            press(Key::Up, none),
+            press(Key::Left, Modifiers::CONTROL),
+            press(Key::F(1), none),
+            press(Key::F(5), none),
+            press(Key::Delete, Modifiers::SHIFT),
+            press(Key::Tab, Modifiers::SHIFT),
+        ]
+    );
+    assert_eq!(parse_all(b"\x1B["), [press(Key::Char('['), Modifiers::ALT)]);
+    assert_eq!(parse_all(b"\x1BO"), [press(Key::Char('O'), Modifiers::ALT)]);
+}
+
+#[test]
+fn test_parse_kitty_keys() {
+    let key = |key, modifiers, kind, keypad| {
+        Event::Terminal(TerminalEvent::Key(KeyPress {
+            key,
+            modifiers,
+            kind,
+            keypad,
+        }))
+    };
+    let none = Modifiers::default();
+
+    assert_eq!(
+        parse_all(b"\x1B[27u\x1B[105;5u\x1B[9u\x1B[97;1:3u\x1B[97:65;2:2u\x1B[49:33;2u"),
+        [
+            key(Key::Escape, none, KeyKind::Press, false),
+            key(Key::Char('i'), Modifiers::CONTROL, KeyKind::Press, false),
+            key(Key::Tab, none, KeyKind::Press, false),
+            key(Key::Char('a'), none, KeyKind::Release, false),
+            key(Key::Char('A'), Modifiers::SHIFT, KeyKind::Repeat, false),
+            key(Key::Char('!'), Modifiers::SHIFT, KeyKind::Press, false),
+        ]
+    );
+    assert_eq!(
+        parse_all(b"\x1B[57400u\x1B[57414;1:3u\x1B[57419u\x1B[57441u\x1B[57376;9u"),
+        [
+            key(Key::Char('1'), none, KeyKind::Press, true),
+            key(Key::Enter, none, KeyKind::Release, true),
+            key(Key::Up, none, KeyKind::Press, true),
+            key(Key::F(13), Modifiers::SUPER, KeyKind::Press, false),
+        ]
+    );
+    assert_eq!(
+        parse_all(b"\x1B[1;1:3A\x1B[5;33:2~\x1B[?15u\x1B[100::68;17u"),
+        [
+            key(Key::Up, none, KeyKind::Release, false),
+            key(Key::PageUp, Modifiers::META, KeyKind::Repeat, false),
+            key(Key::Char('d'), Modifiers::HYPER, KeyKind::Press, false),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_mouse() {
+    let mouse = |kind, x, y, modifiers| {
+        Event::Terminal(TerminalEvent::Mouse(TerminalMouse {
+            kind,
+            at: Vec2::new(x, y),
+            modifiers,
+        }))
+    };
+    let none = Modifiers::default();
+
+    assert_eq!(
+        parse_all(b"\x1B[<0;3;4M\x1B[<32;5;4M\x1B[<0;5;4m\x1B[<65;1;1M\x1B[<18;2;2M"),
+        [
+            mouse(TerminalMouseKind::Press(MouseButton::Left), 2, 3, none),
+            mouse(TerminalMouseKind::Move, 4, 3, none),
+            mouse(TerminalMouseKind::Release, 4, 3, none),
+            mouse(TerminalMouseKind::ScrollDown, 0, 0, none),
+            mouse(
+                TerminalMouseKind::Press(MouseButton::Right),
+                1,
+                1,
+                Modifiers::CONTROL
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_incremental() {
+    let mut parser = Parser::default();
+    let mut events = Vec::new();
+
+    parser.advance(b"\x1B[1;", |event| events.push(event));
+    assert!(events.is_empty());
+    assert!(parser.is_pending());
+
+    parser.advance(b"3C\xF0\x9F", |event| events.push(event));
+    assert_eq!(
+        events,
+        [Event::Terminal(TerminalEvent::Key(KeyPress::new(
+            Key::Right,
+            Modifiers::ALT,
+        )))]
+    );
+    assert!(parser.is_pending());
+
+    parser.advance(b"\x98\x8A", |event| events.push(event));
+    assert_eq!(events.len(), 2);
+    assert!(!parser.is_pending());
+}
+
+#[test]
+fn test_parse_clipboard() {
+    assert_eq!(
+        parse_all(b"\x1B]52;c;aGVsbG8gd29ybGQ=\x07\x1B]52;c;\x1B\\\x1B]52;c;!!\x07"),
+        [
+            Event::Clipboard("hello world".to_owned()),
+            Event::Clipboard(String::new()),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_focus() {
+    assert_eq!(
+        parse_all(b"\x1B[I\x1B[O"),
+        [
+            Event::Terminal(TerminalEvent::Focus(true)),
+            Event::Terminal(TerminalEvent::Focus(false)),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_paste() {
+    let paste = |text: &str| Event::Terminal(TerminalEvent::Paste(text.to_owned()));
+
+    assert_eq!(
+        parse_all(b"a\x1B[200~q\x1B[A\r\nb\rc\x1B[201~d"),
+        [
+            Event::Terminal(TerminalEvent::Key(KeyPress::from('a'))),
+            paste("q\x1B[A\nb\nc"),
+            Event::Terminal(TerminalEvent::Key(KeyPress::from('d'))),
+        ]
+    );
+
+    // Pastes are kept across timeouts until they end.
+    let mut parser = Parser::default();
+    let mut events = Vec::new();
+    parser.advance(b"\x1B[200~hello", |event| events.push(event));
+    parser.timeout(|event| events.push(event));
+    assert!(events.is_empty());
+    assert!(parser.is_pending());
+    parser.advance(b" world\x1B[201~", |event| events.push(event));
+    assert_eq!(events, [paste("hello world")]);
+    assert!(!parser.is_pending());
+}
+
+#[test]
+fn test_parse_cursor_pos() {
+    assert_eq!(
+        parse_all(b"\x1B[5;12R\x1B[1;5R"),
+        [
+            Event::CursorPos(Vec2::new(11, 4)),
+            Event::CursorPos(Vec2::new(4, 0)),
+        ]
+    );
+    assert_eq!(
+        Event::CursorPos(Vec2::new(4, 0)).into_terminal_event(),
+        Some(TerminalEvent::Key(KeyPress::new(
+            Key::F(3),
+            Modifiers::CONTROL,
+        )))
+    );
+    assert_eq!(
+        Event::CursorPos(Vec2::new(11, 4)).into_terminal_event(),
+        None
+    );
+}
+
+#[test]
+fn test_parse_colors() {
+    assert_eq!(
+        parse_all(
+            b"\x1B]10;rgb:ffff/ffff/ffff\x1B\\\x1B]11;rgb:1e1e/2020/2a2a\x07\x1B]4;9;rgb:f/8/0\x1B\\"
+        ),
+        [
+            Event::Color(PaletteColor::Foreground, Rgb::new(255, 255, 255)),
+            Event::Color(PaletteColor::Background, Rgb::new(30, 32, 42)),
+            Event::Color(PaletteColor::Ansi(9), Rgb::new(255, 136, 0)),
+        ]
+    );
+    assert_eq!(
+        parse_all(b"\x1B[?62;22c\x1B]4;16;rgb:0/0/0\x07\x1B]0;title\x07a"),
+        [
+            Event::DeviceAttributes,
+            Event::Terminal(TerminalEvent::Key(KeyPress::new(
+                Key::Char('a'),
+                Modifiers::default(),
+            ))),
+        ]
+    );
+
+    let mut parser = Parser::default();
+    let mut events = Vec::new();
+    parser.advance(b"\x1B]11;rgb:0000/0000/", |event| events.push(event));
+    assert!(events.is_empty());
+    parser.advance(b"0000\x1B\\", |event| events.push(event));
+    assert_eq!(
+        events,
+        [Event::Color(PaletteColor::Background, Rgb::new(0, 0, 0))]
+    );
+    assert!(!parser.is_pending());
+}
@@ -0,0 +1,186 @@
+//! Thin wrappers around the Unix system calls used by the ANSI backend.
+
+use std::env;
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::unix::io::RawFd;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use crate::Vec2;
+
+/// Converts the return value of a system call into an [`io::Result`].
+fn check<T: PartialEq + From<i8>>(ret: T) -> io::Result<T> {
+    if ret == T::from(-1) {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+/// The terminal settings from before raw mode was enabled.
+pub(super) struct RawMode {
+    fd: RawFd,
+    original: libc::termios,
+}
+
+impl RawMode {
+    /// Put the terminal into raw mode.
+    pub(super) fn enable(fd: RawFd) -> io::Result<Self> {
+        let mut termios = MaybeUninit::uninit();
+        check(unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) })?;
+        let original = unsafe { termios.assume_init() };
+
+        let mut raw = original;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        check(unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) })?;
+
+        Ok(Self { fd, original })
+    }
+
+    /// Restore the terminal settings.
+    pub(super) fn disable(self) -> io::Result<()> {
+        check(unsafe { libc::tcsetattr(self.fd, libc::TCSANOW, &self.original) })?;
+        Ok(())
+    }
+}
+
+impl Debug for RawMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawMode")
+            .field("fd", &self.fd)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Get the size of the terminal.
+///
+/// Serial consoles and some pseudoterminals report a size of 0, in which case `$COLUMNS` and
+/// `$LINES` are used, or 80x24 if they aren't set.
+pub(super) fn size(fd: RawFd) -> io::Result<Vec2<u16>> {
+    let mut size = MaybeUninit::<libc::winsize>::uninit();
+    check(unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, size.as_mut_ptr()) })?;
+    let size = unsafe { size.assume_init() };
+
+    let or_var = |value: u16, var: &str, default: u16| match value {
+        0 => env::var(var)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .filter(|&value| value != 0)
+            .unwrap_or(default),
+        value => value,
+    };
+    Ok(Vec2::new(
+        or_var(size.ws_col, "COLUMNS", 80),
+        or_var(size.ws_row, "LINES", 24),
+    ))
+}
+
+/// Create a pipe, returning the read and write ends.
+pub(super) fn pipe() -> io::Result<(RawFd, RawFd)> {
+    let mut fds = [0; 2];
+    check(unsafe { libc::pipe(fds.as_mut_ptr()) })?;
+    Ok((fds[0], fds[1]))
+}
+
+/// Read from a file descriptor, retrying if interrupted.
+pub(super) fn read(fd: RawFd, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match check(unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) }) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            res => return res.map(|n| n as usize),
+        }
+    }
+}
+
+/// Write a single byte to a file descriptor.
+pub(super) fn write_byte(fd: RawFd, byte: u8) -> io::Result<()> {
+    let buf = [byte];
+    check(unsafe { libc::write(fd, buf.as_ptr().cast(), 1) })?;
+    Ok(())
+}
+
+/// Close a file descriptor.
+pub(super) fn close(fd: RawFd) -> io::Result<()> {
+    check(unsafe { libc::close(fd) })?;
+    Ok(())
+}
+
+/// Wait until either of the file descriptors can be read from, with an optional timeout in
+/// milliseconds. Returns whether each file descriptor is readable.
+pub(super) fn poll(first: RawFd, second: RawFd, timeout: Option<u16>) -> io::Result<(bool, bool)> {
+    let mut fds = [
+        libc::pollfd {
+            fd: first,
+            events: libc::POLLIN,
+            revents: 0,
+        },
+        libc::pollfd {
+            fd: second,
+            events: libc::POLLIN,
+            revents: 0,
+        },
+    ];
+    let timeout = timeout.map_or(-1, libc::c_int::from);
+
+    loop {
+        match check(unsafe { libc::poll(fds.as_mut_ptr(), 2, timeout) }) {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+            Ok(_) => break,
+        }
+    }
+
+    let readable = |fd: &libc::pollfd| fd.revents & (libc::POLLIN | libc::POLLHUP) != 0;
+    Ok((readable(&fds[0]), readable(&fds[1])))
+}
+
+/// The file descriptor that the SIGWINCH handler writes to.
+static SIGWINCH_PIPE: AtomicI32 = AtomicI32::new(-1);
+
+/// The byte written to the pipe when SIGWINCH is received.
+pub(super) const SIGWINCH_BYTE: u8 = b'w';
+
+extern "C" fn handle_sigwinch(_: libc::c_int) {
+    let fd = SIGWINCH_PIPE.load(Ordering::Relaxed);
+    if fd != -1 {
+        let _ = write_byte(fd, SIGWINCH_BYTE);
+    }
+}
+
+/// A SIGWINCH handler that writes [`SIGWINCH_BYTE`] to a pipe.
+pub(super) struct SigwinchHandler {
+    previous: libc::sigaction,
+}
+
+impl SigwinchHandler {
+    /// Install the handler.
+    pub(super) fn install(pipe: RawFd) -> io::Result<Self> {
+        SIGWINCH_PIPE.store(pipe, Ordering::Relaxed);
+
+        let mut action: libc::sigaction = unsafe { MaybeUninit::zeroed().assume_init() };
+        action.sa_sigaction = handle_sigwinch as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        check(unsafe { libc::sigemptyset(&mut action.sa_mask) })?;
+
+        let mut previous = MaybeUninit::uninit();
+        check(unsafe { libc::sigaction(libc::SIGWINCH, &action, previous.as_mut_ptr()) })?;
+
+        Ok(Self {
+            previous: unsafe { previous.assume_init() },
+        })
+    }
+
+    /// Restore the previous handler.
+    pub(super) fn uninstall(self) -> io::Result<()> {
+        check(unsafe { libc::sigaction(libc::SIGWINCH, &self.previous, std::ptr::null_mut()) })?;
+        SIGWINCH_PIPE.store(-1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl Debug for SigwinchHandler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigwinchHandler").finish_non_exhaustive()
+    }
+}
@@ -1,10 +1,13 @@
 use std::future::Future;
-use std::io::Write;
+use std::io::{self, Write};
+use std::mem;
+use std::ops::Range;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use crossterm::event::{
-    Event, EventStream, KeyCode, KeyModifiers, MouseButton as CMouseButton, MouseEvent,
+    Event, EventStream, KeyCode, KeyEventKind, KeyEventState, KeyModifiers,
+    MouseButton as CMouseButton, MouseEventKind,
 };
 use crossterm::style::{self, Attribute, Color as CColor};
 use crossterm::{cursor, event, terminal};
@@ -12,59 +15,215 @@ use crossterm::{execute, queue};
 use crossterm_crate as crossterm;
 use futures_core::stream::Stream as _;
 
-use crate::input::{Key, KeyPress, Modifiers, MouseButton};
-use crate::style::{Color, Intensity, Rgb};
-use crate::{CursorShape, Vec2};
+use crate::input::{Key, KeyKind, KeyPress, KeyboardEnhancements, Modifiers, MouseButton};
+use crate::style::{Color, Intensity, Link, Rgb, UnderlineStyle};
+use crate::{Capabilities, CursorShape, Vec2, WidthTable};
 
-use super::{Backend, ReadEvents, TerminalEvent, TerminalMouse, TerminalMouseKind, Tty};
+use super::{escape, Backend, ReadEvents, TerminalEvent, TerminalMouse, TerminalMouseKind, Tty};
 
 /// Crossterm backend.
 ///
 /// Currently there is no configuration here.
-///
-/// Crossterm supports all features except setting the cursor shape (see
-/// <https://github.com/crossterm-rs/crossterm/issues/427>).
 #[derive(Debug, Default, Clone)]
 #[non_exhaustive]
 pub struct Crossterm {}
 
 impl Backend for Crossterm {
-    type Error = crossterm::ErrorKind;
+    type Error = io::Error;
     type Bound = Bound;
 
     fn bind(self, mut io: Tty) -> Result<Self::Bound, Self::Error> {
-        terminal::enable_raw_mode()?;
-        execute!(
-            io,
-            terminal::EnterAlternateScreen,
-            terminal::Clear(terminal::ClearType::All),
-            terminal::DisableLineWrap,
-            event::EnableMouseCapture,
-        )?;
+        set_up(&mut io)?;
 
         Ok(Bound {
             io,
             stream: EventStream::new(),
+            cursor_shape: CursorShape::Block,
+            cursor_blinking: true,
+            underlined: false,
+            underline_style: UnderlineStyle::Straight,
+            alternate_screen: false,
+            keyboard_enhancements: KeyboardEnhancements::default(),
+            // Legacy Windows consoles don't understand escape sequences Crossterm doesn't
+            // translate.
+            repeat: cfg!(unix) && Capabilities::detect().repeat,
         })
     }
 }
 
+fn set_up(io: &mut Tty) -> io::Result<()> {
+    terminal::enable_raw_mode()?;
+    // Crossterm doesn't support the title stack.
+    io.write_all(escape::PUSH_TITLE.as_bytes())?;
+    execute!(
+        *io,
+        terminal::DisableLineWrap,
+        event::EnableMouseCapture,
+        event::EnableBracketedPaste,
+        event::EnableFocusChange,
+    )
+}
+
 #[derive(Debug)]
 pub struct Bound {
     io: Tty,
     stream: EventStream,
+    /// The shape of the cursor; DECSCUSR sets both the shape and blinking at once.
+    cursor_shape: CursorShape,
+    /// Whether the cursor blinks.
+    cursor_blinking: bool,
+    /// Whether the text is underlined; Crossterm sets the underline style with the same attribute.
+    underlined: bool,
+    /// The style of underlines.
+    underline_style: UnderlineStyle,
+    /// Whether the alternate screen has been entered.
+    alternate_screen: bool,
+    /// The keyboard enhancements, which are pushed onto the terminal's stack of them when they
+    /// aren't empty.
+    keyboard_enhancements: KeyboardEnhancements,
+    /// Whether the terminal supports REP and ECH.
+    repeat: bool,
+}
+
+impl Bound {
+    /// Set the attribute for whether the text is underlined and the underline style.
+    fn queue_underline(&mut self) -> io::Result<()> {
+        let attribute = match (self.underlined, self.underline_style) {
+            (false, _) => Attribute::NoUnderline,
+            (true, UnderlineStyle::Straight) => Attribute::Underlined,
+            (true, UnderlineStyle::Double) => Attribute::DoubleUnderlined,
+            (true, UnderlineStyle::Curly) => Attribute::Undercurled,
+            (true, UnderlineStyle::Dotted) => Attribute::Underdotted,
+            (true, UnderlineStyle::Dashed) => Attribute::Underdashed,
+        };
+        queue!(self.io, style::SetAttribute(attribute))
+    }
+
+    /// Push the keyboard enhancements onto the terminal's stack if there are any.
+    fn push_keyboard_enhancements(&mut self) -> io::Result<()> {
+        match self.keyboard_enhancements.flags() {
+            0 => Ok(()),
+            flags => queue!(
+                self.io,
+                event::PushKeyboardEnhancementFlags(
+                    event::KeyboardEnhancementFlags::from_bits_truncate(flags)
+                )
+            ),
+        }
+    }
+
+    /// Pop the keyboard enhancements off the terminal's stack if there are any.
+    fn pop_keyboard_enhancements(&mut self) -> io::Result<()> {
+        if self.keyboard_enhancements.flags() == 0 {
+            return Ok(());
+        }
+        queue!(self.io, event::PopKeyboardEnhancementFlags)
+    }
+
+    /// Restore the terminal's original state.
+    fn release(&mut self) -> io::Result<()> {
+        self.pop_keyboard_enhancements()?;
+        execute!(
+            self.io,
+            event::DisableMouseCapture,
+            event::DisableBracketedPaste,
+            event::DisableFocusChange,
+            terminal::EnableLineWrap,
+            cursor::Show,
+        )?;
+        // Crossterm doesn't support resetting the cursor shape or color.
+        self.io.write_all(b"\x1B[0 q")?;
+        self.io.write_all(escape::RESET_CURSOR_COLOR.as_bytes())?;
+        self.io.write_all(escape::POP_TITLE.as_bytes())?;
+        self.io.flush()?;
+        if self.alternate_screen {
+            execute!(self.io, terminal::LeaveAlternateScreen)?;
+            self.alternate_screen = false;
+        }
+        self.cursor_shape = CursorShape::Block;
+        self.cursor_blinking = true;
+        self.underlined = false;
+        self.underline_style = UnderlineStyle::Straight;
+        terminal::disable_raw_mode()
+    }
 }
 
 impl super::Bound for Bound {
-    type Error = crossterm::ErrorKind;
+    type Error = io::Error;
 
     // General functions
 
     fn size(&mut self) -> Result<Vec2<u16>, Self::Error> {
         terminal::size().map(Vec2::from)
     }
+    fn capabilities(&mut self) -> Result<Capabilities, Self::Error> {
+        let mut capabilities = Capabilities::detect();
+        capabilities.repeat = self.repeat;
+        Ok(capabilities)
+    }
     fn set_title(&mut self, title: &str) -> Result<(), Self::Error> {
-        queue!(self.io, terminal::SetTitle(title))
+        // Crossterm writes the title as is, which could end the sequence early.
+        if cfg!(unix) {
+            escape::set_title(&mut self.io, false, title)
+        } else {
+            queue!(self.io, terminal::SetTitle(title))
+        }
+    }
+    fn set_icon_name(&mut self, icon_name: &str) -> Result<(), Self::Error> {
+        // Crossterm doesn't support setting the icon name.
+        escape::set_title(&mut self.io, true, icon_name)
+    }
+    fn enter_alternate_screen(&mut self) -> Result<(), Self::Error> {
+        self.alternate_screen = true;
+        queue!(
+            self.io,
+            terminal::EnterAlternateScreen,
+            terminal::Clear(terminal::ClearType::All),
+        )
+    }
+    fn begin_synchronized_update(&mut self) -> Result<(), Self::Error> {
+        queue!(self.io, terminal::BeginSynchronizedUpdate)
+    }
+    fn end_synchronized_update(&mut self) -> Result<(), Self::Error> {
+        queue!(self.io, terminal::EndSynchronizedUpdate)
+    }
+    fn measure_widths(&mut self, probes: &[&str]) -> Result<WidthTable, Self::Error> {
+        let mut widths = WidthTable::new();
+        for probe in probes {
+            write!(self.io, "\r{probe}")?;
+            match self.cursor_pos() {
+                Ok(pos) => widths.insert(*probe, pos.x),
+                // Crossterm fails with `Other` if the terminal doesn't report the cursor position
+                // in time, so don't wait for it again.
+                Err(e) if e.kind() == io::ErrorKind::Other => break,
+                Err(e) => return Err(e),
+            }
+        }
+        queue!(
+            self.io,
+            cursor::MoveToColumn(0),
+            terminal::Clear(terminal::ClearType::CurrentLine)
+        )?;
+        Ok(widths)
+    }
+    fn bell(&mut self) -> Result<(), Self::Error> {
+        self.io.write_all(b"\x07")
+    }
+    fn notify(&mut self, title: &str, body: &str) -> Result<(), Self::Error> {
+        // Crossterm doesn't support notifications.
+        escape::notify(&mut self.io, title, body)
+    }
+    fn set_clipboard(&mut self, text: &str) -> Result<(), Self::Error> {
+        // Crossterm doesn't support the clipboard, and can't read the reply to a query of it.
+        escape::set_clipboard(&mut self.io, text)
+    }
+    fn set_keyboard_enhancements(
+        &mut self,
+        enhancements: KeyboardEnhancements,
+    ) -> Result<(), Self::Error> {
+        self.pop_keyboard_enhancements()?;
+        self.keyboard_enhancements = enhancements;
+        self.push_keyboard_enhancements()
     }
 
     // Cursor functions
@@ -75,20 +234,33 @@ impl super::Bound for Bound {
     fn show_cursor(&mut self) -> Result<(), Self::Error> {
         queue!(self.io, cursor::Show)
     }
-    fn set_cursor_shape(&mut self, _shape: CursorShape) -> Result<(), Self::Error> {
-        // BlockedTODO: https://github.com/crossterm-rs/crossterm/issues/427
-        Ok(())
+    fn set_cursor_shape(&mut self, shape: CursorShape) -> Result<(), Self::Error> {
+        self.cursor_shape = shape;
+        escape::set_cursor_style(&mut self.io, shape, self.cursor_blinking)
     }
     fn set_cursor_blinking(&mut self, blinking: bool) -> Result<(), Self::Error> {
-        if blinking {
-            queue!(self.io, cursor::EnableBlinking)
-        } else {
-            queue!(self.io, cursor::DisableBlinking)
-        }
+        self.cursor_blinking = blinking;
+        escape::set_cursor_style(&mut self.io, self.cursor_shape, blinking)
+    }
+    fn set_cursor_color(&mut self, color: Color) -> Result<(), Self::Error> {
+        // Crossterm doesn't support the cursor color.
+        escape::set_cursor_color(&mut self.io, color)
     }
     fn set_cursor_pos(&mut self, pos: Vec2<u16>) -> Result<(), Self::Error> {
         queue!(self.io, cursor::MoveTo(pos.x, pos.y))
     }
+    fn move_cursor(&mut self, from: Vec2<u16>, to: Vec2<u16>) -> Result<(), Self::Error> {
+        // Line feeds also return the cursor to the start of the line on Windows.
+        if cfg!(unix) {
+            escape::move_cursor(&mut self.io, from, to)
+        } else {
+            self.set_cursor_pos(to)
+        }
+    }
+    fn cursor_pos(&mut self) -> Result<Vec2<u16>, Self::Error> {
+        self.io.flush()?;
+        cursor::position().map(Vec2::from)
+    }
 
     // Style functions
     fn set_foreground(&mut self, foreground: Color) -> Result<(), Self::Error> {
@@ -124,14 +296,8 @@ impl super::Bound for Bound {
         )
     }
     fn set_underlined(&mut self, underlined: bool) -> Result<(), Self::Error> {
-        queue!(
-            self.io,
-            style::SetAttribute(if underlined {
-                Attribute::Underlined
-            } else {
-                Attribute::NoUnderline
-            })
-        )
+        self.underlined = underlined;
+        self.queue_underline()
     }
     fn set_blinking(&mut self, blinking: bool) -> Result<(), Self::Error> {
         queue!(
@@ -153,30 +319,113 @@ impl super::Bound for Bound {
             })
         )
     }
+    fn set_underline_style(&mut self, style: UnderlineStyle) -> Result<(), Self::Error> {
+        self.underline_style = style;
+        if self.underlined {
+            self.queue_underline()?;
+        }
+        Ok(())
+    }
+    fn set_underline_color(&mut self, color: Color) -> Result<(), Self::Error> {
+        queue!(self.io, style::SetUnderlineColor(to_crossterm_color(color)))
+    }
+    fn set_reversed(&mut self, reversed: bool) -> Result<(), Self::Error> {
+        queue!(
+            self.io,
+            style::SetAttribute(if reversed {
+                Attribute::Reverse
+            } else {
+                Attribute::NoReverse
+            })
+        )
+    }
+    fn set_hidden(&mut self, hidden: bool) -> Result<(), Self::Error> {
+        queue!(
+            self.io,
+            style::SetAttribute(if hidden {
+                Attribute::Hidden
+            } else {
+                Attribute::NoHidden
+            })
+        )
+    }
+    fn set_overlined(&mut self, overlined: bool) -> Result<(), Self::Error> {
+        queue!(
+            self.io,
+            style::SetAttribute(if overlined {
+                Attribute::OverLined
+            } else {
+                Attribute::NotOverLined
+            })
+        )
+    }
+    fn set_link(&mut self, link: Option<Link>) -> Result<(), Self::Error> {
+        // Crossterm doesn't support hyperlinks.
+        escape::set_link(&mut self.io, link)
+    }
 
     // Writing
 
     fn write(&mut self, text: &str) -> Result<(), Self::Error> {
-        self.io.write_all(text.as_bytes())?;
+        self.io.write_all(text.as_bytes())
+    }
+    fn write_repeated(&mut self, c: char, count: u16) -> Result<(), Self::Error> {
+        // Crossterm doesn't support REP or ECH either.
+        if !self.repeat {
+            for _ in 0..count {
+                write!(self.io, "{c}")?;
+            }
+            return Ok(());
+        }
+        write!(self.io, "{c}")?;
+        if count > 1 {
+            write!(self.io, "\x1B[{}b", count - 1)?;
+        }
+        Ok(())
+    }
+    fn erase_chars(&mut self, count: u16) -> Result<bool, Self::Error> {
+        if !self.repeat {
+            return Ok(false);
+        }
+        write!(self.io, "\x1B[{count}X")?;
+        Ok(true)
+    }
+    fn new_lines(&mut self, lines: u16) -> Result<(), Self::Error> {
+        for _ in 0..lines {
+            self.io.write_all(b"\r\n")?;
+        }
         Ok(())
     }
+    fn scroll(&mut self, region: Range<u16>, lines: i16) -> Result<bool, Self::Error> {
+        // Crossterm doesn't support scroll regions, so set them with DECSTBM manually.
+        write!(self.io, "\x1B[{};{}r", region.start + 1, region.end)?;
+        if lines > 0 {
+            queue!(self.io, terminal::ScrollUp(lines.unsigned_abs()))?;
+        } else {
+            queue!(self.io, terminal::ScrollDown(lines.unsigned_abs()))?;
+        }
+        self.io.write_all(b"\x1B[r")?;
+        Ok(true)
+    }
 
     // Finalizing functions
 
     fn flush(&mut self) -> Result<(), Self::Error> {
-        self.io.flush()?;
-        Ok(())
+        self.io.flush()
+    }
+    fn suspend(&mut self) -> Result<Tty, Self::Error> {
+        self.release()?;
+        // Dropping the stream stops Crossterm from reading input in the background.
+        self.stream = EventStream::new();
+        Ok(mem::replace(&mut self.io, Tty::dummy()))
+    }
+    fn resume(&mut self, io: Tty) -> Result<(), Self::Error> {
+        self.io = io;
+        set_up(&mut self.io)?;
+        self.push_keyboard_enhancements()
     }
     fn reset(mut self) -> Result<Tty, Self::Error> {
-        execute!(
-            self.io,
-            event::DisableMouseCapture,
-            terminal::EnableLineWrap,
-            terminal::LeaveAlternateScreen,
-            cursor::Show,
-        )?;
-        terminal::disable_raw_mode()?;
-
+        self.release()?;
         Ok(self.io)
     }
 }
@@ -189,6 +438,7 @@ impl<'a> ReadEvents<'a> for Bound {
     fn read_event(&'a mut self) -> Self::EventFuture {
         EventFuture {
             stream: &mut self.stream,
+            report_kinds: self.keyboard_enhancements.report_kinds,
         }
     }
 }
@@ -196,15 +446,26 @@ impl<'a> ReadEvents<'a> for Bound {
 #[derive(Debug)]
 pub struct EventFuture<'a> {
     stream: &'a mut EventStream,
+    /// Whether key releases were asked for; Crossterm reports them on Windows regardless.
+    report_kinds: bool,
 }
 
 impl<'a> Future for EventFuture<'a> {
-    type Output = crossterm::Result<TerminalEvent>;
+    type Output = io::Result<TerminalEvent>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        Pin::new(&mut self.stream)
-            .poll_next(cx)
-            .map(|event| event.unwrap().map(from_crossterm_event))
+        loop {
+            let event = match Pin::new(&mut self.stream).poll_next(cx) {
+                Poll::Ready(event) => event.unwrap(),
+                Poll::Pending => return Poll::Pending,
+            };
+            let report_kinds = self.report_kinds;
+            match event.map(|event| from_crossterm_event(event, report_kinds)) {
+                Ok(Some(event)) => return Poll::Ready(Ok(event)),
+                Ok(None) => {}
+                Err(e) => return Poll::Ready(Err(e)),
+            }
+        }
     }
 }
 
@@ -232,12 +493,14 @@ fn to_crossterm_color(color: Color) -> CColor {
     }
 }
 
-fn from_crossterm_event(event: Event) -> TerminalEvent {
-    match event {
+/// Convert a Crossterm event, returning [`None`] if it should be ignored.
+fn from_crossterm_event(event: Event, report_kinds: bool) -> Option<TerminalEvent> {
+    Some(match event {
+        Event::Key(key) if key.kind == KeyEventKind::Release && !report_kinds => return None,
         Event::Key(key) => TerminalEvent::Key(KeyPress {
             key: match key.code {
                 KeyCode::Backspace => Key::Backspace,
-                KeyCode::Enter => Key::Char('\n'),
+                KeyCode::Enter => Key::Enter,
                 KeyCode::Left => Key::Left,
                 KeyCode::Right => Key::Right,
                 KeyCode::Up => Key::Up,
@@ -246,43 +509,46 @@ fn from_crossterm_event(event: Event) -> TerminalEvent {
                 KeyCode::End => Key::End,
                 KeyCode::PageUp => Key::PageUp,
                 KeyCode::PageDown => Key::PageDown,
-                KeyCode::Tab | KeyCode::BackTab => Key::Char('\t'),
-                KeyCode::Delete => Key::Char('\x7f'),
+                KeyCode::Tab | KeyCode::BackTab => Key::Tab,
+                KeyCode::Delete => Key::Delete,
                 KeyCode::Insert => Key::Insert,
                 KeyCode::F(n) => Key::F(n),
-                KeyCode::Char(c) => Key::Char(c.to_ascii_lowercase()),
+                KeyCode::Char(c) => Key::Char(c),
                 KeyCode::Null => Key::Char('\0'),
                 KeyCode::Esc => Key::Escape,
+                _ => return None,
             },
             modifiers: {
                 let mut modifiers = from_crossterm_modifiers(key.modifiers);
-                modifiers.shift = modifiers.shift
-                    || key.code == KeyCode::BackTab
-                    || matches!(key.code, KeyCode::Char(c) if c.is_uppercase());
+                modifiers.shift = modifiers.shift || key.code == KeyCode::BackTab;
                 modifiers
             },
+            kind: match key.kind {
+                KeyEventKind::Press => KeyKind::Press,
+                KeyEventKind::Repeat => KeyKind::Repeat,
+                KeyEventKind::Release => KeyKind::Release,
+            },
+            keypad: key.state.contains(KeyEventState::KEYPAD),
         }),
-        Event::Mouse(mouse) => TerminalEvent::Mouse({
-            let (kind, x, y, modifiers) = match mouse {
-                MouseEvent::Down(button, x, y, modifiers) => (
-                    TerminalMouseKind::Press(from_crossterm_mouse_button(button)),
-                    x,
-                    y,
-                    modifiers,
-                ),
-                MouseEvent::Up(_, x, y, m) => (TerminalMouseKind::Release, x, y, m),
-                MouseEvent::Drag(_, x, y, m) => (TerminalMouseKind::Move, x, y, m),
-                MouseEvent::ScrollDown(x, y, m) => (TerminalMouseKind::ScrollDown, x, y, m),
-                MouseEvent::ScrollUp(x, y, m) => (TerminalMouseKind::ScrollUp, x, y, m),
-            };
-            TerminalMouse {
-                kind,
-                at: Vec2 { x, y },
-                modifiers: from_crossterm_modifiers(modifiers),
-            }
+        Event::Mouse(mouse) => TerminalEvent::Mouse(TerminalMouse {
+            kind: match mouse.kind {
+                MouseEventKind::Down(button) => {
+                    TerminalMouseKind::Press(from_crossterm_mouse_button(button))
+                }
+                MouseEventKind::Up(_) => TerminalMouseKind::Release,
+                MouseEventKind::Drag(_) | MouseEventKind::Moved => TerminalMouseKind::Move,
+                MouseEventKind::ScrollDown => TerminalMouseKind::ScrollDown,
+                MouseEventKind::ScrollUp => TerminalMouseKind::ScrollUp,
+                MouseEventKind::ScrollLeft | MouseEventKind::ScrollRight => return None,
+            },
+            at: Vec2::new(mouse.column, mouse.row),
+            modifiers: from_crossterm_modifiers(mouse.modifiers),
         }),
         Event::Resize(x, y) => TerminalEvent::Resize(Vec2 { x, y }),
-    }
+        Event::Paste(text) => TerminalEvent::Paste(text.replace("\r\n", "\n").replace('\r', "\n")),
+        Event::FocusGained => TerminalEvent::Focus(true),
+        Event::FocusLost => TerminalEvent::Focus(false),
+    })
 }
 fn from_crossterm_mouse_button(button: CMouseButton) -> MouseButton {
     match button {
@@ -296,5 +562,8 @@ fn from_crossterm_modifiers(modifiers: KeyModifiers) -> Modifiers {
         shift: modifiers.contains(KeyModifiers::SHIFT),
         control: modifiers.contains(KeyModifiers::CONTROL),
         alt: modifiers.contains(KeyModifiers::ALT),
+        super_key: modifiers.contains(KeyModifiers::SUPER),
+        hyper: modifiers.contains(KeyModifiers::HYPER),
+        meta: modifiers.contains(KeyModifiers::META),
     }
 }
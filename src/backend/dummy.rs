@@ -2,15 +2,16 @@ use std::cmp::min;
 use std::collections::VecDeque;
 use std::convert::Infallible;
 use std::future::Future;
+use std::ops::Range;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 
-use unicode_width::UnicodeWidthStr;
-
 use crate::buffer::{Buffer, Grid};
 use crate::output::Ext as _;
-use crate::style::{Color, Intensity, Style};
-use crate::{Cursor, CursorShape, Output, Vec2};
+use crate::style::{Color, Intensity, Link, Palette, Style, UnderlineStyle};
+use crate::width::{self, WidthTable};
+use crate::{Capabilities, Cursor, CursorShape, KeyboardEnhancements, Output, Vec2};
 
 use super::{Backend, Bound, ReadEvents, TerminalEvent, Tty};
 
@@ -30,12 +31,15 @@ pub struct Dummy {
     pub events: VecDeque<TerminalEvent>,
     /// The title of the terminal.
     pub title: String,
+    /// The icon name of the terminal. Setting the title also sets this.
+    pub icon_name: String,
     /// The buffer the dummy backend writes to.
     pub buffer: Buffer,
     /// The current position of the cursor.
     ///
     /// Unlike `buffer.cursor`, this stores the position of the cursor even when the cursor is
-    /// hidden. This is the cursor position used for drawing.
+    /// hidden. This is the cursor position used for drawing, and the one reported to inline
+    /// terminals.
     pub cursor_pos: Vec2<u16>,
     /// The current style being written with.
     pub style: Style,
@@ -44,6 +48,16 @@ pub struct Dummy {
     /// Writing to this TTY will panic as the terminal won't give the dummy a real TTY since it
     /// knows it's a dummy.
     pub tty: Option<Tty>,
+    /// The capabilities the dummy reports. By default it supports everything.
+    pub capabilities: Capabilities,
+    /// The colors the dummy reports when its palette is queried.
+    pub palette: Palette,
+    /// The contents of the clipboard. Setting the clipboard sets this, and it is reported when the
+    /// clipboard is read.
+    pub clipboard: Option<String>,
+    /// How wide the dummy displays strings. This table is reported when the terminal measures
+    /// widths, and is used when writing.
+    pub widths: WidthTable,
 }
 
 impl Dummy {
@@ -56,10 +70,15 @@ impl Dummy {
             operations: Vec::new(),
             events: VecDeque::new(),
             title: String::new(),
+            icon_name: String::new(),
             buffer: Buffer::from(Grid::new(size)),
             cursor_pos: Vec2::new(0, 0),
             style: Style::default(),
             tty: None,
+            capabilities: Capabilities::all(),
+            palette: Palette::default(),
+            clipboard: None,
+            widths: WidthTable::new(),
         }
     }
 }
@@ -72,6 +91,33 @@ impl Dummy {
 pub enum Operation {
     /// The title was set.
     SetTitle(String),
+    /// The icon name was set.
+    SetIconName(String),
+    /// The alternate screen was entered.
+    EnterAlternateScreen,
+    /// A synchronized update was begun.
+    BeginSynchronizedUpdate,
+    /// A synchronized update was ended.
+    EndSynchronizedUpdate,
+    /// The widths of strings were measured.
+    MeasureWidths,
+    /// The terminal's palette was queried.
+    QueryPalette,
+    /// The bell was rung.
+    Bell,
+    /// A desktop notification was shown.
+    Notify {
+        /// The title of the notification.
+        title: String,
+        /// The body of the notification.
+        body: String,
+    },
+    /// Text was copied to the clipboard.
+    SetClipboard(String),
+    /// The clipboard was read.
+    QueryClipboard,
+    /// The keyboard enhancements were set.
+    SetKeyboardEnhancements(KeyboardEnhancements),
     /// The cursor was hidden.
     HideCursor,
     /// The cursor was shown.
@@ -80,6 +126,8 @@ pub enum Operation {
     SetCursorShape(CursorShape),
     /// Whether the cursor blinks was set.
     SetCursorBlinking(bool),
+    /// The color of the cursor was set.
+    SetCursorColor(Color),
     /// The position of the cursor was set.
     SetCursorPos(Vec2<u16>),
     /// The foreground color was set.
@@ -96,10 +144,34 @@ pub enum Operation {
     SetBlinking(bool),
     /// Whether the text is crossed out was set.
     SetCrossedOut(bool),
+    /// The style of underlines was set.
+    SetUnderlineStyle(UnderlineStyle),
+    /// The color of underlines was set.
+    SetUnderlineColor(Color),
+    /// Whether the foreground and background colors are swapped was set.
+    SetReversed(bool),
+    /// Whether the text is hidden was set.
+    SetHidden(bool),
+    /// Whether there is a line over the text was set.
+    SetOverlined(bool),
+    /// The hyperlink that text links to was set.
+    SetLink(Option<Link>),
     /// Text was written to the output.
     Write(String),
+    /// A character was written to the output a number of times.
+    WriteRepeated(char, u16),
+    /// A number of cells starting at the cursor were cleared.
+    EraseChars(u16),
+    /// The cursor was moved down by a number of lines.
+    NewLines(u16),
+    /// A region of lines was scrolled up, or down if the number is negative.
+    Scroll(Range<u16>, i16),
     /// The output was flushed.
     Flush,
+    /// The terminal was suspended.
+    Suspend,
+    /// The terminal was resumed.
+    Resume,
 }
 
 impl Backend for Dummy {
@@ -126,10 +198,69 @@ impl Bound for Dummy {
     fn size(&mut self) -> Result<Vec2<u16>, Self::Error> {
         Ok(self.buffer.grid.size())
     }
+    fn capabilities(&mut self) -> Result<Capabilities, Self::Error> {
+        Ok(self.capabilities)
+    }
 
     fn set_title(&mut self, title: &str) -> Result<(), Self::Error> {
         self.operations.push(Operation::SetTitle(title.to_owned()));
         self.title = title.to_owned();
+        self.icon_name = self.title.clone();
+        Ok(())
+    }
+    fn set_icon_name(&mut self, icon_name: &str) -> Result<(), Self::Error> {
+        self.operations
+            .push(Operation::SetIconName(icon_name.to_owned()));
+        icon_name.clone_into(&mut self.icon_name);
+        Ok(())
+    }
+    fn enter_alternate_screen(&mut self) -> Result<(), Self::Error> {
+        self.operations.push(Operation::EnterAlternateScreen);
+        Ok(())
+    }
+    fn begin_synchronized_update(&mut self) -> Result<(), Self::Error> {
+        self.operations.push(Operation::BeginSynchronizedUpdate);
+        Ok(())
+    }
+    fn end_synchronized_update(&mut self) -> Result<(), Self::Error> {
+        self.operations.push(Operation::EndSynchronizedUpdate);
+        Ok(())
+    }
+    fn measure_widths(&mut self, _probes: &[&str]) -> Result<WidthTable, Self::Error> {
+        self.operations.push(Operation::MeasureWidths);
+        Ok(self.widths.clone())
+    }
+    fn query_palette(&mut self) -> Result<Palette, Self::Error> {
+        self.operations.push(Operation::QueryPalette);
+        Ok(self.palette)
+    }
+    fn bell(&mut self) -> Result<(), Self::Error> {
+        self.operations.push(Operation::Bell);
+        Ok(())
+    }
+    fn notify(&mut self, title: &str, body: &str) -> Result<(), Self::Error> {
+        self.operations.push(Operation::Notify {
+            title: title.to_owned(),
+            body: body.to_owned(),
+        });
+        Ok(())
+    }
+    fn set_clipboard(&mut self, text: &str) -> Result<(), Self::Error> {
+        self.operations
+            .push(Operation::SetClipboard(text.to_owned()));
+        self.clipboard = Some(text.to_owned());
+        Ok(())
+    }
+    fn clipboard(&mut self) -> Result<Option<String>, Self::Error> {
+        self.operations.push(Operation::QueryClipboard);
+        Ok(self.clipboard.clone())
+    }
+    fn set_keyboard_enhancements(
+        &mut self,
+        enhancements: KeyboardEnhancements,
+    ) -> Result<(), Self::Error> {
+        self.operations
+            .push(Operation::SetKeyboardEnhancements(enhancements));
         Ok(())
     }
 
@@ -145,6 +276,7 @@ impl Bound for Dummy {
         self.buffer.cursor = Some(Cursor {
             shape: CursorShape::Block,
             blinking: false,
+            color: Color::Default,
             pos: self.cursor_pos,
         });
         Ok(())
@@ -159,6 +291,11 @@ impl Bound for Dummy {
         self.buffer.cursor.as_mut().unwrap().blinking = blinking;
         Ok(())
     }
+    fn set_cursor_color(&mut self, color: Color) -> Result<(), Self::Error> {
+        self.operations.push(Operation::SetCursorColor(color));
+        self.buffer.cursor.as_mut().unwrap().color = color;
+        Ok(())
+    }
     fn set_cursor_pos(&mut self, pos: Vec2<u16>) -> Result<(), Self::Error> {
         self.operations.push(Operation::SetCursorPos(pos));
         if let Some(cursor) = &mut self.buffer.cursor {
@@ -167,6 +304,9 @@ impl Bound for Dummy {
         self.cursor_pos = pos;
         Ok(())
     }
+    fn cursor_pos(&mut self) -> Result<Vec2<u16>, Self::Error> {
+        Ok(self.cursor_pos)
+    }
 
     // Style functions
 
@@ -205,15 +345,48 @@ impl Bound for Dummy {
         self.style.attributes.crossed_out = crossed_out;
         Ok(())
     }
+    fn set_underline_style(&mut self, style: UnderlineStyle) -> Result<(), Self::Error> {
+        self.operations.push(Operation::SetUnderlineStyle(style));
+        self.style.attributes.underline_style = style;
+        Ok(())
+    }
+    fn set_underline_color(&mut self, color: Color) -> Result<(), Self::Error> {
+        self.operations.push(Operation::SetUnderlineColor(color));
+        self.style.underline_color = color;
+        Ok(())
+    }
+    fn set_reversed(&mut self, reversed: bool) -> Result<(), Self::Error> {
+        self.operations.push(Operation::SetReversed(reversed));
+        self.style.attributes.reversed = reversed;
+        Ok(())
+    }
+    fn set_hidden(&mut self, hidden: bool) -> Result<(), Self::Error> {
+        self.operations.push(Operation::SetHidden(hidden));
+        self.style.attributes.hidden = hidden;
+        Ok(())
+    }
+    fn set_overlined(&mut self, overlined: bool) -> Result<(), Self::Error> {
+        self.operations.push(Operation::SetOverlined(overlined));
+        self.style.attributes.overlined = overlined;
+        Ok(())
+    }
+    fn set_link(&mut self, link: Option<Link>) -> Result<(), Self::Error> {
+        self.operations.push(Operation::SetLink(link.clone()));
+        self.style.link = link;
+        Ok(())
+    }
 
     // Writing
 
     fn write(&mut self, text: &str) -> Result<(), Self::Error> {
         self.operations.push(Operation::Write(text.to_owned()));
-        self.buffer.write(self.cursor_pos, text, self.style);
 
+        let _widths = width::Scope::enter(Arc::new(self.widths.clone()));
+        self.buffer.write(self.cursor_pos, text, self.style.clone());
+
+        let text_width = width::str_width(text);
         self.cursor_pos.x = min(
-            self.cursor_pos.x.saturating_add(text.width() as u16),
+            self.cursor_pos.x.saturating_add(text_width as u16),
             self.buffer.grid.width(),
         );
 
@@ -222,6 +395,46 @@ impl Bound for Dummy {
         }
         Ok(())
     }
+    fn write_repeated(&mut self, c: char, count: u16) -> Result<(), Self::Error> {
+        let operations = self.operations.len();
+        self.write(&c.to_string().repeat(count.into()))?;
+        self.operations.truncate(operations);
+        self.operations.push(Operation::WriteRepeated(c, count));
+        Ok(())
+    }
+    fn erase_chars(&mut self, count: u16) -> Result<bool, Self::Error> {
+        self.operations.push(Operation::EraseChars(count));
+        let blanks = " ".repeat(count.into());
+        self.buffer
+            .write(self.cursor_pos, &blanks, self.style.clone());
+        Ok(true)
+    }
+    fn new_lines(&mut self, lines: u16) -> Result<(), Self::Error> {
+        self.operations.push(Operation::NewLines(lines));
+
+        let height = self.buffer.grid.height();
+        let below = height - 1 - self.cursor_pos.y;
+        if lines > below {
+            // Scroll the buffer up by removing lines from the top and adding them to the bottom.
+            let scroll = min(lines - below, height);
+            self.buffer
+                .grid
+                .resize_height_with_anchor(height - scroll, height - 1);
+            self.buffer.grid.resize_height(height);
+        }
+        self.cursor_pos = Vec2::new(0, min(self.cursor_pos.y + lines, height - 1));
+
+        if let Some(cursor) = &mut self.buffer.cursor {
+            cursor.pos = self.cursor_pos;
+        }
+        Ok(())
+    }
+    fn scroll(&mut self, region: Range<u16>, lines: i16) -> Result<bool, Self::Error> {
+        self.operations
+            .push(Operation::Scroll(region.clone(), lines));
+        self.buffer.grid.scroll(region, lines);
+        Ok(true)
+    }
 
     // Finalizing functions
 
@@ -229,6 +442,15 @@ impl Bound for Dummy {
         self.operations.push(Operation::Flush);
         Ok(())
     }
+    fn suspend(&mut self) -> Result<Tty, Self::Error> {
+        self.operations.push(Operation::Suspend);
+        Ok(self.tty.take().unwrap())
+    }
+    fn resume(&mut self, tty: Tty) -> Result<(), Self::Error> {
+        self.operations.push(Operation::Resume);
+        self.tty = Some(tty);
+        Ok(())
+    }
     fn reset(self) -> Result<Tty, Self::Error> {
         Ok(self.tty.unwrap())
     }
@@ -236,32 +458,34 @@ impl Bound for Dummy {
 
 impl<'a> ReadEvents<'a> for Dummy {
     type EventError = Infallible;
-    type EventFuture = EventFuture;
+    type EventFuture = EventFuture<'a>;
 
     fn read_event(&'a mut self) -> Self::EventFuture {
-        let event = self.events.pop_front();
-
-        if let Some(TerminalEvent::Resize(size)) = event {
-            self.buffer.grid.resize_width(size.x);
-            self.buffer
-                .grid
-                .resize_height_with_anchor(size.x, self.cursor_pos.y);
-        }
-
-        EventFuture(event)
+        EventFuture(self)
     }
 }
 
 #[derive(Debug)]
-pub struct EventFuture(Option<TerminalEvent>);
+pub struct EventFuture<'a>(&'a mut Dummy);
 
-impl Future for EventFuture {
+impl Future for EventFuture<'_> {
     type Output = Result<TerminalEvent, Infallible>;
 
     fn poll(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Self::Output> {
-        match self.0.take() {
-            Some(event) => Poll::Ready(Ok(event)),
-            None => Poll::Pending,
+        let dummy = &mut *self.0;
+        let event = match dummy.events.pop_front() {
+            Some(event) => event,
+            None => return Poll::Pending,
+        };
+
+        if let TerminalEvent::Resize(size) = event {
+            dummy.buffer.grid.resize_width(size.x);
+            dummy
+                .buffer
+                .grid
+                .resize_height_with_anchor(size.y, dummy.cursor_pos.y);
         }
+
+        Poll::Ready(Ok(event))
     }
 }
@@ -0,0 +1,448 @@
+//! The ANSI escape sequences shared by the backends, such as those used to move the cursor, and
+//! estimates of how many bytes drawing operations take.
+
+use std::cmp::{min, Ordering};
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+use std::env;
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+use std::io::{self, Write};
+
+use crate::Vec2;
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+use crate::{Color, CursorShape, Link};
+
+/// Save the window and icon titles on the XTWINOPS title stack.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+pub(crate) const PUSH_TITLE: &str = "\x1B[22;0t";
+/// Restore the window and icon titles from the XTWINOPS title stack.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+pub(crate) const POP_TITLE: &str = "\x1B[23;0t";
+
+/// The number of decimal digits in a number.
+fn digits(n: u16) -> usize {
+    match n {
+        0..=9 => 1,
+        10..=99 => 2,
+        100..=999 => 3,
+        1000..=9999 => 4,
+        _ => 5,
+    }
+}
+
+/// The length of a control sequence with a single numeric parameter, such as `CSI n C`.
+pub(crate) fn csi_len(n: u16) -> usize {
+    3 + digits(n)
+}
+
+/// A way to move the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Movement {
+    /// Set the position of the cursor directly.
+    Absolute,
+    /// Move the cursor vertically and then horizontally, optionally going to the start of the
+    /// line first.
+    Relative { carriage_return: bool },
+}
+
+/// The cost of moving the cursor vertically by `dy` lines, using line feeds to move down when
+/// they are shorter.
+fn vertical_cost(dy: i32) -> usize {
+    let lines = dy.unsigned_abs() as u16;
+    match dy.cmp(&0) {
+        Ordering::Equal => 0,
+        Ordering::Greater => min(lines.into(), csi_len(lines)),
+        Ordering::Less => csi_len(lines),
+    }
+}
+
+/// The cost of moving the cursor horizontally by `dx` columns, using backspaces to move left when
+/// they are shorter.
+fn horizontal_cost(dx: i32) -> usize {
+    let columns = dx.unsigned_abs() as u16;
+    match dx.cmp(&0) {
+        Ordering::Equal => 0,
+        Ordering::Greater => csi_len(columns),
+        Ordering::Less => min(columns.into(), csi_len(columns)),
+    }
+}
+
+/// Find the cheapest way to move the cursor between two positions, and its cost in bytes.
+fn plan(from: Vec2<u16>, to: Vec2<u16>) -> (Movement, usize) {
+    let dy = i32::from(to.y) - i32::from(from.y);
+    let dx = i32::from(to.x) - i32::from(from.x);
+
+    let absolute = 4 + digits(to.y.saturating_add(1)) + digits(to.x.saturating_add(1));
+    let relative = vertical_cost(dy) + horizontal_cost(dx);
+    let carriage_return = vertical_cost(dy) + 1 + horizontal_cost(to.x.into());
+
+    if absolute <= relative && absolute <= carriage_return {
+        (Movement::Absolute, absolute)
+    } else if carriage_return < relative {
+        (
+            Movement::Relative {
+                carriage_return: true,
+            },
+            carriage_return,
+        )
+    } else {
+        (
+            Movement::Relative {
+                carriage_return: false,
+            },
+            relative,
+        )
+    }
+}
+
+/// The number of bytes [`move_cursor`] writes.
+pub(crate) fn move_cursor_cost(from: Vec2<u16>, to: Vec2<u16>) -> usize {
+    plan(from, to).1
+}
+
+/// Move the cursor from one position to another (both zero-indexed) using the shortest sequence.
+///
+/// This relies on line wrapping being disabled and the output not translating line feeds.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+pub(crate) fn move_cursor(out: &mut impl Write, from: Vec2<u16>, to: Vec2<u16>) -> io::Result<()> {
+    let carriage_return = match plan(from, to).0 {
+        Movement::Absolute => {
+            return write!(out, "\x1B[{};{}H", to.y + 1, to.x + 1);
+        }
+        Movement::Relative { carriage_return } => carriage_return,
+    };
+
+    if to.y > from.y {
+        let lines = to.y - from.y;
+        if usize::from(lines) <= csi_len(lines) {
+            for _ in 0..lines {
+                out.write_all(b"\n")?;
+            }
+        } else {
+            write!(out, "\x1B[{lines}B")?;
+        }
+    } else if to.y < from.y {
+        write!(out, "\x1B[{}A", from.y - to.y)?;
+    }
+
+    let from_x = if carriage_return {
+        out.write_all(b"\r")?;
+        0
+    } else {
+        from.x
+    };
+
+    if to.x > from_x {
+        write!(out, "\x1B[{}C", to.x - from_x)?;
+    } else if to.x < from_x {
+        let columns = from_x - to.x;
+        if usize::from(columns) <= csi_len(columns) {
+            for _ in 0..columns {
+                out.write_all(b"\x08")?;
+            }
+        } else {
+            write!(out, "\x1B[{columns}D")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Set the shape of the cursor and whether it blinks with DECSCUSR, which sets both at once.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+pub(crate) fn set_cursor_style(
+    out: &mut impl Write,
+    shape: CursorShape,
+    blinking: bool,
+) -> io::Result<()> {
+    let steady = u8::from(!blinking);
+    let style = match shape {
+        CursorShape::Block => 1,
+        CursorShape::Underline => 3,
+        CursorShape::Bar => 5,
+    } + steady;
+    write!(out, "\x1B[{style} q")
+}
+
+/// Set the color of the cursor with OSC 12, or reset it to the terminal's cursor color with
+/// OSC 112.
+///
+/// Terminals can't be given ANSI colors by their value, so they are set to the colors xterm
+/// displays them as.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+pub(crate) fn set_cursor_color(out: &mut impl Write, color: Color) -> io::Result<()> {
+    match color.to_rgb() {
+        Some(rgb) => write!(
+            out,
+            "\x1B]12;#{:02x}{:02x}{:02x}\x1B\\",
+            rgb.r, rgb.g, rgb.b
+        ),
+        None => out.write_all(RESET_CURSOR_COLOR.as_bytes()),
+    }
+}
+
+/// Reset the color of the cursor to the terminal's cursor color.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+pub(crate) const RESET_CURSOR_COLOR: &str = "\x1B]112\x1B\\";
+
+/// Set the window title with OSC 0, or the icon name with OSC 1.
+///
+/// Control characters, which could end the sequence early, are replaced with spaces.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+pub(crate) fn set_title(out: &mut impl Write, icon_name: bool, title: &str) -> io::Result<()> {
+    let title = title.replace(|c: char| c.is_control(), " ");
+    write!(out, "\x1B]{};{title}\x07", u8::from(icon_name))
+}
+
+/// Start writing a hyperlink with OSC 8, or stop writing one.
+///
+/// Characters that would end the sequence early are left out of the URL and the ID, as are the
+/// separators of the ID's parameter.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+pub(crate) fn set_link(out: &mut impl Write, link: Option<Link>) -> io::Result<()> {
+    let link = match link {
+        Some(link) => link,
+        None => return out.write_all(b"\x1B]8;;\x1B\\"),
+    };
+
+    out.write_all(b"\x1B]8;")?;
+    if let Some(id) = link.id() {
+        let id = id.replace(|c: char| c.is_control() || c == ':' || c == ';', "");
+        write!(out, "id={id}")?;
+    }
+    write!(
+        out,
+        ";{}\x1B\\",
+        link.url().replace(|c: char| c.is_control(), "")
+    )
+}
+
+/// The alphabet of standard base64.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode bytes as padded standard base64.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len() / 3 * 4 + 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |n, (i, &b)| n | u32::from(b) << (16 - i * 8));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(char::from(BASE64[(n >> (18 - i * 6)) as usize & 63]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decode standard base64, with or without padding. Returns `None` if it is invalid.
+#[cfg(all(unix, feature = "ansi"))]
+pub(crate) fn decode_base64(encoded: &[u8]) -> Option<Vec<u8>> {
+    let encoded = match encoded.iter().position(|&b| b == b'=') {
+        Some(padding) if encoded[padding..].iter().all(|&b| b == b'=') => &encoded[..padding],
+        Some(_) => return None,
+        None => encoded,
+    };
+    if encoded.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut decoded = Vec::with_capacity(encoded.len() / 4 * 3 + 2);
+    for chunk in encoded.chunks(4) {
+        let mut n = 0;
+        for (i, &b) in chunk.iter().enumerate() {
+            let value = BASE64.iter().position(|&c| c == b)? as u32;
+            n |= value << (18 - i * 6);
+        }
+        decoded.extend(n.to_be_bytes()[1..].iter().take(chunk.len() - 1).copied());
+    }
+    Some(decoded)
+}
+
+/// Copy text to the clipboard with OSC 52.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+pub(crate) fn set_clipboard(out: &mut impl Write, text: &str) -> io::Result<()> {
+    write!(out, "\x1B]52;c;{}\x1B\\", encode_base64(text.as_bytes()))
+}
+
+/// Show a desktop notification.
+///
+/// Terminals support either OSC 777, which has a title and a body, or OSC 9, which only has a
+/// message. OSC 9 is used in the terminals known to only support it, and OSC 777 otherwise.
+/// Control characters, which could end the sequence early, are replaced with spaces in the title
+/// and body, as are semicolons in the title of OSC 777.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+pub(crate) fn notify(out: &mut impl Write, title: &str, body: &str) -> io::Result<()> {
+    let osc_9 = matches!(env::var("TERM_PROGRAM").as_deref(), Ok("iTerm.app"))
+        || matches!(env::var("TERM"), Ok(term) if term.contains("kitty"));
+    write_notification(out, title, body, osc_9)
+}
+
+/// Show a desktop notification with OSC 9 or OSC 777.
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+fn write_notification(
+    out: &mut impl Write,
+    title: &str,
+    body: &str,
+    osc_9: bool,
+) -> io::Result<()> {
+    let body = body.replace(|c: char| c.is_control(), " ");
+    if osc_9 {
+        let title = title.replace(|c: char| c.is_control(), " ");
+        if title.is_empty() {
+            write!(out, "\x1B]9;{body}\x1B\\")
+        } else {
+            write!(out, "\x1B]9;{title}: {body}\x1B\\")
+        }
+    } else {
+        let title = title.replace(|c: char| c.is_control() || c == ';', " ");
+        write!(out, "\x1B]777;notify;{title};{body}\x1B\\")
+    }
+}
+
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+#[test]
+fn test_notify() {
+    fn written(title: &str, body: &str, osc_9: bool) -> String {
+        let mut out = Vec::new();
+        write_notification(&mut out, title, body, osc_9).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    assert_eq!(
+        written("Build; done", "All\x07 passed", false),
+        "\x1B]777;notify;Build  done;All  passed\x1B\\"
+    );
+    assert_eq!(
+        written("Build", "All passed", true),
+        "\x1B]9;Build: All passed\x1B\\"
+    );
+    assert_eq!(written("", "Done", true), "\x1B]9;Done\x1B\\");
+    assert_eq!(
+        written("a\x1Bb", "c\u{9c}d\n", true),
+        "\x1B]9;a b: c d \x1B\\"
+    );
+}
+
+#[cfg(all(unix, feature = "ansi"))]
+#[test]
+fn test_base64() {
+    for &(decoded, encoded) in &[
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("hello world", "aGVsbG8gd29ybGQ="),
+        ("\u{1f600}", "8J+YgA=="),
+    ] {
+        assert_eq!(encode_base64(decoded.as_bytes()), encoded);
+        assert_eq!(
+            decode_base64(encoded.as_bytes()).as_deref(),
+            Some(decoded.as_bytes())
+        );
+    }
+    assert_eq!(decode_base64(b"Zm8").as_deref(), Some(&b"fo"[..]));
+    assert_eq!(decode_base64(b"Zm=8"), None);
+    assert_eq!(decode_base64(b"Z"), None);
+    assert_eq!(decode_base64(b"Zm9v!A=="), None);
+
+    let mut out = Vec::new();
+    set_clipboard(&mut out, "foo").unwrap();
+    assert_eq!(out, b"\x1B]52;c;Zm9v\x1B\\");
+}
+
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+#[test]
+fn test_cursor() {
+    fn written(f: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+        let mut out = Vec::new();
+        f(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    assert_eq!(
+        written(|out| set_cursor_style(out, CursorShape::Bar, true)),
+        "\x1B[5 q"
+    );
+    assert_eq!(
+        written(|out| set_cursor_style(out, CursorShape::Block, false)),
+        "\x1B[2 q"
+    );
+    assert_eq!(
+        written(|out| set_cursor_color(out, Color::Rgb(crate::Rgb::new(255, 128, 0)))),
+        "\x1B]12;#ff8000\x1B\\"
+    );
+    assert_eq!(
+        written(|out| set_cursor_color(out, Color::Red)),
+        "\x1B]12;#ff0000\x1B\\"
+    );
+    assert_eq!(
+        written(|out| set_cursor_color(out, Color::Default)),
+        "\x1B]112\x1B\\"
+    );
+}
+
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+#[test]
+fn test_set_title() {
+    fn written(icon_name: bool, title: &str) -> String {
+        let mut out = Vec::new();
+        set_title(&mut out, icon_name, title).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    assert_eq!(written(false, "Toon"), "\x1B]0;Toon\x07");
+    assert_eq!(written(true, "Toon"), "\x1B]1;Toon\x07");
+    assert_eq!(
+        written(false, "a\x07\x1B]0;b\x1B\\c\u{9c}"),
+        "\x1B]0;a  ]0;b \\c \x07"
+    );
+}
+
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+#[test]
+fn test_set_link() {
+    fn written(link: Option<Link>) -> String {
+        let mut out = Vec::new();
+        set_link(&mut out, link).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    assert_eq!(written(None), "\x1B]8;;\x1B\\");
+    assert_eq!(
+        written(Some(Link::new("https://example.com/\x07"))),
+        "\x1B]8;;https://example.com/\x1B\\"
+    );
+    assert_eq!(
+        written(Some(Link::new("file:///tmp").with_id("a:b;c"))),
+        "\x1B]8;id=abc;file:///tmp\x1B\\"
+    );
+}
+
+#[cfg(any(all(unix, feature = "ansi"), feature = "crossterm"))]
+#[test]
+fn test_move_cursor() {
+    fn moved(from: (u16, u16), to: (u16, u16)) -> String {
+        let mut out = Vec::new();
+        move_cursor(&mut out, from.into(), to.into()).unwrap();
+        assert_eq!(out.len(), move_cursor_cost(from.into(), to.into()));
+        String::from_utf8(out).unwrap()
+    }
+
+    assert_eq!(moved((5, 5), (5, 5)), "");
+    assert_eq!(moved((5, 5), (8, 5)), "\x1B[3C");
+    assert_eq!(moved((5, 5), (3, 5)), "\x08\x08");
+    assert_eq!(moved((5, 5), (5, 6)), "\n");
+    assert_eq!(moved((5, 5), (5, 2)), "\x1B[3A");
+    assert_eq!(moved((5, 5), (0, 6)), "\n\r");
+    assert_eq!(moved((5, 5), (1, 6)), "\n\x08\x08\x08\x08");
+    assert_eq!(moved((1000, 50), (1, 51)), "\n\r\x1B[1C");
+    assert_eq!(moved((50, 5), (0, 40)), "\x1B[35B\r");
+    assert_eq!(moved((50, 50), (0, 0)), "\x1B[1;1H");
+}
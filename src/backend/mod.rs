@@ -3,15 +3,25 @@
 use std::fs::{self, File};
 use std::future::Future;
 use std::io::{self, BufWriter, IoSlice, Write};
+use std::ops::Range;
 #[cfg(unix)]
 use std::os::unix::io::{AsRawFd, RawFd};
 #[cfg(windows)]
 use std::os::windows::io::{AsRawHandle, RawHandle};
 
-use os_pipe::PipeReader;
+use os_pipe::PipeWriter;
 use stdio_override::{StderrOverride, StdoutOverride};
 
-use crate::{Color, CursorShape, Intensity, KeyPress, Modifiers, MouseButton, Vec2};
+use crate::{
+    Capabilities, Color, CursorShape, Intensity, KeyPress, KeyboardEnhancements, Link, Modifiers,
+    MouseButton, Palette, UnderlineStyle, Vec2, WidthTable,
+};
+
+#[cfg(all(unix, feature = "ansi"))]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(all(unix, feature = "ansi"))))]
+mod ansi;
+#[cfg(all(unix, feature = "ansi"))]
+pub use self::ansi::Ansi;
 
 #[cfg(feature = "crossterm")]
 #[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "crossterm")))]
@@ -22,6 +32,8 @@ pub use self::crossterm::Crossterm;
 mod dummy;
 pub use self::dummy::{Dummy, Operation};
 
+pub(crate) mod escape;
+
 /// A backend that can be used with Toon.
 pub trait Backend {
     /// Errors produced by this backend.
@@ -59,9 +71,119 @@ pub trait Bound: for<'a> ReadEvents<'a, EventError = <Self as Bound>::Error> + S
     /// Get the size of the terminal.
     fn size(&mut self) -> Result<Vec2<u16>, Self::Error>;
 
-    /// Set the title of the terminal.
+    /// Get what the terminal supports.
+    ///
+    /// This is called once when the terminal is created. By default the capabilities are
+    /// [detected from the environment](Capabilities::detect).
+    fn capabilities(&mut self) -> Result<Capabilities, Self::Error> {
+        Ok(Capabilities::detect())
+    }
+
+    /// Set the title of the terminal, and its icon name if that isn't set separately.
+    ///
+    /// The backend should save the title the terminal had before binding, for example on the
+    /// XTWINOPS title stack, and restore it when suspending and resetting.
     fn set_title(&mut self, title: &str) -> Result<(), Self::Error>;
 
+    /// Set the icon name of the terminal, which some terminals show in their tab or task bar
+    /// instead of the title.
+    ///
+    /// By default this does nothing.
+    fn set_icon_name(&mut self, icon_name: &str) -> Result<(), Self::Error> {
+        let _ = icon_name;
+        Ok(())
+    }
+
+    /// Switch to the alternate screen and clear it.
+    ///
+    /// This is called after binding and after resuming unless the terminal is drawing inline. If
+    /// it was called, [`suspend`](Self::suspend) and [`reset`](Self::reset) must switch back to the
+    /// main screen.
+    fn enter_alternate_screen(&mut self) -> Result<(), Self::Error>;
+
+    /// Begin a synchronized update. The terminal should not display anything written until
+    /// [`end_synchronized_update`](Self::end_synchronized_update) is called, so that frames never
+    /// appear partially drawn.
+    ///
+    /// By default this does nothing.
+    fn begin_synchronized_update(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// End a synchronized update, displaying everything written since it began.
+    ///
+    /// By default this does nothing.
+    fn end_synchronized_update(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Measure how wide the terminal displays each of the strings, for example by writing them and
+    /// querying the position of the cursor. Strings that couldn't be measured should be left out.
+    ///
+    /// This is called once after the terminal is first set up, with the cursor hidden at the
+    /// start of an empty line. The cursor must be left at the start of the line and the line must
+    /// be left empty.
+    ///
+    /// By default nothing is measured and an empty table is returned.
+    fn measure_widths(&mut self, probes: &[&str]) -> Result<WidthTable, Self::Error> {
+        let _ = probes;
+        Ok(WidthTable::new())
+    }
+
+    /// Query the colors the terminal displays, for example with the OSC 10, 11 and 4 escape
+    /// sequences. Colors the terminal doesn't report in a reasonable time should be left as
+    /// `None`.
+    ///
+    /// By default no colors are reported.
+    fn query_palette(&mut self) -> Result<Palette, Self::Error> {
+        Ok(Palette::default())
+    }
+
+    /// Ring the bell.
+    ///
+    /// By default this does nothing.
+    fn bell(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Show a desktop notification, for example with the OSC 777 or OSC 9 escape sequences.
+    ///
+    /// By default this does nothing.
+    fn notify(&mut self, title: &str, body: &str) -> Result<(), Self::Error> {
+        let _ = (title, body);
+        Ok(())
+    }
+
+    /// Copy text to the system clipboard, for example with the OSC 52 escape sequence.
+    ///
+    /// By default this does nothing.
+    fn set_clipboard(&mut self, text: &str) -> Result<(), Self::Error> {
+        let _ = text;
+        Ok(())
+    }
+
+    /// Read the text in the system clipboard, for example with the OSC 52 escape sequence. Many
+    /// terminals don't allow this, so `None` should be returned if the terminal doesn't reply in
+    /// a reasonable time.
+    ///
+    /// By default the clipboard can't be read and `None` is returned.
+    fn clipboard(&mut self) -> Result<Option<String>, Self::Error> {
+        Ok(None)
+    }
+
+    /// Set how the terminal reports keys, for example by pushing flags of the kitty keyboard
+    /// protocol with `CSI > flags u`. The enhancements must be kept across suspending and
+    /// resuming, and undone when the terminal is reset.
+    ///
+    /// By default this does nothing.
+    fn set_keyboard_enhancements(
+        &mut self,
+        enhancements: KeyboardEnhancements,
+    ) -> Result<(), Self::Error> {
+        let _ = enhancements;
+        Ok(())
+    }
+
     // Cursor functions
 
     /// Hide the cursor.
@@ -76,9 +198,33 @@ pub trait Bound: for<'a> ReadEvents<'a, EventError = <Self as Bound>::Error> + S
     /// Set whether the cursor blinks.
     fn set_cursor_blinking(&mut self, blinking: bool) -> Result<(), Self::Error>;
 
+    /// Set the color of the cursor. The default color uses the terminal's cursor color, which
+    /// [`suspend`](Self::suspend) and [`reset`](Self::reset) must restore.
+    ///
+    /// By default this does nothing.
+    fn set_cursor_color(&mut self, color: Color) -> Result<(), Self::Error> {
+        let _ = color;
+        Ok(())
+    }
+
     /// Set the position of the cursor (zero-indexed).
     fn set_cursor_pos(&mut self, pos: Vec2<u16>) -> Result<(), Self::Error>;
 
+    /// Move the cursor from `from`, where it currently is, to `to` (both zero-indexed).
+    ///
+    /// By default this calls [`set_cursor_pos`](Self::set_cursor_pos), but backends can override
+    /// it to use shorter relative movements.
+    fn move_cursor(&mut self, from: Vec2<u16>, to: Vec2<u16>) -> Result<(), Self::Error> {
+        let _ = from;
+        self.set_cursor_pos(to)
+    }
+
+    /// Query the terminal for the position of the cursor (zero-indexed).
+    ///
+    /// This is used to find where to draw when the terminal is inline, and must flush any
+    /// buffered actions first.
+    fn cursor_pos(&mut self) -> Result<Vec2<u16>, Self::Error>;
+
     // Style functions
 
     /// Set the foreground color to write with.
@@ -102,6 +248,55 @@ pub trait Bound: for<'a> ReadEvents<'a, EventError = <Self as Bound>::Error> + S
     /// Set whether the text is crossed out.
     fn set_crossed_out(&mut self, crossed_out: bool) -> Result<(), Self::Error>;
 
+    /// Set the style of underlines. This is set independently of whether the text is underlined,
+    /// and only has an effect while it is.
+    ///
+    /// By default this does nothing.
+    fn set_underline_style(&mut self, style: UnderlineStyle) -> Result<(), Self::Error> {
+        let _ = style;
+        Ok(())
+    }
+
+    /// Set the color of underlines. The default color uses the foreground color.
+    ///
+    /// By default this does nothing.
+    fn set_underline_color(&mut self, color: Color) -> Result<(), Self::Error> {
+        let _ = color;
+        Ok(())
+    }
+
+    /// Set whether the foreground and background colors are swapped.
+    ///
+    /// By default this does nothing.
+    fn set_reversed(&mut self, reversed: bool) -> Result<(), Self::Error> {
+        let _ = reversed;
+        Ok(())
+    }
+
+    /// Set whether the text is hidden.
+    ///
+    /// By default this does nothing.
+    fn set_hidden(&mut self, hidden: bool) -> Result<(), Self::Error> {
+        let _ = hidden;
+        Ok(())
+    }
+
+    /// Set whether there is a line over the text.
+    ///
+    /// By default this does nothing.
+    fn set_overlined(&mut self, overlined: bool) -> Result<(), Self::Error> {
+        let _ = overlined;
+        Ok(())
+    }
+
+    /// Set the hyperlink that written text links to, or stop writing a hyperlink.
+    ///
+    /// By default this does nothing.
+    fn set_link(&mut self, link: Option<Link>) -> Result<(), Self::Error> {
+        let _ = link;
+        Ok(())
+    }
+
     // Writing
 
     /// Write text to the output.
@@ -110,14 +305,63 @@ pub trait Bound: for<'a> ReadEvents<'a, EventError = <Self as Bound>::Error> + S
     /// the line to overflow or wrap.
     fn write(&mut self, text: &str) -> Result<(), Self::Error>;
 
+    /// Write a character to the output the given number of times.
+    ///
+    /// The same guarantees as [`write`](Self::write) apply. By default this writes the repeated
+    /// characters, but backends can override it to use a shorter sequence.
+    fn write_repeated(&mut self, c: char, count: u16) -> Result<(), Self::Error> {
+        self.write(&c.to_string().repeat(count.into()))
+    }
+
+    /// Clear the given number of cells starting at the cursor with the current style, without
+    /// moving the cursor.
+    ///
+    /// Returns whether erasing is supported. By default it isn't, and this does nothing and
+    /// returns `false`.
+    fn erase_chars(&mut self, count: u16) -> Result<bool, Self::Error> {
+        let _ = count;
+        Ok(false)
+    }
+
+    /// Move the cursor to the start of the line below it the given number of times, scrolling the
+    /// screen up if it is on the last line.
+    fn new_lines(&mut self, lines: u16) -> Result<(), Self::Error>;
+
+    /// Scroll the lines in the region up by the given number of lines, or down if it is negative.
+    ///
+    /// Lines scrolled out of the region are discarded, and the lines revealed are cleared with the
+    /// default background color. Lines outside of the region must not be affected. The position of
+    /// the cursor afterwards is unspecified.
+    ///
+    /// Returns whether scrolling is supported. By default it isn't, and this does nothing and
+    /// returns `false`.
+    fn scroll(&mut self, region: Range<u16>, lines: i16) -> Result<bool, Self::Error> {
+        let _ = (region, lines);
+        Ok(false)
+    }
+
     // Finalizing functions
 
     /// Flush all buffered actions to the tty.
     fn flush(&mut self) -> Result<(), Self::Error>;
 
+    /// Temporarily reset the terminal to its initial state so that other programs can use it,
+    /// returning the TTY.
+    ///
+    /// If this fails the backend is [reset](Self::reset). Otherwise [`resume`](Self::resume) will
+    /// be called afterwards, unless the TTY can't be opened again in which case the suspended
+    /// backend is dropped without being reset.
+    fn suspend(&mut self) -> Result<Tty, Self::Error>;
+
+    /// Set up the terminal again after it was [suspended](Self::suspend), using the given TTY.
+    ///
+    /// If this fails the backend is [reset](Self::reset), so the backend should keep the TTY even
+    /// if setting up the terminal fails.
+    fn resume(&mut self, io: Tty) -> Result<(), Self::Error>;
+
     /// Reset the terminal to its initial state, returning the TTY.
     ///
-    /// This will always be called.
+    /// This will always be called unless the backend is dropped while suspended.
     fn reset(self) -> Result<Tty, Self::Error>;
 }
 
@@ -136,12 +380,16 @@ pub trait ReadEvents<'a> {
 }
 
 /// An event on the terminal.
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum TerminalEvent {
     /// A key input occurred.
     Key(KeyPress),
     /// A mouse input occurred.
     Mouse(TerminalMouse),
+    /// Text was pasted with bracketed paste. Line breaks in the text must be `\n`.
+    Paste(String),
+    /// The terminal gained (`true`) or lost (`false`) focus.
+    Focus(bool),
     /// The terminal was resized. Contains the new size.
     Resize(Vec2<u16>),
 }
@@ -186,14 +434,11 @@ impl Tty {
     pub(crate) fn dummy() -> Self {
         Self { inner: None }
     }
-    pub(crate) fn new() -> io::Result<(Self, PipeReader)> {
-        let (inner, writer) = TtyInner::new()?;
-        Ok((
-            Self {
-                inner: Some(BufWriter::new(inner)),
-            },
-            writer,
-        ))
+    /// Open the TTY, redirecting standard output and standard error to the pipe.
+    pub(crate) fn new(pipe: &PipeWriter) -> io::Result<Self> {
+        Ok(Self {
+            inner: Some(BufWriter::new(TtyInner::new(pipe)?)),
+        })
     }
     pub(crate) fn cleanup(self) -> io::Result<()> {
         if let Some(inner) = self.inner {
@@ -236,11 +481,9 @@ struct TtyInner {
 }
 
 impl TtyInner {
-    fn new() -> io::Result<(Self, PipeReader)> {
-        let (rx, tx) = os_pipe::pipe()?;
-
-        let stdout = StdoutOverride::from_io_ref(&tx)?;
-        let stderr = StderrOverride::from_io(tx)?;
+    fn new(pipe: &PipeWriter) -> io::Result<Self> {
+        let stdout = StdoutOverride::from_io_ref(pipe)?;
+        let stderr = StderrOverride::from_io_ref(pipe)?;
 
         let tty = if cfg!(unix) {
             let tty_path = if cfg!(target_os = "redox") {
@@ -260,14 +503,11 @@ impl TtyInner {
             None
         };
 
-        Ok((
-            Self {
-                stdout,
-                stderr,
-                tty,
-            },
-            rx,
-        ))
+        Ok(Self {
+            stdout,
+            stderr,
+            tty,
+        })
     }
     fn cleanup(self) -> io::Result<()> {
         self.stdout.reset()?;
@@ -1,17 +1,18 @@
 //! Data structures representing terminals.
 //!
-//! Toon uses [`unicode_width`] to get the number of cells a character takes up in a terminal. This
-//! isn't a perfect solution - for example, 👨‍👨‍👧‍👦, a family emoji, cannot be
-//! displayed in Toon as [`unicode_width`] measures it as 8 columns wide. However, it is the same as
-//! what most terminals do and is simple to understand and implement.
+//! Each cell holds an extended grapheme cluster, such as a letter with its combining accents, a
+//! flag or an emoji sequence like 👨‍👨‍👧‍👦. Toon uses
+//! [`grapheme_width`](crate::width::grapheme_width) to get the number of cells a cluster takes up
+//! in a terminal, which uses the widths measured by the terminal where they are known and
+//! [`unicode_width`] otherwise. Clusters that the terminal displays wider than two cells are split
+//! over several cells, one for each emoji or other character that isn't zero-width.
 
 use std::cmp::Ordering;
-use std::iter;
+use std::ops::Range;
+use std::time::Instant;
 
+use crate::{width, Cursor, Output, Request, Style, Vec2};
 use smartstring::{LazyCompact, SmartString};
-use unicode_width::UnicodeWidthChar;
-
-use crate::{Cursor, Output, Style, Vec2};
 
 /// A terminal state.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
@@ -20,19 +21,31 @@ pub struct Buffer {
     pub grid: Grid,
     /// The cursor on the terminal.
     pub cursor: Option<Cursor>,
+    /// When the terminal should be drawn again, if a frame has been requested.
+    pub next_frame: Option<Instant>,
+    /// The requests made while drawing to the buffer, in order.
+    pub requests: Vec<Request>,
 }
 
 impl Buffer {
-    /// Reset the buffer. This clears the grid and removes the cursor.
+    /// Reset the buffer. This clears the grid, removes the cursor and forgets the requested frame
+    /// and requests.
     pub fn reset(&mut self) {
         self.grid.clear();
         self.cursor = None;
+        self.next_frame = None;
+        self.requests.clear();
     }
 }
 
 impl From<Grid> for Buffer {
     fn from(grid: Grid) -> Self {
-        Self { grid, cursor: None }
+        Self {
+            grid,
+            cursor: None,
+            next_frame: None,
+            requests: Vec::new(),
+        }
     }
 }
 
@@ -43,9 +56,18 @@ impl Output for Buffer {
     fn write_char(&mut self, pos: Vec2<u16>, c: char, style: Style) {
         self.grid.write_char(pos, c, style)
     }
+    fn write_grapheme(&mut self, pos: Vec2<u16>, grapheme: &str, style: Style) {
+        self.grid.write_grapheme(pos, grapheme, style);
+    }
     fn set_cursor(&mut self, cursor: Option<Cursor>) {
         self.cursor = cursor;
     }
+    fn request_frame_at(&mut self, at: Instant) {
+        self.next_frame = Some(self.next_frame.map_or(at, |next| next.min(at)));
+    }
+    fn request(&mut self, request: Request) {
+        self.requests.push(request);
+    }
 }
 
 /// The grid of characters on a terminal.
@@ -135,6 +157,29 @@ impl Grid {
             .resize_with(usize::from(new_height), || Line::new(width));
     }
 
+    /// Scroll the lines in the region up by the given number of lines, or down if it is negative.
+    ///
+    /// Lines scrolled out of the region are removed, and the lines revealed are empty. Lines outside
+    /// of the region are unaffected.
+    pub fn scroll(&mut self, region: Range<u16>, lines: i16) {
+        let end = usize::from(region.end).min(self.lines.len());
+        let start = usize::from(region.start).min(end);
+        let region = &mut self.lines[start..end];
+        let amount = usize::from(lines.unsigned_abs()).min(region.len());
+
+        let revealed = if lines > 0 {
+            region.rotate_left(amount);
+            let len = region.len();
+            &mut region[len - amount..]
+        } else {
+            region.rotate_right(amount);
+            &mut region[..amount]
+        };
+        for line in revealed {
+            line.clear();
+        }
+    }
+
     /// Get the grid's contents as a vector of strings.
     ///
     /// This is mostly useful in tests.
@@ -160,6 +205,11 @@ impl Output for Grid {
             line.write_char(Vec2::new(pos.x, 0), c, style);
         }
     }
+    fn write_grapheme(&mut self, pos: Vec2<u16>, grapheme: &str, style: Style) {
+        if let Some(line) = self.lines.get_mut(usize::from(pos.y)) {
+            line.write_grapheme(Vec2::new(pos.x, 0), grapheme, style);
+        }
+    }
     fn set_cursor(&mut self, _cursor: Option<Cursor>) {}
 }
 
@@ -250,16 +300,19 @@ impl Output for Line {
         Vec2::new(self.len(), 1)
     }
     fn write_char(&mut self, pos: Vec2<u16>, c: char, style: Style) {
-        if pos.y != 0 || c == '\0' {
+        self.write_grapheme(pos, c.encode_utf8(&mut [0; 4]), style);
+    }
+    fn write_grapheme(&mut self, pos: Vec2<u16>, grapheme: &str, style: Style) {
+        if pos.y != 0 || grapheme.contains('\0') {
             return;
         }
 
         let x = usize::from(pos.x);
 
-        match c.width() {
+        match width::grapheme_width(grapheme) {
             Some(0) => {
                 if let Some(Cell(CellInner::Char { contents, .. })) = self.cells.get_mut(x) {
-                    contents.push(c);
+                    contents.push_str(grapheme);
                 }
             }
             Some(1) => {
@@ -270,7 +323,7 @@ impl Output for Line {
                 let old_cell = std::mem::replace(
                     &mut cell.0,
                     CellInner::Char {
-                        contents: iter::once(c).collect(),
+                        contents: grapheme.into(),
                         double: false,
                         style,
                     },
@@ -312,7 +365,7 @@ impl Output for Line {
                 let old_first = std::mem::replace(
                     &mut self.cells[x].0,
                     CellInner::Char {
-                        contents: iter::once(c).collect(),
+                        contents: grapheme.into(),
                         double: true,
                         style,
                     },
@@ -342,7 +395,18 @@ impl Output for Line {
                     };
                 }
             }
-            Some(_) => unreachable!(),
+            // Cells are at most two columns wide, so clusters that are wider are written as their
+            // parts, which is how the terminal displays them.
+            Some(width) => {
+                if x + width > self.cells.len() {
+                    return;
+                }
+                let mut x = pos.x;
+                for part in width::split_cluster(grapheme) {
+                    self.write_grapheme(Vec2::new(x, 0), part, style.clone());
+                    x += width::grapheme_width(part).unwrap_or(0) as u16;
+                }
+            }
             None => {}
         };
     }
@@ -366,7 +430,7 @@ impl Cell {
             } => CellKind::Char {
                 contents: &contents,
                 double: *double,
-                style: *style,
+                style,
             },
             CellInner::Continuation => CellKind::Continuation,
         }
@@ -404,9 +468,9 @@ impl Cell {
 
     /// Get the style of cell, if it is not a continuation cell.
     #[must_use]
-    pub fn style(&self) -> Option<Style> {
+    pub fn style(&self) -> Option<&Style> {
         match &self.0 {
-            CellInner::Char { style, .. } => Some(*style),
+            CellInner::Char { style, .. } => Some(style),
             _ => None,
         }
     }
@@ -415,19 +479,19 @@ impl Cell {
 /// A kind of cell.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CellKind<'a> {
-    /// The cell contains a 1-2 width character followed by any number of zero-width characters.
+    /// The cell contains a 1-2 width grapheme cluster, possibly followed by zero-width characters.
     Char {
-        /// The characters in the cell. The first character will be 1-2 columns wide, and the rest
-        /// of the characters will be zero columns wide. This will contain no control characters.
+        /// The characters in the cell. This is an extended grapheme cluster 1-2 columns wide,
+        /// followed by any number of zero-width characters written into the cell afterwards. This
+        /// will contain no control characters.
         contents: &'a str,
-        /// Whether the cell is double-width (that is, the width of the first character of
-        /// [`contents`](Self::Char::contents) as well as all of [`contents`](Self::Char::contents)
-        /// is 2).
+        /// Whether the cell is double-width (that is, the width of the grapheme cluster at the
+        /// start of [`contents`](Self::Char::contents) is 2).
         ///
         /// If a cell is double width the next cell will be a [`Continuation`](Self::Continuation).
         double: bool,
         /// The style of the cell.
-        style: Style,
+        style: &'a Style,
     },
     /// The cell is a continuation of the previous double-width cell.
     Continuation,
@@ -539,6 +603,61 @@ fn test_line() {
     assert_eq!(line.contents(), " a ");
 }
 
+#[cfg(test)]
+#[test]
+fn test_graphemes() {
+    use crate::output::Ext as _;
+
+    let mut grid = Grid::new((8, 1));
+    grid.write((0, 0), "🇬🇧e\u{301}👍🏽\u{7}x", Style::default());
+
+    let cells = grid.lines()[0].cells();
+    let contents: Vec<_> = cells.iter().map(Cell::contents).collect();
+    assert_eq!(
+        contents,
+        [
+            Some("🇬🇧"),
+            None,
+            Some("e\u{301}"),
+            Some("👍🏽"),
+            None,
+            Some("x"),
+            Some(" "),
+            Some(" "),
+        ]
+    );
+    assert!(cells[0].is_double());
+    assert!(!cells[2].is_double());
+
+    // Zero-width characters written on their own are added to the cell before them.
+    grid.write_char(Vec2::new(5, 0), '\u{301}', Style::default());
+    assert_eq!(grid.lines()[0].cells()[5].contents(), Some("x\u{301}"));
+
+    // Overwriting part of a double-width cluster clears the rest of it.
+    grid.write_grapheme(Vec2::new(1, 0), "a", Style::default());
+    assert_eq!(grid.lines()[0].contents(), " ae\u{301}👍🏽x\u{301}  ");
+
+    // Clusters measured wider than a cell are split into their parts.
+    let mut table = width::WidthTable::new();
+    table.insert("👨\u{200d}👩\u{200d}👧", 6);
+    let _widths = width::Scope::enter(std::sync::Arc::new(table));
+    let mut grid = Grid::new((7, 1));
+    grid.write((0, 0), "👨\u{200d}👩\u{200d}👧x", Style::default());
+    let contents: Vec<_> = grid.lines()[0].cells().iter().map(Cell::contents).collect();
+    assert_eq!(
+        contents,
+        [
+            Some("👨\u{200d}"),
+            None,
+            Some("👩\u{200d}"),
+            None,
+            Some("👧"),
+            None,
+            Some("x"),
+        ]
+    );
+}
+
 #[cfg(test)]
 #[test]
 fn test_resize_anchor() {
@@ -584,3 +703,23 @@ fn test_resize_anchor() {
     assert_eq!(grid.lines()[1].cells()[0].contents(), Some("2"));
     assert_eq!(grid.lines()[2].cells()[0].contents(), Some("3"));
 }
+
+#[cfg(test)]
+#[test]
+fn test_scroll() {
+    use crate::output::Ext as _;
+
+    let mut grid = Grid::new((1, 5));
+    for i in 0..5 {
+        grid.write((0, i), i, Style::default());
+    }
+
+    grid.scroll(1..4, 1);
+    assert_eq!(grid.contents(), ["0", "2", "3", " ", "4"]);
+
+    grid.scroll(0..5, -2);
+    assert_eq!(grid.contents(), [" ", " ", "0", "2", "3"]);
+
+    grid.scroll(3..10, 100);
+    assert_eq!(grid.contents(), [" ", " ", "0", " ", " "]);
+}
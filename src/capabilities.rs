@@ -0,0 +1,372 @@
+//! Detecting what terminals support.
+//!
+//! Terminals differ in how many colors they can display and which escape sequences they
+//! understand, and there isn't a reliable way to ask them. Instead [`Capabilities::detect`] guesses
+//! from the environment: the `NO_COLOR`, `COLORTERM` and `TERM` environment variables and the
+//! terminfo entry of the terminal.
+
+use std::env;
+#[cfg(unix)]
+use std::fs;
+#[cfg(unix)]
+use std::path::PathBuf;
+
+use crate::{Color, Style, UnderlineStyle};
+
+/// What a terminal supports.
+///
+/// A terminal gets the capabilities of its backend when it is created, and draws using only what
+/// they allow: colors the terminal can't display are replaced with the nearest ones it can, and
+/// unsupported attributes are left out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// The colors the terminal can display.
+    pub colors: ColorSupport,
+    /// Whether the terminal can display italic text.
+    pub italic: bool,
+    /// Whether the terminal can display curly underlines. Terminals that can are assumed to also
+    /// support the other underline styles and colored underlines.
+    pub undercurl: bool,
+    /// Whether the terminal reports mouse events.
+    pub mouse: bool,
+    /// Whether the terminal has a title that can be set.
+    pub title: bool,
+    /// Whether the terminal can repeat a character with REP and erase characters with ECH.
+    pub repeat: bool,
+}
+
+impl Capabilities {
+    /// Capabilities of a terminal that supports everything.
+    #[must_use]
+    pub const fn all() -> Self {
+        Self {
+            colors: ColorSupport::TrueColor,
+            italic: true,
+            undercurl: true,
+            mouse: true,
+            title: true,
+            repeat: true,
+        }
+    }
+
+    /// Detect the capabilities of the terminal from the environment.
+    #[must_use]
+    pub fn detect() -> Self {
+        Self::from_env(|name| env::var(name).ok())
+    }
+
+    /// Detect the capabilities of a terminal given a function to get environment variables.
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").filter(|term| !term.is_empty());
+        let term = match term {
+            Some(term) => term,
+            // Windows consoles don't set `TERM`, and have supported everything since Windows 10.
+            None if cfg!(windows) => return Self::all().without_color(&var),
+            None => String::new(),
+        };
+
+        if term == "dumb" {
+            return Self {
+                colors: ColorSupport::Monochrome,
+                italic: false,
+                undercurl: false,
+                mouse: false,
+                title: false,
+                repeat: false,
+            };
+        }
+
+        let terminfo = Terminfo::find(&term);
+        let console = term == "linux" || term.starts_with("vt") || term.is_empty();
+
+        let colors = match var("COLORTERM").as_deref() {
+            Some("truecolor" | "24bit") => ColorSupport::TrueColor,
+            _ if term.ends_with("-direct") => ColorSupport::TrueColor,
+            _ => match terminfo.as_ref().and_then(|terminfo| terminfo.colors) {
+                Some(colors) => ColorSupport::from_count(colors),
+                None if term.contains("256color") => ColorSupport::Ansi256,
+                None => ColorSupport::Ansi16,
+            },
+        };
+
+        let undercurl = ["kitty", "wezterm", "foot", "contour"]
+            .iter()
+            .any(|name| term.contains(name))
+            || var("VTE_VERSION").is_some();
+
+        Self {
+            colors,
+            italic: terminfo.map_or(!console, |terminfo| terminfo.italic),
+            undercurl,
+            mouse: !console,
+            title: !console,
+            repeat: !console,
+        }
+        .without_color(&var)
+    }
+
+    /// Remove colors if the user asked for them to be with `NO_COLOR`.
+    fn without_color(mut self, var: impl Fn(&str) -> Option<String>) -> Self {
+        if matches!(var("NO_COLOR"), Some(value) if !value.is_empty()) {
+            self.colors = ColorSupport::Monochrome;
+        }
+        self
+    }
+
+    /// Restrict a style to what the terminal supports.
+    #[must_use]
+    pub fn restrict(self, mut style: Style) -> Style {
+        style.foreground = style.foreground.downsample(self.colors);
+        style.background = style.background.downsample(self.colors);
+        style.underline_color = style.underline_color.downsample(self.colors);
+        if !self.italic {
+            style.attributes.italic = false;
+        }
+        if !self.undercurl {
+            style.attributes.underline_style = UnderlineStyle::Straight;
+            style.underline_color = Color::Default;
+        }
+        style
+    }
+}
+
+/// The colors a terminal can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorSupport {
+    /// No colors, only the terminal's default colors.
+    Monochrome,
+    /// The 16 named colors.
+    Ansi16,
+    /// The 256 ANSI colors, which include the named colors.
+    Ansi256,
+    /// Any 24-bit RGB color.
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Get the color support of a terminal that can display the given number of colors.
+    fn from_count(colors: u32) -> Self {
+        match colors {
+            0..=7 => Self::Monochrome,
+            8..=255 => Self::Ansi16,
+            256..=0xFF_FFFF => Self::Ansi256,
+            _ => Self::TrueColor,
+        }
+    }
+}
+
+/// The parts of a terminfo entry we're interested in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Terminfo {
+    /// The `colors` number capability.
+    colors: Option<u32>,
+    /// Whether the `sitm` string capability is present.
+    italic: bool,
+}
+
+impl Terminfo {
+    /// Find and read the terminfo entry of a terminal.
+    #[cfg(unix)]
+    fn find(term: &str) -> Option<Self> {
+        let first = term.chars().next()?;
+
+        let mut dirs = Vec::new();
+        if let Some(dir) = env::var_os("TERMINFO") {
+            dirs.push(PathBuf::from(dir));
+        }
+        if let Some(home) = env::var_os("HOME") {
+            dirs.push(PathBuf::from(home).join(".terminfo"));
+        }
+        if let Ok(list) = env::var("TERMINFO_DIRS") {
+            dirs.extend(
+                list.split(':')
+                    .filter(|dir| !dir.is_empty())
+                    .map(PathBuf::from),
+            );
+        }
+        dirs.extend(
+            [
+                "/etc/terminfo",
+                "/lib/terminfo",
+                "/usr/share/terminfo",
+                "/usr/lib/terminfo",
+            ]
+            .iter()
+            .map(PathBuf::from),
+        );
+
+        dirs.iter()
+            .flat_map(|dir| {
+                // Entries are in a directory named after their first letter, or its hexadecimal
+                // value on macOS.
+                vec![
+                    dir.join(first.to_string()).join(term),
+                    dir.join(format!("{:x}", u32::from(first))).join(term),
+                ]
+            })
+            .find_map(|path| fs::read(path).ok())
+            .and_then(|bytes| Self::parse(&bytes))
+    }
+    #[cfg(not(unix))]
+    fn find(_term: &str) -> Option<Self> {
+        None
+    }
+
+    /// Parse a compiled terminfo entry, as described in `term(5)`.
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        /// The index of `colors` in the numbers section.
+        const COLORS: usize = 13;
+        /// The index of `sitm` in the strings section.
+        const ENTER_ITALICS_MODE: usize = 311;
+
+        let header = |i: usize| -> Option<usize> {
+            let bytes = bytes.get(i * 2..i * 2 + 2)?;
+            let value = i16::from_le_bytes([bytes[0], bytes[1]]);
+            Some(if value < 0 { 0 } else { value as usize })
+        };
+
+        let number_size = match header(0)? {
+            0o432 => 2,
+            0o1036 => 4,
+            _ => return None,
+        };
+        let (names, bools, numbers, strings) = (header(1)?, header(2)?, header(3)?, header(4)?);
+
+        let mut offset = 12 + names + bools;
+        // The numbers section is aligned to an even byte.
+        offset += offset % 2;
+
+        let colors = if COLORS < numbers {
+            let start = offset + COLORS * number_size;
+            let number = bytes.get(start..start + number_size)?;
+            let value = if number_size == 2 {
+                i32::from(i16::from_le_bytes([number[0], number[1]]))
+            } else {
+                i32::from_le_bytes([number[0], number[1], number[2], number[3]])
+            };
+            if value < 0 {
+                None
+            } else {
+                Some(value as u32)
+            }
+        } else {
+            None
+        };
+        offset += numbers * number_size;
+
+        let italic = ENTER_ITALICS_MODE < strings && {
+            let start = offset + ENTER_ITALICS_MODE * 2;
+            let string = bytes.get(start..start + 2)?;
+            i16::from_le_bytes([string[0], string[1]]) >= 0
+        };
+
+        Some(Self { colors, italic })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_detect() {
+    fn detect(vars: &[(&str, &str)]) -> Capabilities {
+        Capabilities::from_env(|name| {
+            vars.iter()
+                .find(|&&(var, _)| var == name)
+                .map(|&(_, value)| value.to_owned())
+        })
+    }
+
+    let truecolor = detect(&[("TERM", "xterm-256color"), ("COLORTERM", "truecolor")]);
+    assert_eq!(truecolor.colors, ColorSupport::TrueColor);
+    assert!(truecolor.mouse);
+    assert!(truecolor.title);
+    assert!(truecolor.repeat);
+
+    let no_color = detect(&[
+        ("TERM", "xterm-256color"),
+        ("COLORTERM", "truecolor"),
+        ("NO_COLOR", "1"),
+    ]);
+    assert_eq!(no_color.colors, ColorSupport::Monochrome);
+
+    let dumb = detect(&[("TERM", "dumb"), ("COLORTERM", "truecolor")]);
+    assert_eq!(dumb.colors, ColorSupport::Monochrome);
+    assert!(!dumb.mouse);
+
+    let console = detect(&[("TERM", "linux")]);
+    assert!(console.colors <= ColorSupport::Ansi16);
+    assert!(!console.title);
+    assert!(!console.italic);
+    assert!(!console.repeat);
+
+    assert_eq!(
+        detect(&[("TERM", "xterm-kitty"), ("COLORTERM", "24bit")]),
+        Capabilities::all()
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_terminfo() {
+    use std::convert::TryFrom;
+
+    fn entry(magic: i16, numbers: &[i32], strings: &[i16]) -> Vec<u8> {
+        let len = |len: usize| i16::try_from(len).unwrap();
+        let number_size = if magic == 0o432 { 2 } else { 4 };
+        let names = b"test|a test terminal\0";
+        let mut bytes = Vec::new();
+        for &value in &[
+            magic,
+            len(names.len()),
+            2,
+            len(numbers.len()),
+            len(strings.len()),
+            0,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(names);
+        bytes.extend_from_slice(&[1, 0]);
+        if bytes.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        for &number in numbers {
+            bytes.extend_from_slice(&number.to_le_bytes()[..number_size]);
+        }
+        for &string in strings {
+            bytes.extend_from_slice(&string.to_le_bytes());
+        }
+        bytes
+    }
+
+    let mut numbers = vec![-1; 14];
+    numbers[13] = 256;
+    let mut strings = vec![-1; 312];
+
+    assert_eq!(
+        Terminfo::parse(&entry(0o432, &numbers, &strings)),
+        Some(Terminfo {
+            colors: Some(256),
+            italic: false,
+        })
+    );
+
+    numbers[13] = 0x100_0000;
+    strings[311] = 0;
+    assert_eq!(
+        Terminfo::parse(&entry(0o1036, &numbers, &strings)),
+        Some(Terminfo {
+            colors: Some(0x100_0000),
+            italic: true,
+        })
+    );
+
+    assert_eq!(
+        Terminfo::parse(&entry(0o432, &numbers[..5], &[])),
+        Some(Terminfo {
+            colors: None,
+            italic: false,
+        })
+    );
+    assert_eq!(Terminfo::parse(b"not terminfo"), None);
+}
@@ -32,7 +32,7 @@ impl<Event> Element for Block<Event> {
 
             for x in 0..size.x {
                 for y in 0..size.y {
-                    output.write_char(Vec2 { x, y }, ' ', style);
+                    output.write_char(Vec2 { x, y }, ' ', style.clone());
                 }
             }
         }
@@ -83,7 +83,7 @@ fn test_block() {
                 crate::CellKind::Char {
                     contents: " ",
                     double: false,
-                    style: Style::default().on_red(),
+                    style: &Style::default().on_red(),
                 },
             );
         }
@@ -1,6 +1,7 @@
 use std::cmp;
 use std::fmt::{self, Debug, Formatter};
 
+use crate::input::Binding;
 use crate::output::{Ext as _, Output};
 use crate::{Element, Events, Input, Vec2};
 
@@ -66,8 +67,8 @@ pub struct Container1D<E, L> {
     pub layout: L,
     /// The axis of the container.
     pub axis: Axis,
-    /// Whether to broadcast key inputs to all elements. If `false`, only the focused element will
-    /// receive key inputs.
+    /// Whether to broadcast key inputs and pastes to all elements. If `false`, only the focused
+    /// element will receive them. Focus changes are always broadcast.
     pub broadcast_keys: bool,
     /// The index of the focused element of the container. This element will set the title and
     /// cursor of the container, and will receive all inputs if
@@ -139,6 +140,15 @@ where
         }
         Ok(())
     }
+    fn bindings(&self, bindings: &mut Vec<Binding>) {
+        if self.broadcast_keys {
+            for element in self.elements.iter() {
+                element.bindings(bindings);
+            }
+        } else if let Some(element) = self.focused.and_then(|i| self.elements.iter().nth(i)) {
+            element.bindings(bindings);
+        }
+    }
     fn ideal_width(&self, height: u16, max_width: Option<u16>) -> u16 {
         match self.axis {
             Axis::X => self
@@ -183,16 +193,21 @@ where
     }
     fn handle(&self, input: Input, events: &mut dyn Events<Event>) {
         match input {
-            Input::Key(_) if self.broadcast_keys => {
+            Input::Key(_) | Input::Paste(_) if self.broadcast_keys => {
                 for element in self.elements.iter() {
-                    element.handle(input, events);
+                    element.handle(input.clone(), events);
                 }
             }
-            Input::Key(_) => {
+            Input::Key(_) | Input::Paste(_) => {
                 if let Some(element) = self.focused.and_then(|i| self.elements.iter().nth(i)) {
                     element.handle(input, events);
                 }
             }
+            Input::Focus(_) => {
+                for element in self.elements.iter() {
+                    element.handle(input.clone(), events);
+                }
+            }
             Input::Mouse(mouse) => {
                 let (mouse_main_axis, mouse_cross_axis) = self.axis.main_cross_of(mouse.at);
                 let (main_axis_size, cross_axis_size) = self.axis.main_cross_of(mouse.size);
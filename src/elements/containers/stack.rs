@@ -1,5 +1,6 @@
 use std::fmt;
 
+use crate::input::Binding;
 use crate::{Element, Events, Input, Output, Vec2};
 
 use super::Collection;
@@ -13,7 +14,8 @@ use super::Collection;
 pub struct Stack<E> {
     /// The elements in this container.
     pub elements: E,
-    /// Whether to broadcast inputs to all elements instead of just the top one.
+    /// Whether to broadcast inputs to all elements instead of just the top one. Focus changes are
+    /// always broadcast.
     pub broadcast_inputs: bool,
 }
 
@@ -60,9 +62,9 @@ where
             .fold(Vec2::default(), Vec2::max)
     }
     fn handle(&self, input: Input, events: &mut dyn Events<Event>) {
-        if self.broadcast_inputs {
+        if self.broadcast_inputs || matches!(input, Input::Focus(_)) {
             for element in self.elements.iter() {
-                element.handle(input, events);
+                element.handle(input.clone(), events);
             }
         } else if let Some(last) = self.elements.iter().next_back() {
             last.handle(input, events);
@@ -74,6 +76,15 @@ where
         }
         Ok(())
     }
+    fn bindings(&self, bindings: &mut Vec<Binding>) {
+        if self.broadcast_inputs {
+            for element in self.elements.iter() {
+                element.bindings(bindings);
+            }
+        } else if let Some(last) = self.elements.iter().next_back() {
+            last.bindings(bindings);
+        }
+    }
 }
 
 /// Create a [`Stack`] of elements.
@@ -0,0 +1,42 @@
+use std::time::{Duration, Instant};
+
+use crate::{Element, Output};
+
+use super::Filter;
+
+/// A filter that draws an element again after an interval, typically used through the
+/// [`animate`](crate::ElementExt::animate) method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Animate {
+    /// How long to wait before drawing the element again. [`None`] doesn't animate the element.
+    pub interval: Option<Duration>,
+}
+
+impl<Event> Filter<Event> for Animate {
+    fn draw<E: Element>(&self, element: E, output: &mut dyn Output) {
+        element.draw(output);
+        if let Some(interval) = self.interval {
+            output.request_frame_at(Instant::now() + interval);
+        }
+    }
+}
+
+#[test]
+fn test_animate() {
+    use crate::{Buffer, ElementExt, Grid};
+
+    let mut buffer = Buffer::from(Grid::new((1, 1)));
+    crate::span::<_, ()>('x').animate(None).draw(&mut buffer);
+    assert_eq!(buffer.next_frame, None);
+
+    let before = Instant::now();
+    crate::span::<_, ()>('x')
+        .animate(Duration::from_secs(2))
+        .draw(&mut buffer);
+    crate::span::<_, ()>('x')
+        .animate(Duration::from_secs(1))
+        .draw(&mut buffer);
+    let next_frame = buffer.next_frame.unwrap();
+    assert!(next_frame >= before + Duration::from_secs(1));
+    assert!(next_frame < before + Duration::from_secs(2));
+}
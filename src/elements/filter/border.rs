@@ -1,6 +1,6 @@
-use std::fmt;
+use std::cmp::min;
 
-use unicode_width::UnicodeWidthChar;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
     output::{Ext as _, Output},
@@ -10,7 +10,7 @@ use crate::{
 use super::{Alignment, Filter};
 
 /// A filter that adds a border to an element.
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
 #[non_exhaustive]
 pub struct Border {
     /// The characters that make up the sides of the border, in the order of top, left, right,
@@ -219,27 +219,27 @@ impl<Event> Filter<Event> for Border {
         // Fill the padding.
         if self.padding {
             for y in 1..output_size.y.saturating_sub(1) {
-                output.write_char(Vec2::new(1, y), ' ', self.style);
+                output.write_char(Vec2::new(1, y), ' ', self.style.clone());
                 if let Some(right_border) = right_border {
-                    output.write_char(Vec2::new(right_border - 1, y), ' ', self.style);
+                    output.write_char(Vec2::new(right_border - 1, y), ' ', self.style.clone());
                 }
             }
         }
 
         // Write corners
         let (top_left, top_right, bottom_left, bottom_right) = self.corners;
-        output.write_char(Vec2::new(0, 0), top_left, self.style);
+        output.write_char(Vec2::new(0, 0), top_left, self.style.clone());
         if let Some(right_border) = right_border {
-            output.write_char(Vec2::new(right_border, 0), top_right, self.style);
+            output.write_char(Vec2::new(right_border, 0), top_right, self.style.clone());
         }
         if let Some(bottom_border) = bottom_border {
-            output.write_char(Vec2::new(0, bottom_border), bottom_left, self.style);
+            output.write_char(Vec2::new(0, bottom_border), bottom_left, self.style.clone());
         }
         if let (Some(right_border), Some(bottom_border)) = (right_border, bottom_border) {
             output.write_char(
                 Vec2::new(right_border, bottom_border),
                 bottom_right,
-                self.style,
+                self.style.clone(),
             );
         }
 
@@ -247,21 +247,22 @@ impl<Event> Filter<Event> for Border {
 
         // Write both sides
         for y in 1..output_size.y.saturating_sub(1) {
-            output.write_char(Vec2::new(0, y), left, self.style);
+            output.write_char(Vec2::new(0, y), left, self.style.clone());
             if let Some(right_border) = right_border {
-                output.write_char(Vec2::new(right_border, y), right, self.style);
+                output.write_char(Vec2::new(right_border, y), right, self.style.clone());
             }
         }
 
+        // The title is only needed when it is drawn on the top or bottom border.
+        let mut title = String::new();
+        if self.top_title_align.is_some() || self.bottom_title_align.is_some() {
+            let _ = element.title(&mut title);
+        }
+
         // Get the title width, is lazy because only when one of the top title and bottom title is
         // aligned to the center or right is this needed.
         let mut title_width = crate::util::Lazy::new(|| {
-            let mut width: u16 = 0;
-            let _ = element.title(&mut crate::util::WriteCharsFn(|c| {
-                width = width.saturating_add(c.width().unwrap_or(0) as u16);
-                Ok(())
-            }));
-            width
+            min(crate::width::str_width(&title), usize::from(u16::MAX)) as u16
         });
 
         let available_width = output_size.x.saturating_sub(2);
@@ -281,45 +282,46 @@ impl<Event> Filter<Event> for Border {
         let mut offset_top = title_start_top;
         let mut offset_bottom = title_start_bottom;
 
-        // Draw the title
-        if offset_top.is_some() || offset_bottom.is_some() {
-            let _ = element.title(&mut crate::util::WriteCharsFn(|c| {
-                let width = match c.width() {
-                    Some(width) => width,
-                    None => return Ok(()),
-                } as u16;
-
-                if let Some(offset) = &mut offset_top {
-                    let after = offset.checked_add(width).ok_or(fmt::Error)?;
-                    if Some(after) > right_border {
-                        return Err(fmt::Error);
-                    }
-                    output.write_char(Vec2::new(*offset, 0), c, self.title_style);
-                    *offset = after;
-                }
-
-                if let (Some(offset), Some(y)) = (&mut offset_bottom, bottom_border) {
-                    let after = offset.checked_add(width).ok_or(fmt::Error)?;
-                    if Some(after) > right_border {
-                        return Err(fmt::Error);
-                    }
-                    output.write_char(Vec2::new(*offset, y), c, self.title_style);
-                    *offset = after;
-                }
+        // Draw the title, stopping at the first cluster that doesn't fit.
+        for grapheme in title.graphemes(true) {
+            let width = match crate::width::grapheme_width(grapheme) {
+                Some(width) => width,
+                None => continue,
+            } as u16;
+            let fits = |offset: u16| {
+                offset
+                    .checked_add(width)
+                    .filter(|&after| Some(after) <= right_border)
+            };
+
+            if let Some(offset) = &mut offset_top {
+                let after = match fits(*offset) {
+                    Some(after) => after,
+                    None => break,
+                };
+                output.write_grapheme(Vec2::new(*offset, 0), grapheme, self.title_style.clone());
+                *offset = after;
+            }
 
-                Ok(())
-            }));
+            if let (Some(offset), Some(y)) = (&mut offset_bottom, bottom_border) {
+                let after = match fits(*offset) {
+                    Some(after) => after,
+                    None => break,
+                };
+                output.write_grapheme(Vec2::new(*offset, y), grapheme, self.title_style.clone());
+                *offset = after;
+            }
         }
 
         // Write top and bottom borders, not overwriting the title
         for x in 1..output_size.x.saturating_sub(1) {
             if title_start_top.map_or(true, |start| x < start || x >= offset_top.unwrap()) {
-                output.write_char(Vec2::new(x, 0), top, self.style);
+                output.write_char(Vec2::new(x, 0), top, self.style.clone());
             }
             if let Some(y) = bottom_border {
                 if title_start_bottom.map_or(true, |start| x < start || x >= offset_bottom.unwrap())
                 {
-                    output.write_char(Vec2::new(x, y), bottom, self.style);
+                    output.write_char(Vec2::new(x, y), bottom, self.style.clone());
                 }
             }
         }
@@ -355,7 +357,7 @@ impl<Event> Filter<Event> for Border {
         events: &mut dyn Events<Event>,
     ) {
         let input = match input {
-            Input::Key(key) => Some(Input::Key(key)),
+            Input::Key(_) | Input::Paste(_) | Input::Focus(_) => Some(input),
             Input::Mouse(mouse) => (|| {
                 let xborder = if self.padding { 2 } else { 1 };
 
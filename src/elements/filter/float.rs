@@ -77,7 +77,7 @@ impl<Event> Filter<Event> for Float {
         events: &mut dyn Events<Event>,
     ) {
         let input = match input {
-            Input::Key(key) => Some(Input::Key(key)),
+            Input::Key(_) | Input::Paste(_) | Input::Focus(_) => Some(input),
             Input::Mouse(mouse) => {
                 let (offset, size) = self.calculate_layout(&element, mouse.size);
 
@@ -16,7 +16,7 @@ impl<P: input::Pattern, Event> Filter<Event> for InputMask<P> {
         input: Input,
         events: &mut dyn Events<Event>,
     ) {
-        if self.pattern.matches(input) {
+        if self.pattern.matches(&input) {
             element.handle(input, events);
         }
     }
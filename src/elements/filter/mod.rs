@@ -6,9 +6,14 @@
 
 use std::fmt;
 use std::marker::PhantomData;
+use std::time::Instant;
 
+use crate::input::Binding;
 use crate::output::Output;
-use crate::{Cursor, Element, Events, Input, KeyPress, Mouse, Style, Vec2};
+use crate::{Cursor, Element, Events, Input, KeyPress, Mouse, Request, Style, Vec2};
+
+mod animate;
+pub use animate::*;
 
 mod border;
 pub use border::*;
@@ -25,6 +30,9 @@ pub use input_mask::*;
 mod on;
 pub use on::*;
 
+mod on_sequence;
+pub use on_sequence::*;
+
 mod ratio;
 pub use ratio::*;
 
@@ -44,8 +52,8 @@ pub use title::*;
 pub trait Filter<Event> {
     /// Draw the filtered element to the output.
     ///
-    /// By default this method forwards to [`write_char`](Self::write_char) and
-    /// [`set_cursor`](Self::set_cursor).
+    /// By default this method forwards to [`write_char`](Self::write_char),
+    /// [`write_grapheme`](Self::write_grapheme) and [`set_cursor`](Self::set_cursor).
     fn draw<E: Element>(&self, element: E, output: &mut dyn Output) {
         struct DrawFilterOutput<'a, F: ?Sized, Event> {
             inner: &'a mut dyn Output,
@@ -59,9 +67,18 @@ pub trait Filter<Event> {
             fn write_char(&mut self, pos: Vec2<u16>, c: char, style: Style) {
                 self.filter.write_char(self.inner, pos, c, style);
             }
+            fn write_grapheme(&mut self, pos: Vec2<u16>, grapheme: &str, style: Style) {
+                self.filter.write_grapheme(self.inner, pos, grapheme, style);
+            }
             fn set_cursor(&mut self, cursor: Option<Cursor>) {
                 self.filter.set_cursor(self.inner, cursor);
             }
+            fn request_frame_at(&mut self, at: Instant) {
+                self.inner.request_frame_at(at);
+            }
+            fn request(&mut self, request: Request) {
+                self.inner.request(request);
+            }
         }
 
         element.draw(&mut DrawFilterOutput {
@@ -79,6 +96,26 @@ pub trait Filter<Event> {
         base.write_char(pos, self.filter_char(c), self.filter_style(style));
     }
 
+    /// Write a single filtered extended grapheme cluster to the output.
+    ///
+    /// By default clusters of a single character are written with
+    /// [`write_char`](Self::write_char). Other clusters are written with their style filtered with
+    /// [`filter_style`](Self::filter_style) if [`filter_char`](Self::filter_char) leaves their
+    /// first character unchanged, and otherwise each of their characters is written with
+    /// [`write_char`](Self::write_char) at the same position.
+    fn write_grapheme(&self, base: &mut dyn Output, pos: Vec2<u16>, grapheme: &str, style: Style) {
+        let mut chars = grapheme.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => self.write_char(base, pos, c, style),
+            (Some(c), Some(_)) if self.filter_char(c) != c => {
+                for c in grapheme.chars() {
+                    self.write_char(base, pos, c, style.clone());
+                }
+            }
+            _ => base.write_grapheme(pos, grapheme, self.filter_style(style)),
+        }
+    }
+
     /// Filter the value of a character being written to the output.
     ///
     /// By default this returns the character.
@@ -120,6 +157,13 @@ pub trait Filter<Event> {
         element.title(title)
     }
 
+    /// Report the labelled inputs the filtered element responds to.
+    ///
+    /// By default this reports the [bindings](Element::bindings) of the element.
+    fn bindings<E: Element>(&self, element: E, bindings: &mut Vec<Binding>) {
+        element.bindings(bindings);
+    }
+
     /// Get the ideal width the element takes up given a fixed height and optional maximum width.
     ///
     /// By default this calls the element's [`ideal_width`](Element::ideal_width) method.
@@ -156,11 +200,12 @@ pub trait Filter<Event> {
     /// Filter inputs given to the wrapped element.
     ///
     /// By default this forwards to [`filter_key_press`](Self::filter_key_press) and
-    /// [`filter_mouse`](Self::filter_mouse).
+    /// [`filter_mouse`](Self::filter_mouse), and leaves other inputs unchanged.
     fn filter_input(&self, input: Input) -> Input {
         match input {
             Input::Key(key) => Input::Key(self.filter_key_press(key)),
             Input::Mouse(mouse) => Input::Mouse(self.filter_mouse(mouse)),
+            Input::Paste(_) | Input::Focus(_) => input,
         }
     }
 
@@ -205,6 +250,9 @@ impl<T: Element, F: Filter<T::Event>> Element for Filtered<T, F> {
     fn title(&self, title: &mut dyn fmt::Write) -> fmt::Result {
         self.filter.title(&self.element, title)
     }
+    fn bindings(&self, bindings: &mut Vec<Binding>) {
+        self.filter.bindings(&self.element, bindings);
+    }
     fn ideal_width(&self, height: u16, max_width: Option<u16>) -> u16 {
         self.filter.ideal_width(&self.element, height, max_width)
     }
@@ -229,3 +277,42 @@ pub enum Alignment {
     /// Aligned to the end of the container.
     End,
 }
+
+#[test]
+fn test_write_grapheme() {
+    use crate::buffer::Grid;
+
+    struct Mask;
+    impl Filter<()> for Mask {
+        fn filter_char(&self, _: char) -> char {
+            '*'
+        }
+    }
+    struct Upper;
+    impl Filter<()> for Upper {
+        fn filter_char(&self, c: char) -> char {
+            c.to_ascii_uppercase()
+        }
+    }
+
+    let mut grid = Grid::new((3, 1));
+    for (x, grapheme) in ["e\u{301}", "a\u{308}\u{323}", "b"].iter().enumerate() {
+        Mask.write_grapheme(
+            &mut grid,
+            Vec2::new(x as u16, 0),
+            grapheme,
+            Style::default(),
+        );
+    }
+    assert_eq!(grid.contents(), ["***"]);
+
+    let mut grid = Grid::new((2, 1));
+    Upper.write_grapheme(
+        &mut grid,
+        Vec2::new(0, 0),
+        "\u{e9}\u{301}",
+        Style::default(),
+    );
+    Upper.write_grapheme(&mut grid, Vec2::new(1, 0), "e\u{301}", Style::default());
+    assert_eq!(grid.contents(), ["\u{e9}\u{301}E\u{301}"]);
+}
@@ -1,6 +1,7 @@
-use crate::{input, Element, Events, Input};
+use crate::input::{self, Binding};
+use crate::{Element, Events, Input};
 
-use super::Filter;
+use super::{Filter, Filtered};
 
 /// A filter that triggers an event when an input occurs, typically used through the
 /// [`on`](crate::ElementExt::on) and [`on_passive`](crate::ElementExt::on_passive) methods.
@@ -14,6 +15,9 @@ pub struct On<I, F> {
     /// Whether it listens to inputs passively. If `true`, this type will not intercept inputs if
     /// it triggers its event.
     pub passive: bool,
+    /// What the event does, like `Quit`. If set, the input is reported as one of the element's
+    /// [bindings](Element::bindings).
+    pub label: Option<&'static str>,
 }
 
 impl<I, F> On<I, F> {
@@ -27,6 +31,7 @@ impl<I, F> On<I, F> {
             input_pattern,
             event,
             passive: false,
+            label: None,
         }
     }
 
@@ -38,6 +43,38 @@ impl<I, F> On<I, F> {
             ..self
         }
     }
+
+    /// Label what the event does, so that the input is reported as one of the element's
+    /// [bindings](Element::bindings).
+    #[must_use]
+    pub fn label(self, label: &'static str) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+}
+
+impl<T, I, F> Filtered<T, On<I, F>> {
+    /// Label what the event of the filter does, so that its input is reported as one of the
+    /// element's [bindings](Element::bindings).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use toon::ElementExt;
+    /// # let element = toon::empty();
+    /// # #[derive(Clone)]
+    /// # enum Event { Quit }
+    /// let element = element.on('q', |_| Event::Quit).label("Quit");
+    /// ```
+    #[must_use]
+    pub fn label(self, label: &'static str) -> Self {
+        Self {
+            filter: self.filter.label(label),
+            ..self
+        }
+    }
 }
 
 impl<I: input::Pattern, F: Fn(Input) -> Event, Event> Filter<Event> for On<I, F> {
@@ -47,12 +84,49 @@ impl<I: input::Pattern, F: Fn(Input) -> Event, Event> Filter<Event> for On<I, F>
         input: Input,
         events: &mut dyn Events<Event>,
     ) {
-        let matches = self.input_pattern.matches(input);
-        if matches {
+        if !self.input_pattern.matches(&input) {
+            element.handle(input, events);
+        } else if self.passive {
+            events.add((self.event)(input.clone()));
+            element.handle(input, events);
+        } else {
             events.add((self.event)(input));
         }
-        if self.passive || !matches {
-            element.handle(input, events);
+    }
+    fn bindings<E: Element>(&self, element: E, bindings: &mut Vec<Binding>) {
+        if let Some(label) = self.label {
+            bindings.push(Binding::new(&self.input_pattern, label));
         }
+        element.bindings(bindings);
     }
 }
+
+#[test]
+fn test_bindings() {
+    use crate::{ElementExt, Key};
+
+    let element = crate::column(
+        crate::stretch(1),
+        (
+            crate::empty().on('a', |_| ()).label("First"),
+            crate::empty().on('b', |_| ()).label("Second"),
+        ),
+    )
+    .focus(1)
+    .on(Key::Escape, |_| ())
+    .on(crate::input!(Control + Key(q)), |_| ())
+    .label("Quit")
+    .on_passive('?', |_| ())
+    .label("Help");
+
+    let mut bindings = Vec::new();
+    element.bindings(&mut bindings);
+    let bindings: Vec<_> = bindings
+        .iter()
+        .map(|binding| (binding.input.as_deref().unwrap(), &*binding.label))
+        .collect();
+    assert_eq!(
+        bindings,
+        [("?", "Help"), ("Control+q", "Quit"), ("b", "Second")]
+    );
+}
@@ -0,0 +1,200 @@
+use std::mem;
+
+use crate::events::Vector;
+use crate::input::{Binding, KeySequence, Patterns, SequenceMatch};
+use crate::{sequence, Element, Events, Input};
+
+use super::{Filter, Filtered};
+
+/// A filter that triggers an event when a sequence of inputs occurs, typically used through the
+/// [`on_sequence`](crate::ElementExt::on_sequence) method.
+///
+/// The inputs of a sequence that is in progress are not passed to the inner element. The
+/// [terminal](crate::Terminal) keeps them until the sequence is completed, the
+/// [sequence timeout](crate::Terminal::set_sequence_timeout) passes or an input that doesn't
+/// continue it occurs, in which case that input is handled as if no sequence was in progress.
+///
+/// When a sequence is the start of a longer one, like `g` and `g g`, the shorter sequence's event
+/// is only triggered once the timeout has passed without the longer one continuing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct OnSequence<P, F> {
+    /// The sequence this filter listens for.
+    pub sequence: KeySequence<P>,
+    /// The event function called with the last input of the sequence when it occurs.
+    pub event: F,
+    /// What the event does, like `Save`. If set, the sequence is reported as one of the element's
+    /// [bindings](Element::bindings).
+    pub label: Option<&'static str>,
+}
+
+impl<P, F> OnSequence<P, F> {
+    /// Create a new filter that triggers the event when the sequence occurs.
+    #[must_use]
+    pub const fn new(sequence: KeySequence<P>, event: F) -> Self {
+        Self {
+            sequence,
+            event,
+            label: None,
+        }
+    }
+
+    /// Label what the event does, so that the sequence is reported as one of the element's
+    /// [bindings](Element::bindings).
+    #[must_use]
+    pub fn label(self, label: &'static str) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+}
+
+impl<T, P, F> Filtered<T, OnSequence<P, F>> {
+    /// Label what the event of the filter does, so that its sequence is reported as one of the
+    /// element's [bindings](Element::bindings).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use toon::ElementExt;
+    /// # let element = toon::empty();
+    /// # #[derive(Clone)]
+    /// # enum Event { Top }
+    /// let element = element
+    ///     .on_sequence(toon::input_seq!(Key(g), Key(g)), |_| Event::Top)
+    ///     .label("Top");
+    /// ```
+    #[must_use]
+    pub fn label(self, label: &'static str) -> Self {
+        Self {
+            filter: self.filter.label(label),
+            ..self
+        }
+    }
+}
+
+impl<P: Patterns, F: Fn(Input) -> Event, Event> Filter<Event> for OnSequence<P, F> {
+    fn handle<E: Element<Event = Event>>(
+        &self,
+        element: E,
+        input: Input,
+        events: &mut dyn Events<Event>,
+    ) {
+        let (matched, restarted, timed_out) = sequence::with_state(|state| match state {
+            Some(state) => {
+                let matched = self.sequence.matches(&state.pending, &input);
+                state.matched |= matched != SequenceMatch::None;
+                let restarted = if state.matched || state.pending.is_empty() {
+                    SequenceMatch::None
+                } else {
+                    self.sequence.matches(&[], &input)
+                };
+                (matched, restarted, state.timed_out)
+            }
+            None => (
+                self.sequence.matches(&[], &input),
+                SequenceMatch::None,
+                false,
+            ),
+        });
+
+        if restarted != SequenceMatch::None {
+            // The input doesn't continue the sequence in progress but starts this one anew. Only
+            // abandon the sequence in progress for it if the inner element doesn't continue it.
+            let mut inner = Vector::new();
+            element.handle(input.clone(), &mut inner);
+            let abandoned = sequence::with_state(|state| match state {
+                Some(state) if !state.matched => {
+                    state.pending.clear();
+                    state.matched = true;
+                    state.continued = restarted == SequenceMatch::Prefix && !timed_out;
+                    true
+                }
+                _ => false,
+            });
+
+            match restarted {
+                SequenceMatch::Complete if abandoned => events.add((self.event)(input)),
+                SequenceMatch::Prefix if abandoned && !timed_out => {}
+                _ => forward(inner, events),
+            }
+            return;
+        }
+
+        match matched {
+            SequenceMatch::Prefix if !timed_out => sequence::with_state(|state| {
+                if let Some(state) = state {
+                    state.continued = true;
+                }
+            }),
+            SequenceMatch::Complete if !timed_out => {
+                // Wait for the timeout if the inner element is in the middle of a longer sequence.
+                let outer = sequence::with_state(|state| match state {
+                    Some(state) => mem::replace(&mut state.continued, false),
+                    None => false,
+                });
+                let mut inner = Vector::new();
+                element.handle(input.clone(), &mut inner);
+                let continued = sequence::with_state(|state| match state {
+                    Some(state) => {
+                        let continued = state.continued;
+                        state.continued |= outer;
+                        continued
+                    }
+                    None => false,
+                });
+
+                if continued {
+                    forward(inner, events);
+                } else {
+                    events.add((self.event)(input));
+                }
+            }
+            SequenceMatch::Complete => events.add((self.event)(input)),
+            SequenceMatch::Prefix | SequenceMatch::None => element.handle(input, events),
+        }
+    }
+    fn bindings<E: Element>(&self, element: E, bindings: &mut Vec<Binding>) {
+        if let Some(label) = self.label {
+            bindings.push(Binding {
+                input: self.sequence.describe(),
+                label: label.into(),
+            });
+        }
+        element.bindings(bindings);
+    }
+}
+
+/// Pass on the events and requests of an inner element.
+fn forward<Event>(inner: Vector<Event>, events: &mut dyn Events<Event>) {
+    for event in inner.events {
+        events.add(event);
+    }
+    for request in inner.requests {
+        events.request(request);
+    }
+}
+
+#[test]
+fn test_bindings() {
+    use crate::ElementExt;
+
+    let element = crate::empty()
+        .on_sequence(crate::input_seq!(Key(g), Key(g)), |_| ())
+        .label("Top")
+        .on_sequence(
+            crate::input_seq!(Control + Key(x), Control + Key(s)),
+            |_| (),
+        )
+        .label("Save")
+        .on_sequence(crate::input_seq!(Key(d), Key(d)), |_| ());
+
+    let mut bindings = Vec::new();
+    element.bindings(&mut bindings);
+    let bindings: Vec<_> = bindings
+        .iter()
+        .map(|binding| (binding.input.as_deref().unwrap(), &*binding.label))
+        .collect();
+    assert_eq!(bindings, [("Control+x Control+s", "Save"), ("g g", "Top")]);
+}
@@ -55,7 +55,7 @@ fn test_ratio() {
 
     let a = crate::span::<_, ()>("a").tile((0, 0));
 
-    a.ratio(2.).float((Middle, Middle)).draw(&mut grid);
+    a.clone().ratio(2.).float((Middle, Middle)).draw(&mut grid);
     assert_eq!(grid.contents(), ["     ", "aaaaa", "aaaaa", "aaaaa"]);
 
     grid.clear();
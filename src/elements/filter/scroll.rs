@@ -74,7 +74,7 @@ impl<Event> Filter<Event> for Scroll {
                         ..mouse
                     })
                 }
-                Input::Key(_) => input,
+                Input::Key(_) | Input::Paste(_) | Input::Focus(_) => input,
             },
             events,
         );
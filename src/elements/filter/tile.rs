@@ -76,7 +76,7 @@ impl<Event> Filter<Event> for Tile {
     ) {
         element.handle(
             match input {
-                Input::Key(key) => Input::Key(key),
+                Input::Key(_) | Input::Paste(_) | Input::Focus(_) => input,
                 Input::Mouse(mouse) => {
                     let (offset, size) = self.layout(&element, mouse.size);
 
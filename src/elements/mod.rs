@@ -4,8 +4,9 @@
 //! yourself.
 
 use std::fmt::Display;
+use std::time::Duration;
 
-use crate::{input, Color, Element, Input, Vec2};
+use crate::{input, Color, Element, Input, KeySequence, Vec2};
 
 pub mod containers;
 pub use containers::*;
@@ -73,6 +74,31 @@ pub trait ElementExt: Element + Sized {
         self.filter(On::new(input_pattern, event).passive())
     }
 
+    /// Trigger an event when a sequence of inputs occurs, such as `g g` or `Control+X Control+S`.
+    ///
+    /// See [`OnSequence`] for how sequences in progress and sequences that start longer ones are
+    /// handled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use toon::ElementExt;
+    /// # let element = toon::empty();
+    /// # #[derive(Clone)]
+    /// # enum Event { Save, Top }
+    /// let element = element
+    ///     .on_sequence(toon::input_seq!(Control + Key(x), Control + Key(s)), |_| Event::Save)
+    ///     .on_sequence(toon::input_seq!(Key(g), Key(g)), |_| Event::Top);
+    /// ```
+    #[must_use]
+    fn on_sequence<P: input::Patterns, F: Fn(Input) -> Self::Event>(
+        self,
+        sequence: KeySequence<P>,
+        event: F,
+    ) -> Filtered<Self, OnSequence<P, F>> {
+        self.filter(OnSequence::new(sequence, event))
+    }
+
     /// Make the element float in both axes with the given alignment.
     ///
     /// # Example
@@ -203,6 +229,15 @@ pub trait ElementExt: Element + Sized {
         self.filter(Tile::new(offset.into().map(Some)))
     }
 
+    /// Draw the element again after the given interval has passed, for elements that change over
+    /// time such as spinners and timers. [`None`] doesn't animate the element.
+    #[must_use]
+    fn animate(self, interval: impl Into<Option<Duration>>) -> Filtered<Self, Animate> {
+        self.filter(Animate {
+            interval: interval.into(),
+        })
+    }
+
     /// Fill the background of the element.
     #[must_use]
     fn fill_background(self, color: impl Into<Color>) -> Filtered<Self, FillBackground> {
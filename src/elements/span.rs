@@ -1,8 +1,7 @@
+use std::convert::TryFrom;
 use std::fmt::{Display, Write};
 use std::marker::PhantomData;
 
-use unicode_width::UnicodeWidthChar;
-
 use crate::{
     output::{Ext as _, Output},
     Element, Events, Input, Style, Vec2,
@@ -19,7 +18,7 @@ use crate::{
 ///
 /// let element: toon::Span<_, ()> = toon::span("Hello World").black().on_white();
 /// ```
-#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
 pub struct Span<T, Event> {
     /// The text being displayed.
     pub text: T,
@@ -34,8 +33,8 @@ impl<T: Display, Event> Span<T, Event> {
         let mut width = 0;
 
         write!(
-            crate::util::WriteCharsFn(|c| {
-                width += c.width().unwrap_or(0) as u16;
+            crate::util::WriteFn(|s| {
+                width += crate::width::str_width(s);
                 Ok(())
             }),
             "{}",
@@ -43,7 +42,7 @@ impl<T: Display, Event> Span<T, Event> {
         )
         .expect("formatting failed");
 
-        width
+        u16::try_from(width).unwrap_or(u16::MAX)
     }
 }
 
@@ -62,7 +61,7 @@ impl<T: Display, Event> Element for Span<T, Event> {
     type Event = Event;
 
     fn draw(&self, output: &mut dyn Output) {
-        output.write((0, 0), &self.text, self.style);
+        output.write((0, 0), &self.text, self.style.clone());
     }
     fn ideal_width(&self, _height: u16, _max_width: Option<u16>) -> u16 {
         self.width()
@@ -109,7 +108,20 @@ fn test_span() {
     assert_eq!(grid.contents(), ["asd", "   ",]);
 
     for (top, bottom) in grid.lines()[0].cells().iter().zip(grid.lines()[1].cells()) {
-        assert_eq!(top.style().unwrap(), Style::default().black().on_white());
-        assert_eq!(bottom.style().unwrap(), Style::default());
+        assert_eq!(top.style().unwrap(), &Style::default().black().on_white());
+        assert_eq!(bottom.style().unwrap(), &Style::default());
     }
 }
+
+#[test]
+fn test_span_width() {
+    assert_eq!(span::<_, ()>("a🇬🇧❤\u{fe0f}").width(), 5);
+    assert_eq!(
+        span::<_, ()>(format_args!("{}{}", "e", "\u{301}")).width(),
+        1
+    );
+
+    // Long text saturates instead of wrapping.
+    let text = "a".repeat(usize::from(u16::MAX) + 2);
+    assert_eq!(span::<_, ()>(text).width(), u16::MAX);
+}
@@ -10,6 +10,11 @@ pub trait Events<Event>: sealed::Sealed {
     /// Add an event to the collection of events.
     fn add(&mut self, event: Event);
 
+    /// Ask the terminal to do something, such as copying text to the clipboard.
+    ///
+    /// Requests are carried out by the terminal after the element has handled the input.
+    fn request(&mut self, request: Request);
+
     /// Map the type of event being collected.
     fn map<F: Fn(Event2) -> Event, Event2>(self, f: F) -> Map<Self, F>
     where
@@ -23,15 +28,33 @@ impl<'a, T: Events<Event> + ?Sized, Event> Events<Event> for &'a mut T {
     fn add(&mut self, event: Event) {
         (*self).add(event);
     }
+    fn request(&mut self, request: Request) {
+        (*self).request(request);
+    }
 }
 impl<'a, T: ?Sized> sealed::Sealed for &'a mut T {}
 
-/// An event collector that collects events into a vector.
-pub(crate) struct Vector<E>(pub(crate) Vec<E>);
+/// An event collector that collects events and requests into vectors.
+pub(crate) struct Vector<E> {
+    pub(crate) events: Vec<E>,
+    pub(crate) requests: Vec<Request>,
+}
+
+impl<E> Vector<E> {
+    pub(crate) fn new() -> Self {
+        Self {
+            events: Vec::new(),
+            requests: Vec::new(),
+        }
+    }
+}
 
 impl<E> Events<E> for Vector<E> {
     fn add(&mut self, event: E) {
-        self.0.push(event);
+        self.events.push(event);
+    }
+    fn request(&mut self, request: Request) {
+        self.requests.push(request);
     }
 }
 impl<E> sealed::Sealed for Vector<E> {}
@@ -49,9 +72,30 @@ impl<E: Events<Event>, Event2, Event, F: Fn(Event2) -> Event> Events<Event2> for
     fn add(&mut self, event: Event2) {
         self.inner.add((self.f)(event));
     }
+    fn request(&mut self, request: Request) {
+        self.inner.request(request);
+    }
 }
 impl<E, F> sealed::Sealed for Map<E, F> {}
 
+/// A request to the terminal made by an element, as passed to [`Events::request`] or
+/// [`Output::request`](crate::Output::request).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Request {
+    /// Copy text to the clipboard. See [`Terminal::set_clipboard`](crate::Terminal::set_clipboard).
+    SetClipboard(String),
+    /// Ring the bell. See [`Terminal::bell`](crate::Terminal::bell).
+    Bell,
+    /// Show a desktop notification. See [`Terminal::notify`](crate::Terminal::notify).
+    Notify {
+        /// The title of the notification.
+        title: String,
+        /// The body of the notification.
+        body: String,
+    },
+}
+
 mod sealed {
     pub trait Sealed {}
 }
@@ -1,42 +1,68 @@
-//! Terminal inputs, such as keypresses, clicks and resizes.
+//! Terminal inputs, such as keypresses, clicks, pastes and resizes.
 
+use std::borrow::Cow;
+use std::fmt::{self, Debug, Display, Formatter};
 use std::ops::{BitOr, BitOrAssign};
 
 use crate::Vec2;
 
 /// A user input on the terminal.
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum Input {
-    /// A key was pressed.
+    /// A key was pressed, repeated or released.
     Key(KeyPress),
     /// A mouse button was pressed, released or dragged, or the mouse wheel was scrolled.
     Mouse(Mouse),
+    /// Text was pasted into the terminal. Line breaks in the text are always `\n`.
+    ///
+    /// Only terminals that support bracketed paste report this; others report each character as
+    /// a separate key press.
+    Paste(String),
+    /// The terminal gained (`true`) or lost (`false`) focus. Not all terminals report this.
+    Focus(bool),
 }
 
 impl Input {
     /// Get the key press of the input.
     #[must_use]
-    pub fn key(self) -> Option<KeyPress> {
+    pub fn key(&self) -> Option<KeyPress> {
         match self {
-            Self::Key(press) => Some(press),
-            Self::Mouse(_) => None,
+            Self::Key(press) => Some(*press),
+            Self::Mouse(_) | Self::Paste(_) | Self::Focus(_) => None,
         }
     }
     /// Get the mouse input of the input.
     #[must_use]
-    pub fn mouse(self) -> Option<Mouse> {
+    pub fn mouse(&self) -> Option<Mouse> {
+        match self {
+            Self::Mouse(mouse) => Some(*mouse),
+            Self::Key(_) | Self::Paste(_) | Self::Focus(_) => None,
+        }
+    }
+    /// Get the pasted text of the input.
+    #[must_use]
+    pub fn paste(&self) -> Option<&str> {
+        match self {
+            Self::Paste(text) => Some(text),
+            Self::Key(_) | Self::Mouse(_) | Self::Focus(_) => None,
+        }
+    }
+    /// Get whether the terminal gained focus if the input is a focus change.
+    #[must_use]
+    pub fn focus(&self) -> Option<bool> {
         match self {
-            Self::Key(_) => None,
-            Self::Mouse(mouse) => Some(mouse),
+            Self::Focus(focused) => Some(*focused),
+            Self::Key(_) | Self::Mouse(_) | Self::Paste(_) => None,
         }
     }
 
-    /// Get the modifiers of the input.
+    /// Get the modifiers of the input. Pastes and focus changes never have modifiers.
     #[must_use]
     pub fn modifiers(&self) -> Modifiers {
         match self {
             Self::Key(press) => press.modifiers,
             Self::Mouse(mouse) => mouse.modifiers,
+            Self::Paste(_) | Self::Focus(_) => Modifiers::default(),
         }
     }
 }
@@ -64,8 +90,8 @@ impl PartialEq<Input> for KeyPress {
 }
 
 impl PartialEq<char> for Input {
-    fn eq(&self, &other: &char) -> bool {
-        *self == KeyPress::from(other)
+    fn eq(&self, other: &char) -> bool {
+        matches!(self, Self::Key(press) if press == other)
     }
 }
 impl PartialEq<Input> for char {
@@ -85,30 +111,69 @@ impl PartialEq<Input> for Mouse {
     }
 }
 
-/// A key was pressed.
+/// A key was pressed, repeated or released.
+///
+/// Terminals only report repeats and releases, keypad keys and the super, hyper and meta
+/// modifiers when [keyboard enhancements](KeyboardEnhancements) are enabled.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct KeyPress {
     /// Which key was pressed.
     pub key: Key,
     /// The modifiers active while the key was pressed.
     pub modifiers: Modifiers,
+    /// Whether the key was pressed, repeated or released.
+    pub kind: KeyKind,
+    /// Whether the key is on the numeric keypad.
+    pub keypad: bool,
 }
 
+impl KeyPress {
+    /// Create a press of a key with modifiers.
+    #[must_use]
+    pub const fn new(key: Key, modifiers: Modifiers) -> Self {
+        Self {
+            key,
+            modifiers,
+            kind: KeyKind::Press,
+            keypad: false,
+        }
+    }
+}
+
+/// Creates a press of the key that types the character, without modifiers.
 impl From<char> for KeyPress {
     fn from(key: char) -> Self {
-        Self {
-            key: Key::Char(key.to_ascii_lowercase()),
-            modifiers: Modifiers {
-                shift: key.is_ascii_uppercase(),
-                ..Modifiers::default()
-            },
+        Self::new(Key::Char(key), Modifiers::NONE)
+    }
+}
+
+/// Displays the modifiers and the key, like `Control+Shift+f`, followed by the kind if it isn't a
+/// press.
+impl Display for KeyPress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.modifiers.are_none() {
+            write!(f, "{}+", self.modifiers)?;
+        }
+        write!(f, "{}", self.key)?;
+        match self.kind {
+            KeyKind::Press => Ok(()),
+            KeyKind::Repeat => f.write_str(" (repeat)"),
+            KeyKind::Release => f.write_str(" (release)"),
         }
     }
 }
 
+/// Characters are equal to presses and repeats of the key that types them, wherever it is on the
+/// keyboard. Shift may be held, as typing the character can need it, but no other modifiers.
 impl PartialEq<char> for KeyPress {
     fn eq(&self, &other: &char) -> bool {
-        *self == Self::from(other)
+        self.key == Key::Char(other)
+            && Modifiers {
+                shift: false,
+                ..self.modifiers
+            }
+            .are_none()
+            && self.kind.is_press()
     }
 }
 impl PartialEq<KeyPress> for char {
@@ -118,12 +183,16 @@ impl PartialEq<KeyPress> for char {
 }
 
 /// Any key.
-///
-/// The enter/return, tab and delete keys are mapped to their respective ASCII characters.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub enum Key {
     /// The backspace key.
     Backspace,
+    /// The enter or return key.
+    Enter,
+    /// The tab key. Terminals report back tab as this with shift held.
+    Tab,
+    /// The delete key.
+    Delete,
     /// The left arrow.
     Left,
     /// The right arrow.
@@ -146,10 +215,102 @@ pub enum Key {
     Escape,
     /// A function key (e.g. F(5) is F5).
     F(u8),
-    /// A key which maps to a character. This character will never be uppercase.
+    /// A key which maps to a character, given as the character it types. Its case follows shift
+    /// and caps lock, like `A` for Shift+a.
+    ///
+    /// Whether shift is in the [modifiers](KeyPress::modifiers) depends on whether the terminal
+    /// reports it, which most don't for characters unless
+    /// [keyboard enhancements](KeyboardEnhancements) are enabled.
     Char(char),
 }
 
+/// Displays the name of the key, like `Escape`, `F5`, `Tab`, `Space` or `a`.
+impl Display for Key {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Backspace => f.write_str("Backspace"),
+            Self::Enter => f.write_str("Enter"),
+            Self::Tab => f.write_str("Tab"),
+            Self::Delete => f.write_str("Delete"),
+            Self::Left => f.write_str("Left"),
+            Self::Right => f.write_str("Right"),
+            Self::Up => f.write_str("Up"),
+            Self::Down => f.write_str("Down"),
+            Self::Home => f.write_str("Home"),
+            Self::End => f.write_str("End"),
+            Self::PageUp => f.write_str("PageUp"),
+            Self::PageDown => f.write_str("PageDown"),
+            Self::Insert => f.write_str("Insert"),
+            Self::Escape => f.write_str("Escape"),
+            Self::F(n) => write!(f, "F{n}"),
+            Self::Char(' ') => f.write_str("Space"),
+            Self::Char(c) => write!(f, "{c}"),
+        }
+    }
+}
+
+/// Whether a key was pressed, repeated or released.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum KeyKind {
+    /// The key was pressed.
+    Press,
+    /// The key was held down long enough to repeat.
+    Repeat,
+    /// The key was released.
+    Release,
+}
+
+impl KeyKind {
+    /// Whether the key was pressed or repeated, as opposed to released. Patterns that don't
+    /// mention the kind of key input only match these.
+    #[must_use]
+    pub const fn is_press(self) -> bool {
+        matches!(self, Self::Press | Self::Repeat)
+    }
+}
+
+/// Enhancements to how the terminal reports keys, from the
+/// [kitty keyboard protocol](https://sw.kovidgoyal.net/kitty/keyboard-protocol/).
+///
+/// Terminals that don't support the protocol ignore these and keep reporting keys the legacy way,
+/// where for example Tab and Control+I, and Escape and the start of an Alt combination, can't be
+/// told apart.
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct KeyboardEnhancements {
+    /// Report keys unambiguously, as well as keypad keys and the super, hyper and meta modifiers.
+    pub disambiguate: bool,
+    /// Report when keys are repeated and released, not only pressed.
+    pub report_kinds: bool,
+}
+
+impl KeyboardEnhancements {
+    /// All of the enhancements.
+    #[must_use]
+    pub const fn all() -> Self {
+        Self {
+            disambiguate: true,
+            report_kinds: true,
+        }
+    }
+
+    /// The progressive enhancement flags of the kitty keyboard protocol that enable these
+    /// enhancements.
+    #[must_use]
+    pub const fn flags(self) -> u8 {
+        let mut flags = 0;
+        if self.disambiguate {
+            flags |= 0b1;
+        }
+        if self.report_kinds {
+            // Also report the shifted keys, so that releases of shifted keys can be matched with
+            // the characters their presses typed.
+            flags |= 0b1 | 0b10 | 0b100;
+        }
+        flags
+    }
+}
+
 /// A mouse button was pressed, released or dragged, or the mouse wheel was scrolled.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct Mouse {
@@ -161,6 +322,14 @@ pub struct Mouse {
     pub size: Vec2<u16>,
     /// The modifiers active while the input occurred. Only some terminals report this.
     pub modifiers: Modifiers,
+    /// How many times the button was clicked in quick succession, for example 2 for the second
+    /// press of a double click and the drag and release that follow it. This is 0 for inputs
+    /// without a button, such as moves and scrolls.
+    ///
+    /// How close in time and space the clicks have to be can be configured with
+    /// [`Terminal::set_click_interval`](crate::Terminal::set_click_interval) and
+    /// [`Terminal::set_click_distance`](crate::Terminal::set_click_distance).
+    pub clicks: u8,
 }
 
 /// A kind of mouse input.
@@ -191,6 +360,30 @@ pub enum MouseButton {
     Right,
 }
 
+/// Displays the kind of mouse input, like `Left click` or `Scroll up`.
+impl Display for MouseKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Press(button) => write!(f, "{button} click"),
+            Self::Release(button) => write!(f, "{button} release"),
+            Self::Drag(button) => write!(f, "{button} drag"),
+            Self::Move => f.write_str("Mouse move"),
+            Self::ScrollDown => f.write_str("Scroll down"),
+            Self::ScrollUp => f.write_str("Scroll up"),
+        }
+    }
+}
+
+impl Display for MouseButton {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Left => "Left",
+            Self::Middle => "Middle",
+            Self::Right => "Right",
+        })
+    }
+}
+
 /// Key modifiers.
 #[derive(Default, Debug, Clone, Copy, Hash, PartialEq, Eq)]
 pub struct Modifiers {
@@ -200,32 +393,59 @@ pub struct Modifiers {
     pub control: bool,
     /// The alt key.
     pub alt: bool,
+    /// The super key, also known as the Windows, command or logo key.
+    pub super_key: bool,
+    /// The hyper key.
+    pub hyper: bool,
+    /// The meta key.
+    pub meta: bool,
 }
 
 impl Modifiers {
+    /// No modifiers.
+    pub const NONE: Self = Self {
+        shift: false,
+        control: false,
+        alt: false,
+        super_key: false,
+        hyper: false,
+        meta: false,
+    };
     /// Only shift.
     pub const SHIFT: Self = Self {
         shift: true,
-        control: false,
-        alt: false,
+        ..Self::NONE
     };
     /// Only control.
     pub const CONTROL: Self = Self {
-        shift: false,
         control: true,
-        alt: false,
+        ..Self::NONE
     };
     /// Only alt.
     pub const ALT: Self = Self {
-        shift: false,
-        control: false,
         alt: true,
+        ..Self::NONE
+    };
+    /// Only super.
+    pub const SUPER: Self = Self {
+        super_key: true,
+        ..Self::NONE
+    };
+    /// Only hyper.
+    pub const HYPER: Self = Self {
+        hyper: true,
+        ..Self::NONE
+    };
+    /// Only meta.
+    pub const META: Self = Self {
+        meta: true,
+        ..Self::NONE
     };
 
     /// Returns `true` if no modifiers held down.
     #[must_use]
     pub const fn are_none(self) -> bool {
-        !self.shift && !self.control && !self.alt
+        !self.shift && !self.control && !self.alt && !self.super_key && !self.hyper && !self.meta
     }
 }
 
@@ -236,14 +456,40 @@ impl BitOr for Modifiers {
             shift: self.shift | rhs.shift,
             control: self.control | rhs.control,
             alt: self.alt | rhs.alt,
+            super_key: self.super_key | rhs.super_key,
+            hyper: self.hyper | rhs.hyper,
+            meta: self.meta | rhs.meta,
         }
     }
 }
 impl BitOrAssign for Modifiers {
     fn bitor_assign(&mut self, rhs: Self) {
-        self.shift |= rhs.shift;
-        self.control |= rhs.control;
-        self.alt |= rhs.alt;
+        *self = *self | rhs;
+    }
+}
+
+/// Displays the modifiers joined with `+`, like `Control+Shift`.
+impl Display for Modifiers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let names = [
+            (self.control, "Control"),
+            (self.alt, "Alt"),
+            (self.shift, "Shift"),
+            (self.super_key, "Super"),
+            (self.hyper, "Hyper"),
+            (self.meta, "Meta"),
+        ];
+        let mut names = names
+            .iter()
+            .filter(|&&(held, _)| held)
+            .map(|&(_, name)| name);
+        if let Some(first) = names.next() {
+            f.write_str(first)?;
+        }
+        for name in names {
+            write!(f, "+{name}")?;
+        }
+        Ok(())
     }
 }
 
@@ -252,53 +498,280 @@ impl BitOrAssign for Modifiers {
 /// This is implemented for:
 /// - Functions that take an input and return a boolean.
 /// - [`Input`], [`KeyPress`], [`Mouse`] and [`char`] which just perform an equality check.
-/// - [`Key`], which does not allow any modifiers to be held down.
+/// - [`Key`], which does not allow any modifiers to be held down and matches presses and repeats.
 /// - [`MouseKind`], which can occur at any position without modifiers.
+/// - [`Paste`], which matches any paste.
 /// - Tuples, which detect any one of the inputs occurring.
 ///
 /// You can use the [`input`](crate::input!) macro to generate patterns concisely.
+///
+/// Apart from functions, these can also [describe](Self::describe) the inputs they match, which is
+/// used to generate help text from the [bindings](crate::Element::bindings) of elements.
 pub trait Pattern {
     /// Whether the pattern matches this input.
-    fn matches(&self, input: Input) -> bool;
+    fn matches(&self, input: &Input) -> bool;
+
+    /// Describe the inputs the pattern matches to the user, like `Control+s` or `Left click`.
+    ///
+    /// By default this returns [`None`], meaning that the pattern can't be described.
+    fn describe(&self) -> Option<String> {
+        None
+    }
 }
 
-impl<F: Fn(Input) -> bool> Pattern for F {
-    fn matches(&self, input: Input) -> bool {
+impl<F: Fn(&Input) -> bool> Pattern for F {
+    fn matches(&self, input: &Input) -> bool {
         (self)(input)
     }
 }
 
 impl Pattern for Input {
-    fn matches(&self, input: Input) -> bool {
-        *self == input
+    fn matches(&self, input: &Input) -> bool {
+        self == input
     }
 }
 impl Pattern for KeyPress {
-    fn matches(&self, input: Input) -> bool {
-        *self == input
+    fn matches(&self, input: &Input) -> bool {
+        self == input
+    }
+    fn describe(&self) -> Option<String> {
+        Some(self.to_string())
     }
 }
 impl Pattern for Mouse {
-    fn matches(&self, input: Input) -> bool {
-        *self == input
+    fn matches(&self, input: &Input) -> bool {
+        self == input
     }
 }
 impl Pattern for char {
-    fn matches(&self, input: Input) -> bool {
-        *self == input
+    fn matches(&self, input: &Input) -> bool {
+        self == input
+    }
+    fn describe(&self) -> Option<String> {
+        Some(KeyPress::from(*self).to_string())
     }
 }
 
 impl Pattern for Key {
-    fn matches(&self, input: Input) -> bool {
-        matches!(input, Input::Key(press) if press.key == *self && press.modifiers.are_none())
+    fn matches(&self, input: &Input) -> bool {
+        matches!(
+            input,
+            Input::Key(press)
+                if press.key == *self && press.modifiers.are_none() && press.kind.is_press()
+        )
+    }
+    fn describe(&self) -> Option<String> {
+        Some(self.to_string())
     }
 }
 
 impl Pattern for MouseKind {
-    fn matches(&self, input: Input) -> bool {
+    fn matches(&self, input: &Input) -> bool {
         matches!(input, Input::Mouse(mouse) if mouse.kind == *self && mouse.modifiers.are_none())
     }
+    fn describe(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+/// A pattern that matches any paste.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct Paste;
+
+impl Pattern for Paste {
+    fn matches(&self, input: &Input) -> bool {
+        matches!(input, Input::Paste(_))
+    }
+    fn describe(&self) -> Option<String> {
+        Some("Paste".to_owned())
+    }
+}
+
+/// An input that an element responds to, reported by [`Element::bindings`](crate::Element::bindings).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Binding {
+    /// The [description](Pattern::describe) of the inputs, or [`None`] if the pattern can't
+    /// describe them.
+    pub input: Option<String>,
+    /// What the element does when the input occurs, like `Quit`.
+    pub label: Cow<'static, str>,
+}
+
+impl Binding {
+    /// Create a binding of the inputs matched by the pattern.
+    #[must_use]
+    pub fn new<P: Pattern + ?Sized>(pattern: &P, label: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            input: pattern.describe(),
+            label: label.into(),
+        }
+    }
+}
+
+/// A description of a pattern that is being built, used by the [`input`](crate::input!) macro and
+/// parsed patterns.
+///
+/// The parts of the pattern are joined with `+`, after any modifiers, like `Control+Shift+f`.
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct Description {
+    modifiers: Modifiers,
+    parts: Vec<String>,
+}
+
+impl Description {
+    pub fn modifiers(&mut self, modifiers: Modifiers) {
+        self.modifiers |= modifiers;
+    }
+
+    pub fn key(&mut self, key: Option<Key>, kind: Option<KeyKind>) {
+        let key = key.map_or_else(|| "Any key".to_owned(), |key| key.to_string());
+        self.parts.push(match kind {
+            None => key,
+            Some(KeyKind::Press) => format!("{key} (press)"),
+            Some(KeyKind::Repeat) => format!("{key} (repeat)"),
+            Some(KeyKind::Release) => format!("{key} (release)"),
+        });
+    }
+
+    pub fn mouse(&mut self, kind: MouseKindPattern, (x, y): (Option<u16>, Option<u16>)) {
+        let kind = match kind {
+            MouseKindPattern::Any => "Mouse".to_owned(),
+            MouseKindPattern::Press(None) => "Click".to_owned(),
+            MouseKindPattern::Release(None) => "Mouse release".to_owned(),
+            MouseKindPattern::Drag(None) => "Drag".to_owned(),
+            MouseKindPattern::Press(Some(button)) => MouseKind::Press(button).to_string(),
+            MouseKindPattern::Release(Some(button)) => MouseKind::Release(button).to_string(),
+            MouseKindPattern::Drag(Some(button)) => MouseKind::Drag(button).to_string(),
+            MouseKindPattern::Other(kind) => kind.to_string(),
+            MouseKindPattern::Clicks(button, clicks) => {
+                let clicks = if clicks == 2 { "double" } else { "triple" };
+                match button {
+                    Some(button) => format!("{button} {clicks} click"),
+                    None if clicks == "double" => "Double click".to_owned(),
+                    None => "Triple click".to_owned(),
+                }
+            }
+        };
+        let coordinate = |n: Option<u16>| n.map_or_else(|| "_".to_owned(), |n| n.to_string());
+        self.parts.push(match (x, y) {
+            (None, None) => kind,
+            (x, y) => format!("{kind} at ({}, {})", coordinate(x), coordinate(y)),
+        });
+    }
+
+    pub fn paste(&mut self) {
+        self.parts.push("Paste".to_owned());
+    }
+
+    pub fn focus(&mut self, focus: Option<bool>) {
+        self.parts.push(
+            match focus {
+                None => "Focus change",
+                Some(true) => "Focus gained",
+                Some(false) => "Focus lost",
+            }
+            .to_owned(),
+        );
+    }
+
+    #[must_use]
+    pub fn finish(mut self) -> String {
+        if !self.modifiers.are_none() {
+            self.parts.insert(0, self.modifiers.to_string());
+        }
+        if self.parts.is_empty() {
+            return "No modifiers".to_owned();
+        }
+        self.parts.join("+")
+    }
+}
+
+/// The kind of mouse input in a mouse pattern.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseKindPattern {
+    /// Any kind of mouse input.
+    Any,
+    /// A press, optionally of a specific button.
+    Press(Option<MouseButton>),
+    /// A release, optionally of a specific button.
+    Release(Option<MouseButton>),
+    /// A drag, optionally of a specific button.
+    Drag(Option<MouseButton>),
+    /// A kind of mouse input without a button.
+    Other(MouseKind),
+    /// A press that is the second or third click in quick succession, optionally of a specific
+    /// button.
+    Clicks(Option<MouseButton>, u8),
+}
+
+impl MouseKindPattern {
+    pub(crate) fn matches(self, mouse: &Mouse) -> bool {
+        let button_matches =
+            |pattern: Option<MouseButton>, button| pattern.is_none() || pattern == Some(button);
+        match (self, mouse.kind) {
+            (Self::Press(pattern), MouseKind::Press(button))
+            | (Self::Release(pattern), MouseKind::Release(button))
+            | (Self::Drag(pattern), MouseKind::Drag(button)) => button_matches(pattern, button),
+            (Self::Clicks(pattern, clicks), MouseKind::Press(button)) => {
+                mouse.clicks == clicks && button_matches(pattern, button)
+            }
+            (Self::Other(pattern), kind) => pattern == kind,
+            (Self::Any, _) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A pattern generated by the [`input`](crate::input!) macro.
+///
+/// The macro generates both how the pattern matches inputs and how it describes them, so it can
+/// describe itself unless it contains negations or `where` clauses.
+#[derive(Clone, Copy)]
+pub struct MacroPattern<F> {
+    f: F,
+}
+
+impl<F: Fn(MacroQuery<'_>) -> bool> MacroPattern<F> {
+    #[doc(hidden)]
+    pub const fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<F: Fn(MacroQuery<'_>) -> bool> Debug for MacroPattern<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MacroPattern")
+            .field(&self.describe())
+            .finish()
+    }
+}
+
+impl<F: Fn(MacroQuery<'_>) -> bool> Pattern for MacroPattern<F> {
+    fn matches(&self, input: &Input) -> bool {
+        (self.f)(MacroQuery::Matches(input))
+    }
+    fn describe(&self) -> Option<String> {
+        let mut description = Description::default();
+        if (self.f)(MacroQuery::Describe(&mut description)) {
+            Some(description.finish())
+        } else {
+            None
+        }
+    }
+}
+
+/// What the function of a [`MacroPattern`] is asked to do. Both are generated in one function so
+/// that they can use the same captured variables.
+#[doc(hidden)]
+#[derive(Debug)]
+pub enum MacroQuery<'a> {
+    /// Return whether the pattern matches the input.
+    Matches(&'a Input),
+    /// Add the pattern to the description, returning whether it can be described.
+    Describe(&'a mut Description),
 }
 
 macro_rules! impl_input_pattern_for_tuples {
@@ -306,12 +779,23 @@ macro_rules! impl_input_pattern_for_tuples {
         $(
             impl<$($param: Pattern,)*> Pattern for ($($param,)*) {
                 #[allow(unused_variables)]
-                fn matches(&self, input: Input) -> bool {
+                fn matches(&self, input: &Input) -> bool {
                     #[allow(non_snake_case)]
                     let ($($param,)*) = self;
                     false
                     $(|| $param.matches(input))*
                 }
+                fn describe(&self) -> Option<String> {
+                    #[allow(non_snake_case)]
+                    let ($($param,)*) = self;
+                    let descriptions: Vec<String> =
+                        IntoIterator::into_iter([$($param.describe()),*]).collect::<Option<_>>()?;
+                    if descriptions.is_empty() {
+                        None
+                    } else {
+                        Some(descriptions.join(" or "))
+                    }
+                }
             }
         )*
     }
@@ -332,6 +816,167 @@ impl_input_pattern_for_tuples! {
     (A, B, C, D, E, F, G, H, I, J, K, L),
 }
 
+/// A sequence of inputs that occur one after another, such as `g g` or `Control+X Control+S`.
+///
+/// Each input of the sequence is matched by one of its [patterns](Patterns). Sequences are
+/// detected by the [`on_sequence`](crate::ElementExt::on_sequence) filter, and you can use the
+/// [`input_seq`](crate::input_seq!) macro to create them concisely.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct KeySequence<P> {
+    /// The patterns of the inputs in the sequence.
+    pub patterns: P,
+}
+
+impl<P: Patterns> KeySequence<P> {
+    /// Create a new key sequence from its patterns.
+    #[must_use]
+    pub const fn new(patterns: P) -> Self {
+        Self { patterns }
+    }
+
+    /// Describe the sequence as the descriptions of its patterns separated by spaces, like
+    /// `Control+x Control+s`.
+    ///
+    /// This returns [`None`] if the sequence is empty or any of its patterns can't be described.
+    #[must_use]
+    pub fn describe(&self) -> Option<String> {
+        if self.patterns.is_empty() {
+            return None;
+        }
+        let descriptions: Vec<String> = (0..self.patterns.len())
+            .map(|i| self.patterns.describe_at(i))
+            .collect::<Option<_>>()?;
+        Some(descriptions.join(" "))
+    }
+
+    /// How far the pending inputs of a sequence followed by another input get through this one.
+    pub(crate) fn matches(&self, pending: &[Input], input: &Input) -> SequenceMatch {
+        let len = pending.len() + 1;
+        if len > self.patterns.len()
+            || !pending
+                .iter()
+                .chain(std::iter::once(input))
+                .enumerate()
+                .all(|(i, input)| self.patterns.matches_at(i, input))
+        {
+            SequenceMatch::None
+        } else if len == self.patterns.len() {
+            SequenceMatch::Complete
+        } else {
+            SequenceMatch::Prefix
+        }
+    }
+}
+
+/// How far inputs get through a [`KeySequence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SequenceMatch {
+    /// The inputs don't match the start of the sequence.
+    None,
+    /// The inputs match the start of the sequence, but more are needed.
+    Prefix,
+    /// The inputs match the whole sequence.
+    Complete,
+}
+
+/// A list of input patterns that make up a [`KeySequence`].
+///
+/// This is implemented for tuples, arrays and vectors of [patterns](Pattern).
+pub trait Patterns {
+    /// The number of patterns in the list.
+    fn len(&self) -> usize;
+
+    /// Whether the list has no patterns. Sequences of no patterns are never matched.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the pattern at the index matches the input.
+    fn matches_at(&self, index: usize, input: &Input) -> bool;
+
+    /// [Describe](Pattern::describe) the inputs matched by the pattern at the index.
+    fn describe_at(&self, index: usize) -> Option<String>;
+}
+
+impl<P: Pattern> Patterns for [P] {
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn matches_at(&self, index: usize, input: &Input) -> bool {
+        self[index].matches(input)
+    }
+    fn describe_at(&self, index: usize) -> Option<String> {
+        self[index].describe()
+    }
+}
+impl<P: Pattern, const N: usize> Patterns for [P; N] {
+    fn len(&self) -> usize {
+        N
+    }
+    fn matches_at(&self, index: usize, input: &Input) -> bool {
+        self[index].matches(input)
+    }
+    fn describe_at(&self, index: usize) -> Option<String> {
+        self[index].describe()
+    }
+}
+impl<P: Pattern> Patterns for Vec<P> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+    fn matches_at(&self, index: usize, input: &Input) -> bool {
+        self[index].matches(input)
+    }
+    fn describe_at(&self, index: usize) -> Option<String> {
+        self[index].describe()
+    }
+}
+impl<T: Patterns + ?Sized> Patterns for &T {
+    fn len(&self) -> usize {
+        (**self).len()
+    }
+    fn matches_at(&self, index: usize, input: &Input) -> bool {
+        (**self).matches_at(index, input)
+    }
+    fn describe_at(&self, index: usize) -> Option<String> {
+        (**self).describe_at(index)
+    }
+}
+
+macro_rules! impl_patterns_for_tuples {
+    ($(($($param:ident $index:tt),*),)*) => {
+        $(
+            impl<$($param: Pattern,)*> Patterns for ($($param,)*) {
+                fn len(&self) -> usize {
+                    [$($index,)*].len()
+                }
+                fn matches_at(&self, index: usize, input: &Input) -> bool {
+                    match index {
+                        $($index => self.$index.matches(input),)*
+                        _ => false,
+                    }
+                }
+                fn describe_at(&self, index: usize) -> Option<String> {
+                    match index {
+                        $($index => self.$index.describe(),)*
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    }
+}
+impl_patterns_for_tuples! {
+    (A 0),
+    (A 0, B 1),
+    (A 0, B 1, C 2),
+    (A 0, B 1, C 2, D 3),
+    (A 0, B 1, C 2, D 3, E 4),
+    (A 0, B 1, C 2, D 3, E 4, F 5),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6),
+    (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7),
+}
+
 /// A macro that generates [input patterns](Pattern).
 ///
 /// # Examples
@@ -357,13 +1002,46 @@ impl_input_pattern_for_tuples! {
 /// # ;
 /// ```
 ///
+/// A pattern that matches the space key being released while control is held down:
+///
+/// ```
+/// toon::input!(Control + Key(' ') on Release)
+/// # ;
+/// ```
+///
+/// A pattern that matches double clicks with the left mouse button:
+///
+/// ```
+/// toon::input!(Mouse(DoubleClick Left))
+/// # ;
+/// ```
+///
+/// A pattern that matches the terminal losing focus:
+///
+/// ```
+/// toon::input!(Focus(Lost))
+/// # ;
+/// ```
+///
+/// A pattern that matches pasted text containing more than one line:
+///
+/// ```
+/// toon::input!(Paste where (|text: &str| text.contains('\n')))
+/// # ;
+/// ```
+///
 /// # Grammar
 ///
 /// ```text
 /// pattern = part [ '+' pattern ] | '!' pattern;
-/// part = '(' pattern ')' | 'Key' key-pattern | 'Mouse' mouse-pattern | modifier-pattern;
+/// part = '(' pattern ')'
+///     | 'Key' key-pattern
+///     | 'Mouse' mouse-pattern
+///     | 'Paste' paste-pattern
+///     | 'Focus' [ '(' focus ')' ]
+///     | modifier-pattern;
 ///
-/// key-pattern = [ '(' key ')' ] [ 'where' '(' expression ')' ];
+/// key-pattern = [ '(' key ')' ] [ 'on' key-kind ] [ 'where' '(' expression ')' ];
 /// key = 'Backspace'
 ///     | 'Left' | 'Right' | 'Up' | 'Down'
 ///     | 'Home' | 'End'
@@ -381,30 +1059,55 @@ impl_input_pattern_for_tuples! {
 ///     | '|' | ';' | ':' | '@' | '#' | '~' | '<' | '>' | ',' | '.' | '/' | '?'
 ///     | char-literal
 ///     | 'Char' expression;
+/// key-kind = 'Press' | 'Repeat' | 'Release';
 ///
 /// mouse-pattern = [ '(' mouse-kind ')' ] [ 'at' mouse-at ] [ 'where' '(' expression ')' ];
 /// mouse-kind = 'Press' [ mouse-button ]
 ///     | 'Release' [ mouse-button ]
 ///     | 'Drag' [ mouse-button ]
+///     | 'DoubleClick' [ mouse-button ]
+///     | 'TripleClick' [ mouse-button ]
 ///     | 'Move'
 ///     | 'ScrollDown' | 'ScrollUp';
 /// mouse-button = 'Left' | 'Middle' | 'Right';
 /// mouse-at = '(' ( '_' | expression ) ',' ( '_' | expression ) [ ',' ] ')'
 ///
-/// modifier-pattern = 'Shift' | 'Control' | 'Alt' | 'None';
+/// paste-pattern = [ 'where' '(' expression ')' ];
+///
+/// focus = 'Gained' | 'Lost';
+///
+/// modifier-pattern = 'Shift' | 'Control' | 'Alt' | 'Super' | 'Hyper' | 'Meta' | 'None';
 /// ```
 ///
-/// The expression given in the `where` part of `key-pattern` and `mouse-pattern` is a function
-/// that takes a [`KeyPress`] or [`Mouse`] and returns a [`bool`].
+/// Key patterns without a `key-kind` match both presses and repeats of keys, but not releases.
+///
+/// `DoubleClick` and `TripleClick` match presses that are the second or third
+/// [click](Mouse::clicks) of their button in quick succession, while `Press` matches every press.
+///
+/// The generated pattern is a [`MacroPattern`], which can [describe](Pattern::describe) itself
+/// unless it uses `!` or `where` clauses.
+///
+/// The expression given in the `where` part of `key-pattern`, `mouse-pattern` and
+/// `paste-pattern` is a function that takes a [`KeyPress`], [`Mouse`] or [`&str`](str) and returns
+/// a [`bool`].
 ///
 /// Note that the `!` operator might not work how you expect; `!Control + Key(f)` is equal to
 /// `!(Control + Key(f))` not `(!Control) + Key(f)`.
 #[macro_export]
 macro_rules! input {
     ($($input:tt)*) => {
-        move |input: $crate::Input| -> $crate::std::primitive::bool {
-            $crate::__internal_input!(input, $($input)*)
-        }
+        $crate::input::MacroPattern::new(
+            move |query: $crate::input::MacroQuery<'_>| -> $crate::std::primitive::bool {
+                match query {
+                    $crate::input::MacroQuery::Matches(input) => {
+                        $crate::__internal_input!(input, $($input)*)
+                    }
+                    $crate::input::MacroQuery::Describe(description) => {
+                        $crate::__internal_describe!(description, $($input)*)
+                    }
+                }
+            },
+        )
     };
 }
 
@@ -418,11 +1121,17 @@ macro_rules! __internal_input {
         $crate::__internal_input!($input, $($inner)*) $(&& $crate::__internal_input!($input, $($rest)*))?
     };
     // Key pattern
-    ($input:ident, Key $(($($key:tt)*))? $(where ($f:expr))? $(+ $($rest:tt)*)?) => {{
+    ($input:ident,
+        Key
+        $(($($key:tt)*))?
+        $(on $kind:ident)?
+        $(where ($f:expr))?
+        $(+ $($rest:tt)*)?
+    ) => {{
         #[allow(unused_variables)]
         let b = $crate::std::matches!(
-                $input,
-                $crate::Input::Key(press) if true
+                *$input,
+                $crate::Input::Key(press) if $crate::__internal_key_kind!(press $(, $kind)?)
                     $(&& press.key == $crate::__internal_key!($($key)*))?
                     $(&& $f(press))?
             )
@@ -439,7 +1148,7 @@ macro_rules! __internal_input {
     ) => {{
         #[allow(unused_variables, clippy::redundant_closure_call)]
         let b = $crate::std::matches!(
-            $input,
+            *$input,
             $crate::Input::Mouse(mouse) if true
                 $(&& $crate::__internal_mouse_kind!(mouse, $($mouse)*))?
                 $(&& $crate::__internal_mouse_at!(mouse, $($at)*))?
@@ -448,6 +1157,28 @@ macro_rules! __internal_input {
             $(&& $crate::__internal_input!($input, $($rest)*))?;
         b
     }};
+    // Paste pattern
+    ($input:ident, Paste $(where ($f:expr))? $(+ $($rest:tt)*)?) => {{
+        #[allow(unused_variables, clippy::redundant_closure_call)]
+        let b = $crate::std::matches!(
+            $input,
+            $crate::Input::Paste(text) if true
+                $(&& ($f)($crate::std::string::String::as_str(text)))?
+        )
+            $(&& $crate::__internal_input!($input, $($rest)*))?;
+        b
+    }};
+    // Focus pattern
+    ($input:ident, Focus $(($focus:ident))? $(+ $($rest:tt)*)?) => {{
+        #[allow(unused_variables)]
+        let b = $crate::std::matches!(
+            *$input,
+            $crate::Input::Focus(focused) if true
+                $(&& focused == $crate::__internal_focus!($focus))?
+        )
+            $(&& $crate::__internal_input!($input, $($rest)*))?;
+        b
+    }};
     // Modifier pattern
     ($input:ident, $modifier:ident $(+ $($rest:tt)*)?) => {
         $crate::__internal_modifier_pattern!($input, $modifier)
@@ -455,6 +1186,66 @@ macro_rules! __internal_input {
     };
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __internal_describe {
+    // Negations and `where` clauses can't be described.
+    ($description:ident, !$($rest:tt)*) => {{
+        let _ = $description;
+        false
+    }};
+    ($description:ident,
+        $part:ident $(($($args:tt)*))? $(on $kind:ident)? $(at ($($at:tt)*))? where $($rest:tt)*
+    ) => {{
+        let _ = $description;
+        false
+    }};
+    ($description:ident, ($($inner:tt)*) $(+ $($rest:tt)*)?) => {
+        $crate::__internal_describe!($description, $($inner)*)
+            $(&& $crate::__internal_describe!($description, $($rest)*))?
+    };
+    ($description:ident, Key $(($($key:tt)*))? $(on $kind:ident)? $(+ $($rest:tt)*)?) => {{
+        $description.key(
+            $crate::__internal_option!($($crate::__internal_key!($($key)*))?),
+            $crate::__internal_option!($($crate::KeyKind::$kind)?),
+        );
+        true $(&& $crate::__internal_describe!($description, $($rest)*))?
+    }};
+    ($description:ident, Mouse $(($($mouse:tt)*))? $(at ($($at:tt)*))? $(+ $($rest:tt)*)?) => {{
+        $description.mouse(
+            $crate::__internal_mouse_kind_pattern!($($($mouse)*)?),
+            $crate::__internal_describe_at!($($($at)*)?),
+        );
+        true $(&& $crate::__internal_describe!($description, $($rest)*))?
+    }};
+    ($description:ident, Paste $(+ $($rest:tt)*)?) => {{
+        $description.paste();
+        true $(&& $crate::__internal_describe!($description, $($rest)*))?
+    }};
+    ($description:ident, Focus $(($focus:ident))? $(+ $($rest:tt)*)?) => {{
+        $description.focus($crate::__internal_option!($($crate::__internal_focus!($focus))?));
+        true $(&& $crate::__internal_describe!($description, $($rest)*))?
+    }};
+    ($description:ident, None $(+ $($rest:tt)*)?) => {
+        true $(&& $crate::__internal_describe!($description, $($rest)*))?
+    };
+    ($description:ident, $modifier:ident $(+ $($rest:tt)*)?) => {{
+        $description.modifiers($crate::__internal_modifiers!($modifier));
+        true $(&& $crate::__internal_describe!($description, $($rest)*))?
+    }};
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __internal_option {
+    () => {
+        $crate::std::option::Option::None
+    };
+    ($value:expr) => {
+        $crate::std::option::Option::Some($value)
+    };
+}
+
 #[macro_export]
 #[doc(hidden)]
 #[rustfmt::skip]
@@ -483,11 +1274,11 @@ macro_rules! __internal_key {
     (F11) => ($crate::Key::F(11));
     (F12) => ($crate::Key::F(12));
     (F $n:expr) => ($crate::Key::F($n));
-    (Tab) => ($crate::Key::Char('\t'));
-    (Enter) => ($crate::Key::Char('\n'));
-    (Return) => ($crate::Key::Char('\n'));
-    (Del) => ($crate::Key::Char('\x7F'));
-    (Delete) => ($crate::Key::Char('\x7F'));
+    (Tab) => ($crate::Key::Tab);
+    (Enter) => ($crate::Key::Enter);
+    (Return) => ($crate::Key::Enter);
+    (Del) => ($crate::Key::Delete);
+    (Delete) => ($crate::Key::Delete);
     (1) => ($crate::Key::Char('1'));
     (2) => ($crate::Key::Char('2'));
     (3) => ($crate::Key::Char('3'));
@@ -549,6 +1340,17 @@ macro_rules! __internal_key {
     (Char $c:expr) => ($crate::Key::Char($c));
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __internal_key_kind {
+    ($press:ident) => {
+        $press.kind.is_press()
+    };
+    ($press:ident, $kind:ident) => {
+        $press.kind == $crate::KeyKind::$kind
+    };
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __internal_mouse_kind {
@@ -570,11 +1372,84 @@ macro_rules! __internal_mouse_kind {
             $crate::MouseKind::Drag(button) $(if button == $crate::MouseButton::$button)?
         )
     };
+    ($input:ident, DoubleClick $($button:ident)?) => {
+        $input.clicks == 2 && $crate::__internal_mouse_kind!($input, Press $($button)?)
+    };
+    ($input:ident, TripleClick $($button:ident)?) => {
+        $input.clicks == 3 && $crate::__internal_mouse_kind!($input, Press $($button)?)
+    };
     ($input:ident, $other:ident $(at $($at:tt)*)?) => {
         $crate::std::matches!($input.kind, $crate::MouseKind::$other)
     }
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __internal_mouse_kind_pattern {
+    () => {
+        $crate::input::MouseKindPattern::Any
+    };
+    (Press $($button:ident)?) => {
+        $crate::input::MouseKindPattern::Press(
+            $crate::__internal_option!($($crate::MouseButton::$button)?),
+        )
+    };
+    (Release $($button:ident)?) => {
+        $crate::input::MouseKindPattern::Release(
+            $crate::__internal_option!($($crate::MouseButton::$button)?),
+        )
+    };
+    (Drag $($button:ident)?) => {
+        $crate::input::MouseKindPattern::Drag(
+            $crate::__internal_option!($($crate::MouseButton::$button)?),
+        )
+    };
+    (DoubleClick $($button:ident)?) => {
+        $crate::input::MouseKindPattern::Clicks(
+            $crate::__internal_option!($($crate::MouseButton::$button)?),
+            2,
+        )
+    };
+    (TripleClick $($button:ident)?) => {
+        $crate::input::MouseKindPattern::Clicks(
+            $crate::__internal_option!($($crate::MouseButton::$button)?),
+            3,
+        )
+    };
+    ($other:ident) => {
+        $crate::input::MouseKindPattern::Other($crate::MouseKind::$other)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __internal_describe_at {
+    () => {
+        (
+            $crate::std::option::Option::None,
+            $crate::std::option::Option::None,
+        )
+    };
+    (_, $y:expr $(,)?) => {
+        (
+            $crate::std::option::Option::None,
+            $crate::std::option::Option::Some($y),
+        )
+    };
+    ($x:expr, _ $(,)?) => {
+        (
+            $crate::std::option::Option::Some($x),
+            $crate::std::option::Option::None,
+        )
+    };
+    ($x:expr, $y:expr $(,)?) => {
+        (
+            $crate::std::option::Option::Some($x),
+            $crate::std::option::Option::Some($y),
+        )
+    };
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __internal_mouse_at {
@@ -589,6 +1464,17 @@ macro_rules! __internal_mouse_at {
     };
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __internal_focus {
+    (Gained) => {
+        true
+    };
+    (Lost) => {
+        false
+    };
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __internal_modifier_pattern {
@@ -601,11 +1487,121 @@ macro_rules! __internal_modifier_pattern {
     ($input:ident, Alt) => {
         $input.modifiers().alt
     };
+    ($input:ident, Super) => {
+        $input.modifiers().super_key
+    };
+    ($input:ident, Hyper) => {
+        $input.modifiers().hyper
+    };
+    ($input:ident, Meta) => {
+        $input.modifiers().meta
+    };
     ($input:ident, None) => {
         $input.modifiers().are_none()
     };
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __internal_modifiers {
+    (Shift) => {
+        $crate::Modifiers::SHIFT
+    };
+    (Control) => {
+        $crate::Modifiers::CONTROL
+    };
+    (Alt) => {
+        $crate::Modifiers::ALT
+    };
+    (Super) => {
+        $crate::Modifiers::SUPER
+    };
+    (Hyper) => {
+        $crate::Modifiers::HYPER
+    };
+    (Meta) => {
+        $crate::Modifiers::META
+    };
+}
+
+/// A macro that generates a [`KeySequence`] from a comma-separated list of the
+/// [patterns](crate::input!) of its inputs.
+///
+/// # Examples
+///
+/// A sequence of pressing `g` twice:
+///
+/// ```
+/// toon::input_seq!(Key(g), Key(g))
+/// # ;
+/// ```
+///
+/// A sequence of pressing Control+X and then Control+S:
+///
+/// ```
+/// toon::input_seq!(Control + Key(x), Control + Key(s))
+/// # ;
+/// ```
+#[macro_export]
+macro_rules! input_seq {
+    ($($input:tt)*) => {
+        $crate::input::KeySequence::new($crate::__internal_input_seq!([] [] $($input)*))
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __internal_input_seq {
+    ([$($done:tt)*] []) => {
+        ($($done)*)
+    };
+    ([$($done:tt)*] [$($current:tt)+]) => {
+        ($($done)* $crate::input!($($current)+),)
+    };
+    ([$($done:tt)*] [$($current:tt)+] , $($rest:tt)*) => {
+        $crate::__internal_input_seq!([$($done)* $crate::input!($($current)+),] [] $($rest)*)
+    };
+    ([$($done:tt)*] [$($current:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::__internal_input_seq!([$($done)*] [$($current)* $next] $($rest)*)
+    };
+}
+
+#[test]
+fn test_key_sequence() {
+    use std::slice;
+
+    let g = Input::Key(KeyPress::from('g'));
+    let control_x = Input::Key(KeyPress::new(Key::Char('x'), Modifiers::CONTROL));
+    let control_s = Input::Key(KeyPress::new(Key::Char('s'), Modifiers::CONTROL));
+
+    let gg = input_seq!(Key(g), Key(g));
+    assert_eq!(gg.matches(&[], &g), SequenceMatch::Prefix);
+    assert_eq!(gg.matches(slice::from_ref(&g), &g), SequenceMatch::Complete);
+    assert_eq!(gg.matches(&[g.clone(), g.clone()], &g), SequenceMatch::None);
+    assert_eq!(gg.matches(&[], &control_x), SequenceMatch::None);
+
+    let save = input_seq!(Control + Key(x), Control + Key(s),);
+    assert_eq!(save.patterns.len(), 2);
+    assert_eq!(save.matches(&[], &control_x), SequenceMatch::Prefix);
+    assert_eq!(
+        save.matches(slice::from_ref(&control_x), &control_s),
+        SequenceMatch::Complete
+    );
+    assert_eq!(save.matches(&[control_x], &g), SequenceMatch::None);
+
+    assert_eq!(
+        KeySequence::new(['g']).matches(&[], &g),
+        SequenceMatch::Complete
+    );
+    assert_eq!(
+        KeySequence::new(Vec::<char>::new()).matches(&[], &g),
+        SequenceMatch::None
+    );
+    assert_eq!(save.describe().unwrap(), "Control+x Control+s");
+    assert_eq!(KeySequence::new(['g', 'g']).describe().unwrap(), "g g");
+    assert_eq!(KeySequence::new(Vec::<char>::new()).describe(), None);
+}
+
 #[test]
 fn test_input_macro() {
     let mouse = Mouse {
@@ -613,48 +1609,195 @@ fn test_input_macro() {
         at: Vec2::new(5, 6),
         size: Vec2::new(7, 8),
         modifiers: Modifiers::SHIFT,
+        clicks: 1,
     };
 
-    assert!(input!(Key).matches(Input::Key(KeyPress::from('b'))));
-    assert!(input!(Mouse).matches(Input::Mouse(mouse)));
-    assert!(!input!(Key).matches(Input::Mouse(mouse)));
-    assert!(!input!(Mouse).matches(Input::Key(KeyPress::from('b'))));
+    assert!(input!(Key).matches(&Input::Key(KeyPress::from('b'))));
+    assert!(input!(Mouse).matches(&Input::Mouse(mouse)));
+    assert!(!input!(Key).matches(&Input::Mouse(mouse)));
+    assert!(!input!(Mouse).matches(&Input::Key(KeyPress::from('b'))));
 
-    assert!(input!(Key(@)).matches(Input::Key(KeyPress::from('@'))));
+    assert!(input!(Key(@)).matches(&Input::Key(KeyPress::from('@'))));
 
-    assert!(input!(Key(a)).matches(Input::Key(KeyPress::from('A'))));
+    let shifted = |c| KeyPress::new(Key::Char(c), Modifiers::SHIFT);
+    assert!(input!(Key(a)).matches(&Input::Key(shifted('a'))));
+    assert!(!input!(Key(a)).matches(&Input::Key(KeyPress::from('A'))));
+    assert!(input!(Key('A')).matches(&Input::Key(KeyPress::from('A'))));
+    assert!(input!(Key('A')).matches(&Input::Key(shifted('A'))));
+    assert!(!input!(Key('A') + None).matches(&Input::Key(shifted('A'))));
+    assert!(shifted('A') == 'A');
+    assert!(KeyPress::new(Key::Char('A'), Modifiers::CONTROL) != 'A');
+    assert!(!Key::Tab.matches(&Input::Key(KeyPress::from('\t'))));
 
-    assert!(input!(Shift + Key(a)).matches(Input::Key(KeyPress::from('A'))));
-    assert!(!input!(Shift + Key(a)).matches(Input::Key(KeyPress::from('B'))));
-    assert!(!input!(Shift + Key(a)).matches(Input::Key(KeyPress::from('a'))));
+    assert!(input!(Shift + Key(a)).matches(&Input::Key(shifted('a'))));
+    assert!(!input!(Shift + Key(a)).matches(&Input::Key(shifted('b'))));
+    assert!(!input!(Shift + Key(a)).matches(&Input::Key(KeyPress::from('a'))));
 
     let first = input!((!Shift) + Key(a));
     let second_1 = input!(!Shift + Key(a));
     let second_2 = input!(!(Shift + Key(a)));
 
-    assert!(first.matches(Input::Key(KeyPress::from('a'))));
-    assert!(!first.matches(Input::Key(KeyPress::from('A'))));
-    assert!(!first.matches(Input::Key(KeyPress::from('m'))));
-    assert!(!first.matches(Input::Key(KeyPress::from('M'))));
+    assert!(first.matches(&Input::Key(KeyPress::from('a'))));
+    assert!(!first.matches(&Input::Key(shifted('a'))));
+    assert!(!first.matches(&Input::Key(KeyPress::from('m'))));
+    assert!(!first.matches(&Input::Key(shifted('m'))));
 
-    assert!(second_1.matches(Input::Key(KeyPress::from('a'))));
-    assert!(!second_1.matches(Input::Key(KeyPress::from('A'))));
-    assert!(second_1.matches(Input::Key(KeyPress::from('m'))));
-    assert!(second_1.matches(Input::Key(KeyPress::from('M'))));
+    assert!(second_1.matches(&Input::Key(KeyPress::from('a'))));
+    assert!(!second_1.matches(&Input::Key(shifted('a'))));
+    assert!(second_1.matches(&Input::Key(KeyPress::from('m'))));
+    assert!(second_1.matches(&Input::Key(shifted('m'))));
 
-    assert!(second_2.matches(Input::Key(KeyPress::from('a'))));
-    assert!(!second_2.matches(Input::Key(KeyPress::from('A'))));
-    assert!(second_2.matches(Input::Key(KeyPress::from('m'))));
-    assert!(second_2.matches(Input::Key(KeyPress::from('M'))));
+    assert!(second_2.matches(&Input::Key(KeyPress::from('a'))));
+    assert!(!second_2.matches(&Input::Key(shifted('a'))));
+    assert!(second_2.matches(&Input::Key(KeyPress::from('m'))));
+    assert!(second_2.matches(&Input::Key(shifted('m'))));
+
+    assert!(input!(Control + Key(b)).matches(&Input::Key(KeyPress::new(
+        Key::Char('b'),
+        Modifiers::CONTROL
+    ))));
+
+    let release = KeyPress {
+        kind: KeyKind::Release,
+        ..KeyPress::from('a')
+    };
+    let repeat = KeyPress {
+        kind: KeyKind::Repeat,
+        ..KeyPress::from('a')
+    };
+    assert!(!input!(Key(a)).matches(&Input::Key(release)));
+    assert!(!Key::Char('a').matches(&Input::Key(release)));
+    assert!(release != 'a');
+    assert!(input!(Key(a) on Release).matches(&Input::Key(release)));
+    assert!(!input!(Key(a) on Release).matches(&Input::Key(KeyPress::from('a'))));
+    assert!(input!(Key(a)).matches(&Input::Key(repeat)));
+    assert!(!input!(Key(a) on Press).matches(&Input::Key(repeat)));
+    assert!(repeat == 'a');
+
+    let keypad = KeyPress {
+        keypad: true,
+        ..KeyPress::from('5')
+    };
+    assert!(input!(Key(5)).matches(&Input::Key(keypad)));
+    assert!(Input::Key(keypad) == '5');
+    assert!(input!(Key where (|press: KeyPress| press.keypad)).matches(&Input::Key(keypad)));
 
-    assert!(input!(Control + Key(b)).matches(Input::Key(KeyPress {
-        key: Key::Char('b'),
-        modifiers: Modifiers::CONTROL,
+    let super_q = Input::Key(KeyPress::new(Key::Char('q'), Modifiers::SUPER));
+    assert!(input!(Super + Key(q)).matches(&super_q));
+    assert!(!input!(Key(q) + None).matches(&super_q));
+    assert!(!input!(Meta + Key(q)).matches(&super_q));
+
+    assert!(input!(Mouse(Press)).matches(&Input::Mouse(mouse)));
+    assert!(!input!(Mouse(Release)).matches(&Input::Mouse(mouse)));
+    assert!(!input!(Mouse(Release Middle)).matches(&Input::Mouse(mouse)));
+    assert!(input!(Mouse(Press Middle)).matches(&Input::Mouse(mouse)));
+    assert!(!input!(Mouse(Press Left)).matches(&Input::Mouse(mouse)));
+    assert!(!input!(Mouse(DoubleClick)).matches(&Input::Mouse(mouse)));
+
+    let double_click = Mouse { clicks: 2, ..mouse };
+    assert!(input!(Mouse(Press Middle)).matches(&Input::Mouse(double_click)));
+    assert!(input!(Mouse(DoubleClick)).matches(&Input::Mouse(double_click)));
+    assert!(input!(Mouse(DoubleClick Middle)).matches(&Input::Mouse(double_click)));
+    assert!(!input!(Mouse(DoubleClick Left)).matches(&Input::Mouse(double_click)));
+    assert!(!input!(Mouse(TripleClick)).matches(&Input::Mouse(double_click)));
+    assert!(!input!(Mouse(DoubleClick)).matches(&Input::Mouse(Mouse {
+        kind: MouseKind::Release(MouseButton::Middle),
+        ..double_click
     })));
 
-    assert!(input!(Mouse(Press)).matches(Input::Mouse(mouse)));
-    assert!(!input!(Mouse(Release)).matches(Input::Mouse(mouse)));
-    assert!(!input!(Mouse(Release Middle)).matches(Input::Mouse(mouse)));
-    assert!(input!(Mouse(Press Middle)).matches(Input::Mouse(mouse)));
-    assert!(!input!(Mouse(Press Left)).matches(Input::Mouse(mouse)));
+    assert!(input!(Paste).matches(&Input::Paste("q".to_owned())));
+    assert!(!input!(Key(q)).matches(&Input::Paste("q".to_owned())));
+    assert!(!input!(Paste).matches(&Input::Key(KeyPress::from('q'))));
+    assert!(!'q'.matches(&Input::Paste("q".to_owned())));
+    assert!(Paste.matches(&Input::Paste(String::new())));
+
+    assert!(input!(Focus).matches(&Input::Focus(true)));
+    assert!(input!(Focus(Lost)).matches(&Input::Focus(false)));
+    assert!(!input!(Focus(Gained)).matches(&Input::Focus(false)));
+    assert!(!input!(Focus).matches(&Input::Key(KeyPress::from('f'))));
+
+    let multiline = input!(Paste where (|text: &str| text.contains('\n')));
+    assert!(multiline.matches(&Input::Paste("a\nb".to_owned())));
+    assert!(!multiline.matches(&Input::Paste("ab".to_owned())));
+}
+
+#[test]
+fn test_describe() {
+    assert_eq!('q'.describe().unwrap(), "q");
+    assert_eq!('Q'.describe().unwrap(), "Q");
+    assert_eq!(' '.describe().unwrap(), "Space");
+    assert_eq!(Key::F(5).describe().unwrap(), "F5");
+    assert_eq!(Key::Tab.describe().unwrap(), "Tab");
+    assert_eq!(
+        KeyPress::new(Key::Escape, Modifiers::CONTROL | Modifiers::ALT)
+            .describe()
+            .unwrap(),
+        "Control+Alt+Escape"
+    );
+    assert_eq!(
+        KeyPress {
+            kind: KeyKind::Release,
+            ..KeyPress::from('a')
+        }
+        .describe()
+        .unwrap(),
+        "a (release)"
+    );
+    assert_eq!(
+        MouseKind::Press(MouseButton::Left).describe().unwrap(),
+        "Left click"
+    );
+    assert_eq!(MouseKind::ScrollUp.describe().unwrap(), "Scroll up");
+    assert_eq!(Paste.describe().unwrap(), "Paste");
+
+    assert_eq!(('q', Key::Escape).describe().unwrap(), "q or Escape");
+    assert_eq!(().describe(), None);
+    assert_eq!(('q', |_: &Input| true).describe(), None);
+
+    assert_eq!(input!(Key(q)).describe().unwrap(), "q");
+    assert_eq!(
+        input!(Key(f) + Shift + Control).describe().unwrap(),
+        "Control+Shift+f"
+    );
+    assert_eq!(input!(Key(' ') + None).describe().unwrap(), "Space");
+    assert_eq!(
+        input!(Control + Key(' ') on Release).describe().unwrap(),
+        "Control+Space (release)"
+    );
+    assert_eq!(
+        input!(Alt + Mouse(Press Left)).describe().unwrap(),
+        "Alt+Left click"
+    );
+    assert_eq!(
+        input!(Mouse(Press) at (0, _)).describe().unwrap(),
+        "Click at (0, _)"
+    );
+    assert_eq!(
+        input!(Mouse(DoubleClick Left)).describe().unwrap(),
+        "Left double click"
+    );
+    assert_eq!(
+        input!(Mouse(TripleClick)).describe().unwrap(),
+        "Triple click"
+    );
+    assert_eq!(input!(Focus(Lost)).describe().unwrap(), "Focus lost");
+    assert_eq!(input!(!Key(q)).describe(), None);
+    assert_eq!(
+        input!(Paste where (|text: &str| text.is_empty())).describe(),
+        None
+    );
+
+    // Expressions are evaluated when describing.
+    let (n, column) = (5, 3);
+    assert_eq!(input!(Key(F n)).describe().unwrap(), "F5");
+    assert_eq!(
+        input!(Shift + (Mouse(ScrollUp) at (column + 1, 2)))
+            .describe()
+            .unwrap(),
+        "Shift+Scroll up at (4, 2)"
+    );
+    assert_eq!(
+        format!("{:?}", input!(Control + Key(q))),
+        "MacroPattern(Some(\"Control+q\"))"
+    );
 }
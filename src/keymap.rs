@@ -0,0 +1,857 @@
+//! Key bindings configured at runtime.
+//!
+//! The [`input`](crate::input!) macro builds patterns at compile time, so changing a shortcut means
+//! recompiling. [`ParsedPattern`] parses the same grammar from a string instead, and a [`Keymap`]
+//! binds parsed patterns to actions, for example ones read from a configuration file:
+//!
+//! ```toml
+//! "Control + Key(s)" = "Save"
+//! "Key(q)" = "Quit"
+//! "Mouse(Press Middle)" = "Paste"
+//! ```
+//!
+//! With the `serde` feature enabled, [`Keymap`] can be deserialized from a map of patterns to
+//! actions like the one above in any format Serde supports.
+
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use std::marker::PhantomData;
+
+#[cfg(feature = "serde")]
+use serde_crate::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+
+use crate::input::{Binding, Description, MouseKindPattern, Pattern};
+use crate::{Element, Events, Filter, Input, Key, KeyKind, Modifiers, MouseButton, MouseKind};
+
+/// An input pattern parsed at runtime.
+///
+/// Patterns use the grammar of the [`input`](crate::input!) macro, except that `where` clauses
+/// aren't supported and expressions can only be number and character literals, such as in
+/// `Key(F 13)`, `Key(Char 'x')` and `Mouse at (0, _)`.
+///
+/// # Examples
+///
+/// ```
+/// use toon::keymap::ParsedPattern;
+///
+/// let pattern: ParsedPattern = "Control + Shift + Key(f)".parse()?;
+/// # Ok::<_, toon::keymap::ParseError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParsedPattern {
+    /// The string the pattern was parsed from.
+    source: String,
+    /// The parsed pattern.
+    node: Node,
+}
+
+impl ParsedPattern {
+    /// Parse a pattern.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the string is not a valid pattern.
+    pub fn parse(source: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            index: 0,
+            len: source.len(),
+        };
+        let node = parser.pattern()?;
+        if parser.index < parser.tokens.len() {
+            return Err(parser.error("`+` or the end of the pattern"));
+        }
+        Ok(Self {
+            source: source.to_owned(),
+            node,
+        })
+    }
+
+    /// Get the string the pattern was parsed from.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+}
+
+impl FromStr for ParsedPattern {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl Display for ParsedPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Pattern for ParsedPattern {
+    fn matches(&self, input: &Input) -> bool {
+        self.node.matches(input)
+    }
+    fn describe(&self) -> Option<String> {
+        let mut description = Description::default();
+        if self.node.describe(&mut description) {
+            Some(description.finish())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "serde")))]
+impl<'de> Deserialize<'de> for ParsedPattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PatternVisitor;
+        impl Visitor<'_> for PatternVisitor {
+            type Value = ParsedPattern;
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.write_str("an input pattern")
+            }
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                ParsedPattern::parse(v).map_err(E::custom)
+            }
+        }
+        deserializer.deserialize_str(PatternVisitor)
+    }
+}
+
+/// An error parsing a [`ParsedPattern`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ParseError {
+    /// The byte offset in the source at which the error occurred.
+    pub offset: usize,
+    /// A description of what was expected at the offset.
+    pub expected: &'static str,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {} at offset {}", self.expected, self.offset)
+    }
+}
+impl StdError for ParseError {}
+
+/// A part of a parsed pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Node {
+    /// The inner pattern doesn't match.
+    Not(Box<Node>),
+    /// All of the patterns match.
+    All(Vec<Node>),
+    /// A key input, optionally of a specific key and kind.
+    Key(Option<Key>, Option<KeyKind>),
+    /// A mouse input of a kind, optionally at a specific column and row.
+    Mouse(MouseKindPattern, Option<u16>, Option<u16>),
+    /// Any paste.
+    Paste,
+    /// A focus input, optionally of gaining or losing focus.
+    Focus(Option<bool>),
+    /// The modifiers are held down.
+    Modifiers(Modifiers),
+    /// No modifiers are held down.
+    NoModifiers,
+}
+
+impl Node {
+    fn matches(&self, input: &Input) -> bool {
+        match self {
+            Self::Not(inner) => !inner.matches(input),
+            Self::All(nodes) => nodes.iter().all(|node| node.matches(input)),
+            Self::Key(key, kind) => matches!(
+                input,
+                Input::Key(press) if kind.map_or(press.kind.is_press(), |kind| press.kind == kind)
+                    && (key.is_none() || *key == Some(press.key))
+            ),
+            Self::Mouse(kind, x, y) => matches!(
+                input,
+                Input::Mouse(mouse) if kind.matches(mouse)
+                    && (x.is_none() || *x == Some(mouse.at.x))
+                    && (y.is_none() || *y == Some(mouse.at.y))
+            ),
+            Self::Paste => matches!(input, Input::Paste(_)),
+            Self::Focus(focus) => matches!(
+                *input,
+                Input::Focus(focused) if focus.is_none() || *focus == Some(focused)
+            ),
+            &Self::Modifiers(modifiers) => (input.modifiers() | modifiers) == input.modifiers(),
+            Self::NoModifiers => input.modifiers().are_none(),
+        }
+    }
+
+    /// Add the pattern to a description, returning whether it can be described. Negations can't.
+    fn describe(&self, description: &mut Description) -> bool {
+        match self {
+            Self::Not(_) => return false,
+            Self::All(nodes) => return nodes.iter().all(|node| node.describe(description)),
+            &Self::Key(key, kind) => description.key(key, kind),
+            &Self::Mouse(kind, x, y) => description.mouse(kind, (x, y)),
+            Self::Paste => description.paste(),
+            &Self::Focus(focus) => description.focus(focus),
+            &Self::Modifiers(modifiers) => description.modifiers(modifiers),
+            Self::NoModifiers => {}
+        }
+        true
+    }
+}
+
+/// A token of a pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    /// A run of letters, digits and underscores.
+    Word(&'a str),
+    /// A character literal.
+    Char(char),
+    /// Any other character.
+    Punct(char),
+}
+
+/// Split a pattern into tokens and their byte offsets.
+fn tokenize(source: &str) -> Result<Vec<(usize, Token<'_>)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        let token = if c.is_whitespace() {
+            continue;
+        } else if c.is_alphanumeric() || c == '_' {
+            let mut end = offset + c.len_utf8();
+            while let Some(&(i, c)) = chars.peek() {
+                if !c.is_alphanumeric() && c != '_' {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            Token::Word(&source[offset..end])
+        } else if c == '\'' && matches!(chars.peek(), Some(&(_, c)) if c != '\'') {
+            let error = ParseError {
+                offset,
+                expected: "a character literal",
+            };
+            let c = match chars.next().ok_or_else(|| error.clone())?.1 {
+                '\\' => match chars.next().ok_or_else(|| error.clone())?.1 {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    '0' => '\0',
+                    c @ ('\\' | '\'' | '"') => c,
+                    _ => return Err(error),
+                },
+                c => c,
+            };
+            if chars.next().map(|(_, c)| c) != Some('\'') {
+                return Err(error);
+            }
+            Token::Char(c)
+        } else {
+            Token::Punct(c)
+        };
+        tokens.push((offset, token));
+    }
+
+    Ok(tokens)
+}
+
+/// A parser of the tokens of a pattern.
+struct Parser<'a> {
+    tokens: Vec<(usize, Token<'a>)>,
+    /// The index of the next token.
+    index: usize,
+    /// The length of the source, used as the offset of errors at the end.
+    len: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.index).map(|&(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let token = self.peek()?;
+        self.index += 1;
+        Some(token)
+    }
+
+    /// Create an error at the next token.
+    fn error(&self, expected: &'static str) -> ParseError {
+        ParseError {
+            offset: self
+                .tokens
+                .get(self.index)
+                .map_or(self.len, |&(offset, _)| offset),
+            expected,
+        }
+    }
+
+    /// Consume the next token if it is the given token.
+    fn eat(&mut self, token: Token<'_>) -> bool {
+        let matches = self.peek() == Some(token);
+        if matches {
+            self.index += 1;
+        }
+        matches
+    }
+
+    fn expect(&mut self, token: Token<'_>, expected: &'static str) -> Result<(), ParseError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    /// Parse a word from a list of options.
+    fn word<T: Copy>(
+        &mut self,
+        options: &[(&str, T)],
+        expected: &'static str,
+    ) -> Result<T, ParseError> {
+        match self.peek() {
+            Some(Token::Word(word)) => {
+                let &(_, value) = options
+                    .iter()
+                    .find(|&&(name, _)| name == word)
+                    .ok_or_else(|| self.error(expected))?;
+                self.index += 1;
+                Ok(value)
+            }
+            _ => Err(self.error(expected)),
+        }
+    }
+
+    fn pattern(&mut self) -> Result<Node, ParseError> {
+        if self.eat(Token::Punct('!')) {
+            return Ok(Node::Not(Box::new(self.pattern()?)));
+        }
+
+        let part = self.part()?;
+        if !self.eat(Token::Punct('+')) {
+            return Ok(part);
+        }
+        Ok(match self.pattern()? {
+            Node::All(mut nodes) => {
+                nodes.insert(0, part);
+                Node::All(nodes)
+            }
+            rest => Node::All(vec![part, rest]),
+        })
+    }
+
+    fn part(&mut self) -> Result<Node, ParseError> {
+        if self.eat(Token::Punct('(')) {
+            let pattern = self.pattern()?;
+            self.expect(Token::Punct(')'), "`)`")?;
+            return Ok(pattern);
+        }
+
+        let part = self.word(
+            &[
+                ("Key", Part::Key),
+                ("Mouse", Part::Mouse),
+                ("Paste", Part::Paste),
+                ("Focus", Part::Focus),
+                ("Shift", Part::Modifiers(Modifiers::SHIFT)),
+                ("Control", Part::Modifiers(Modifiers::CONTROL)),
+                ("Alt", Part::Modifiers(Modifiers::ALT)),
+                ("Super", Part::Modifiers(Modifiers::SUPER)),
+                ("Hyper", Part::Modifiers(Modifiers::HYPER)),
+                ("Meta", Part::Modifiers(Modifiers::META)),
+                ("None", Part::NoModifiers),
+            ],
+            "a pattern",
+        )?;
+
+        Ok(match part {
+            Part::Key => {
+                let key = if self.eat(Token::Punct('(')) {
+                    let key = self.key()?;
+                    self.expect(Token::Punct(')'), "`)`")?;
+                    Some(key)
+                } else {
+                    None
+                };
+                let kind = if self.eat(Token::Word("on")) {
+                    Some(self.word(
+                        &[
+                            ("Press", KeyKind::Press),
+                            ("Repeat", KeyKind::Repeat),
+                            ("Release", KeyKind::Release),
+                        ],
+                        "a key kind",
+                    )?)
+                } else {
+                    None
+                };
+                Node::Key(key, kind)
+            }
+            Part::Mouse => {
+                let kind = if self.eat(Token::Punct('(')) {
+                    let kind = self.mouse_kind()?;
+                    self.expect(Token::Punct(')'), "`)`")?;
+                    kind
+                } else {
+                    MouseKindPattern::Any
+                };
+                let (x, y) = if self.eat(Token::Word("at")) {
+                    self.expect(Token::Punct('('), "`(`")?;
+                    let x = self.coordinate()?;
+                    self.expect(Token::Punct(','), "`,`")?;
+                    let y = self.coordinate()?;
+                    self.eat(Token::Punct(','));
+                    self.expect(Token::Punct(')'), "`)`")?;
+                    (x, y)
+                } else {
+                    (None, None)
+                };
+                Node::Mouse(kind, x, y)
+            }
+            Part::Paste => Node::Paste,
+            Part::Focus => {
+                let focus = if self.eat(Token::Punct('(')) {
+                    let focus =
+                        self.word(&[("Gained", true), ("Lost", false)], "`Gained` or `Lost`")?;
+                    self.expect(Token::Punct(')'), "`)`")?;
+                    Some(focus)
+                } else {
+                    None
+                };
+                Node::Focus(focus)
+            }
+            Part::Modifiers(modifiers) => Node::Modifiers(modifiers),
+            Part::NoModifiers => Node::NoModifiers,
+        })
+    }
+
+    fn key(&mut self) -> Result<Key, ParseError> {
+        const NAMED: &[(&str, Key)] = &[
+            ("Backspace", Key::Backspace),
+            ("Left", Key::Left),
+            ("Right", Key::Right),
+            ("Up", Key::Up),
+            ("Down", Key::Down),
+            ("Home", Key::Home),
+            ("End", Key::End),
+            ("PageUp", Key::PageUp),
+            ("PageDown", Key::PageDown),
+            ("Insert", Key::Insert),
+            ("Escape", Key::Escape),
+            ("Tab", Key::Tab),
+            ("Enter", Key::Enter),
+            ("Return", Key::Enter),
+            ("Del", Key::Delete),
+            ("Delete", Key::Delete),
+        ];
+        const EXPECTED: &str = "a key";
+
+        let error = self.error(EXPECTED);
+        Ok(match self.next().ok_or_else(|| error.clone())? {
+            Token::Word("F") => match self.next() {
+                Some(Token::Word(n)) => Key::F(n.parse().map_err(|_| error)?),
+                _ => return Err(error),
+            },
+            Token::Word("Char") => match self.next() {
+                Some(Token::Char(c)) => Key::Char(c),
+                _ => return Err(error),
+            },
+            Token::Word(word) => {
+                if let Some(&(_, key)) = NAMED.iter().find(|&&(name, _)| name == word) {
+                    key
+                } else if let Some(n) = word.strip_prefix('F').and_then(|n| n.parse().ok()) {
+                    Key::F(n)
+                } else {
+                    let mut chars = word.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c @ ('a'..='z' | '0'..='9' | '_')), None) => Key::Char(c),
+                        _ => return Err(error),
+                    }
+                }
+            }
+            Token::Char(c)
+            | Token::Punct(
+                c @ ('!' | '%' | '^' | '&' | '*' | '-' | '=' | '+' | '|' | ';' | ':' | '@' | '#'
+                | '~' | '<' | '>' | ',' | '.' | '/' | '?'),
+            ) => Key::Char(c),
+            Token::Punct(_) => return Err(error),
+        })
+    }
+
+    fn mouse_kind(&mut self) -> Result<MouseKindPattern, ParseError> {
+        let kind = self.word(
+            &[
+                ("Press", MouseKindPattern::Press(None)),
+                ("Release", MouseKindPattern::Release(None)),
+                ("Drag", MouseKindPattern::Drag(None)),
+                ("DoubleClick", MouseKindPattern::Clicks(None, 2)),
+                ("TripleClick", MouseKindPattern::Clicks(None, 3)),
+                ("Move", MouseKindPattern::Other(MouseKind::Move)),
+                ("ScrollDown", MouseKindPattern::Other(MouseKind::ScrollDown)),
+                ("ScrollUp", MouseKindPattern::Other(MouseKind::ScrollUp)),
+            ],
+            "a kind of mouse input",
+        )?;
+
+        let button = match self.peek() {
+            Some(Token::Word(_)) if !matches!(kind, MouseKindPattern::Other(_)) => {
+                Some(self.word(
+                    &[
+                        ("Left", MouseButton::Left),
+                        ("Middle", MouseButton::Middle),
+                        ("Right", MouseButton::Right),
+                    ],
+                    "a mouse button",
+                )?)
+            }
+            _ => None,
+        };
+
+        Ok(match kind {
+            MouseKindPattern::Press(_) => MouseKindPattern::Press(button),
+            MouseKindPattern::Release(_) => MouseKindPattern::Release(button),
+            MouseKindPattern::Drag(_) => MouseKindPattern::Drag(button),
+            MouseKindPattern::Clicks(_, clicks) => MouseKindPattern::Clicks(button, clicks),
+            other => other,
+        })
+    }
+
+    /// Parse a column or row of a mouse pattern, where `_` is any.
+    fn coordinate(&mut self) -> Result<Option<u16>, ParseError> {
+        const EXPECTED: &str = "a number or `_`";
+
+        match self.peek() {
+            Some(Token::Word("_")) => {
+                self.index += 1;
+                Ok(None)
+            }
+            Some(Token::Word(n)) => {
+                let n = n.parse().map_err(|_| self.error(EXPECTED))?;
+                self.index += 1;
+                Ok(Some(n))
+            }
+            _ => Err(self.error(EXPECTED)),
+        }
+    }
+}
+
+/// The first word of a part of a pattern.
+#[derive(Debug, Clone, Copy)]
+enum Part {
+    Key,
+    Mouse,
+    Paste,
+    Focus,
+    Modifiers(Modifiers),
+    NoModifiers,
+}
+
+/// A set of key bindings from [input patterns](ParsedPattern) to actions.
+///
+/// A keymap is a [filter](Filter) that triggers the action of the first binding whose pattern
+/// matches an input, and passes inputs that don't match any binding to the inner element. Its
+/// [bindings](Element::bindings) are reported labelled with the [`Display`] of their actions.
+///
+/// # Examples
+///
+/// ```
+/// # use toon::ElementExt;
+/// use std::fmt::{self, Display, Formatter};
+///
+/// #[derive(Debug, Clone)]
+/// enum Action {
+///     Save,
+///     Quit,
+/// }
+///
+/// impl Display for Action {
+///     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+///         f.write_str(match self {
+///             Self::Save => "Save",
+///             Self::Quit => "Quit",
+///         })
+///     }
+/// }
+///
+/// let mut keymap = toon::Keymap::new();
+/// keymap.bind("Control + Key(s)", Action::Save)?;
+/// keymap.bind("Key(q) + None", Action::Quit)?;
+///
+/// let element = toon::empty().filter(keymap);
+/// # Ok::<_, toon::keymap::ParseError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Keymap<A> {
+    bindings: Vec<(ParsedPattern, A)>,
+}
+
+impl<A> Keymap<A> {
+    /// Create a new keymap without any bindings.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Bind a pattern to an action, after the existing bindings.
+    pub fn insert(&mut self, pattern: ParsedPattern, action: A) {
+        self.bindings.push((pattern, action));
+    }
+
+    /// Parse a pattern and bind it to an action, after the existing bindings.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the pattern is invalid.
+    pub fn bind(&mut self, pattern: &str, action: A) -> Result<(), ParseError> {
+        self.insert(ParsedPattern::parse(pattern)?, action);
+        Ok(())
+    }
+
+    /// Get the action of the first binding that matches the input.
+    #[must_use]
+    pub fn action(&self, input: &Input) -> Option<&A> {
+        self.bindings
+            .iter()
+            .find(|(pattern, _)| pattern.matches(input))
+            .map(|(_, action)| action)
+    }
+
+    /// Get the bindings of the keymap, in order.
+    #[must_use]
+    pub fn bindings(&self) -> &[(ParsedPattern, A)] {
+        &self.bindings
+    }
+}
+
+impl<A> Default for Keymap<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> Extend<(ParsedPattern, A)> for Keymap<A> {
+    fn extend<I: IntoIterator<Item = (ParsedPattern, A)>>(&mut self, iter: I) {
+        self.bindings.extend(iter);
+    }
+}
+
+impl<A> std::iter::FromIterator<(ParsedPattern, A)> for Keymap<A> {
+    fn from_iter<I: IntoIterator<Item = (ParsedPattern, A)>>(iter: I) -> Self {
+        Self {
+            bindings: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Reports its bindings labelled with their actions, followed by those of the inner element.
+impl<A: Clone + Display> Filter<A> for Keymap<A> {
+    fn handle<E: Element<Event = A>>(&self, element: E, input: Input, events: &mut dyn Events<A>) {
+        match self.action(&input) {
+            Some(action) => events.add(action.clone()),
+            None => element.handle(input, events),
+        }
+    }
+    fn bindings<E: Element>(&self, element: E, bindings: &mut Vec<Binding>) {
+        for (pattern, action) in &self.bindings {
+            bindings.push(Binding::new(pattern, action.to_string()));
+        }
+        element.bindings(bindings);
+    }
+}
+
+/// Deserializes a map from patterns to actions, binding them in the order of the map.
+#[cfg(feature = "serde")]
+#[cfg_attr(feature = "doc_cfg", doc(cfg(feature = "serde")))]
+impl<'de, A: Deserialize<'de>> Deserialize<'de> for Keymap<A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeymapVisitor<A>(PhantomData<A>);
+        impl<'de, A: Deserialize<'de>> Visitor<'de> for KeymapVisitor<A> {
+            type Value = Keymap<A>;
+            fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.write_str("a map from input patterns to actions")
+            }
+            fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<Self::Value, M::Error> {
+                let mut keymap = Keymap::new();
+                while let Some((pattern, action)) = map.next_entry()? {
+                    keymap.insert(pattern, action);
+                }
+                Ok(keymap)
+            }
+        }
+        deserializer.deserialize_map(KeymapVisitor(PhantomData))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_parse_pattern() {
+    use crate::{KeyPress, Mouse, Vec2};
+
+    let key = |key, modifiers, kind| {
+        Input::Key(KeyPress {
+            kind,
+            ..KeyPress::new(key, modifiers)
+        })
+    };
+    let mouse = |kind, x, y, modifiers| {
+        Input::Mouse(Mouse {
+            kind,
+            at: Vec2::new(x, y),
+            size: Vec2::new(10, 10),
+            modifiers,
+            clicks: 1,
+        })
+    };
+    let matches =
+        |pattern: &str, input: &Input| ParsedPattern::parse(pattern).unwrap().matches(input);
+
+    let control_shift_f = key(
+        Key::Char('f'),
+        Modifiers::CONTROL | Modifiers::SHIFT,
+        KeyKind::Press,
+    );
+    assert!(matches("Control+Shift+Key(f)", &control_shift_f));
+    assert!(matches("Key(f) + Shift", &control_shift_f));
+    assert!(!matches("Key(f) + None", &control_shift_f));
+    assert!(!matches("Alt + Key(f)", &control_shift_f));
+    assert!(!matches("!Control + Key(f)", &control_shift_f));
+    assert!(matches("!Alt + Key(f)", &control_shift_f));
+    assert!(matches("(Control + Key) + !Key(g)", &control_shift_f));
+
+    let release = key(Key::Char(' '), Modifiers::NONE, KeyKind::Release);
+    assert!(!matches("Key(' ')", &release));
+    assert!(matches("Key(' ') on Release", &release));
+    assert!(matches("Key(Char ' ') on Release + None", &release));
+
+    assert!(matches(
+        "Key(Tab)",
+        &Input::from(KeyPress::new(Key::Tab, Modifiers::NONE))
+    ));
+    assert!(matches("Key(+)", &Input::from(KeyPress::from('+'))));
+    assert!(matches("Key('\\n')", &Input::from(KeyPress::from('\n'))));
+    assert!(matches(
+        "Key(F5)",
+        &key(Key::F(5), Modifiers::NONE, KeyKind::Press)
+    ));
+    assert!(matches(
+        "Key(F 20)",
+        &key(Key::F(20), Modifiers::NONE, KeyKind::Press)
+    ));
+
+    let click = mouse(MouseKind::Press(MouseButton::Left), 3, 4, Modifiers::ALT);
+    assert!(matches("Mouse(Press Left)", &click));
+    assert!(matches("Mouse(Press) + Alt", &click));
+    assert!(!matches("Mouse(Press Right)", &click));
+    assert!(!matches("Mouse(Release)", &click));
+    assert!(matches("Mouse at (3, 4)", &click));
+    assert!(matches("Mouse(Press) at (_, 4,)", &click));
+    assert!(!matches("Mouse at (4, _)", &click));
+    assert!(matches(
+        "Mouse(ScrollUp)",
+        &mouse(MouseKind::ScrollUp, 0, 0, Modifiers::NONE)
+    ));
+    assert!(!matches("Mouse(DoubleClick)", &click));
+    let double_click = Input::Mouse(Mouse {
+        kind: MouseKind::Press(MouseButton::Right),
+        at: Vec2::new(0, 0),
+        size: Vec2::new(10, 10),
+        modifiers: Modifiers::NONE,
+        clicks: 2,
+    });
+    assert!(matches("Mouse(DoubleClick Right)", &double_click));
+    assert!(matches("Mouse(Press)", &double_click));
+    assert!(!matches("Mouse(DoubleClick Left)", &double_click));
+    assert!(!matches("Mouse(TripleClick)", &double_click));
+
+    assert!(matches("Paste", &Input::Paste("text".to_owned())));
+    assert!(matches("Focus", &Input::Focus(false)));
+    assert!(matches("Focus(Lost)", &Input::Focus(false)));
+    assert!(!matches("Focus(Gained)", &Input::Focus(false)));
+
+    let error = |pattern: &str| ParsedPattern::parse(pattern).unwrap_err();
+    assert_eq!(
+        error("Key(f) + "),
+        ParseError {
+            offset: 9,
+            expected: "a pattern",
+        }
+    );
+    assert_eq!(error("Key(F)").offset, 4);
+    assert_eq!(error("Key(A)").expected, "a key");
+    assert_eq!(error("Key(a").expected, "`)`");
+    assert_eq!(
+        error("Key(a) Shift").expected,
+        "`+` or the end of the pattern"
+    );
+    assert_eq!(error("Key(a) where (f)").offset, 7);
+    assert_eq!(error("Mouse(Press Up)").expected, "a mouse button");
+    assert_eq!(error("Key('ab')").expected, "a character literal");
+}
+
+#[cfg(test)]
+#[test]
+fn test_keymap() {
+    use crate::events::Vector;
+    use crate::{ElementExt, KeyPress};
+
+    let mut keymap = Keymap::new();
+    keymap.bind("Control + Key(s)", "save").unwrap();
+    keymap.bind("Key(q)", "quit").unwrap();
+    keymap.bind("Key", "other key").unwrap();
+
+    let control_s = Input::Key(KeyPress::new(Key::Char('s'), Modifiers::CONTROL));
+    let q = Input::Key(KeyPress::from('q'));
+    let s = Input::Key(KeyPress::from('s'));
+    assert_eq!(keymap.action(&control_s), Some(&"save"));
+    assert_eq!(keymap.action(&q), Some(&"quit"));
+    assert_eq!(keymap.action(&s), Some(&"other key"));
+    assert_eq!(keymap.action(&Input::Focus(true)), None);
+
+    let element = crate::empty().on(Input::Focus(true), |_| "focus");
+    let mut events = Vector::new();
+    keymap.handle(&element, q, &mut events);
+    keymap.handle(&element, Input::Focus(true), &mut events);
+    assert_eq!(events.events, ["quit", "focus"]);
+
+    let mut bindings = Vec::new();
+    Filter::bindings(&keymap, &element, &mut bindings);
+    let bindings: Vec<_> = bindings
+        .iter()
+        .map(|binding| (binding.input.as_deref().unwrap(), &*binding.label))
+        .collect();
+    assert_eq!(
+        bindings,
+        [
+            ("Control+s", "save"),
+            ("q", "quit"),
+            ("Any key", "other key")
+        ]
+    );
+}
+
+#[cfg(all(test, feature = "serde"))]
+#[test]
+fn test_deserialize_keymap() {
+    use serde_crate::de::value::{Error, MapDeserializer};
+
+    let bindings = vec![("Control + Key(s)", "save"), ("Mouse(Press Left)", "click")];
+    let keymap =
+        Keymap::<String>::deserialize(MapDeserializer::<_, Error>::new(bindings.into_iter()))
+            .unwrap();
+    assert_eq!(keymap.bindings().len(), 2);
+    assert_eq!(keymap.bindings()[0].0.as_str(), "Control + Key(s)");
+    assert_eq!(keymap.bindings()[1].1, "click");
+
+    let invalid = vec![("Key(s) +", "save")];
+    let error =
+        Keymap::<String>::deserialize(MapDeserializer::<_, Error>::new(invalid.into_iter()))
+            .unwrap_err();
+    assert_eq!(error.to_string(), "expected a pattern at offset 8");
+}
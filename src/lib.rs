@@ -59,15 +59,24 @@
 //!
 //! # Features
 //!
-//! Toon offers the following features, none of which are enabled by default:
+//! Toon offers the following features, all of which except `serde` are enabled by default:
+//! - `ansi`: Enable the [Ansi](https://docs.rs/toon/0.1/toon/backend/struct.Ansi.html) backend, a
+//!   native backend for Unix that does not depend on any terminal library.
 //! - `crossterm`: Enable the
-//! [Crossterm](https://docs.rs/toon/0.1/toon/backend/struct.Crossterm.html) backend.
+//!   [Crossterm](https://docs.rs/toon/0.1/toon/backend/struct.Crossterm.html) backend.
 //! - `dev`: Enable developer tools.
 //! - `either`: Integrate with the [`either`](https://crates.io/crates/either) crate. This
-//! implements [`Element`](https://docs.rs/toon/0.1/toon/trait.Element.html),
-//! [`Output`](https://docs.rs/toon/0.1/toon/output/trait.Output.html) and
-//! [`Collection`](https://docs.rs/toon/0.1/toon/elements/containers/trait.Collection.html) for
-//! `Either`.
+//!   implements [`Element`](https://docs.rs/toon/0.1/toon/trait.Element.html),
+//!   [`Output`](https://docs.rs/toon/0.1/toon/output/trait.Output.html) and
+//!   [`Collection`](https://docs.rs/toon/0.1/toon/elements/containers/trait.Collection.html) for
+//!   `Either`.
+//! - `serde`: Integrate with [Serde](https://serde.rs). This implements `Deserialize` for
+//!   [`Keymap`](https://docs.rs/toon/0.1/toon/keymap/struct.Keymap.html) and
+//!   [`ParsedPattern`](https://docs.rs/toon/0.1/toon/keymap/struct.ParsedPattern.html), so key
+//!   bindings can be loaded from configuration files.
+//! - `suspend`: Enable
+//!   [`Terminal::suspend`](https://docs.rs/toon/0.1/toon/struct.Terminal.html#method.suspend),
+//!   which suspends the process on Unix.
 #![cfg_attr(feature = "doc_cfg", feature(doc_cfg))]
 #![warn(
     clippy::cargo,
@@ -116,6 +125,9 @@ pub use either_crate as either;
 use either_crate::Either;
 
 pub mod backend;
+#[cfg(all(unix, feature = "ansi"))]
+#[doc(no_inline)]
+pub use backend::Ansi;
 #[cfg(feature = "crossterm")]
 #[doc(no_inline)]
 pub use backend::Crossterm;
@@ -125,11 +137,20 @@ pub use backend::{Backend, Dummy};
 pub mod buffer;
 pub use buffer::*;
 
+pub mod capabilities;
+pub use capabilities::{Capabilities, ColorSupport};
+
 pub mod elements;
 pub use elements::*;
 
 pub mod input;
-pub use input::{Input, Key, KeyPress, Modifiers, Mouse, MouseButton, MouseKind};
+pub use input::{
+    Binding, Input, Key, KeyKind, KeyPress, KeySequence, KeyboardEnhancements, Modifiers, Mouse,
+    MouseButton, MouseKind,
+};
+
+pub mod keymap;
+pub use keymap::Keymap;
 
 pub mod output;
 pub use output::Output;
@@ -138,16 +159,23 @@ pub mod style;
 pub use style::*;
 
 mod events;
-pub use events::Events;
+pub use events::{Events, Request};
+
+mod sequence;
 
 mod terminal;
 pub use terminal::*;
 
+mod timer;
+
 mod util;
 
 mod vec2;
 pub use vec2::Vec2;
 
+pub mod width;
+pub use width::WidthTable;
+
 /// A composable part of the UI.
 ///
 /// Elements are cheap, immutable, borrowed and short-lived. They usually implement [`Copy`].
@@ -199,6 +227,13 @@ pub trait Element {
     fn title(&self, _title: &mut dyn fmt::Write) -> fmt::Result {
         Ok(())
     }
+
+    /// Report the labelled inputs the element responds to, such as those of
+    /// [`on`](ElementExt::on) filters given a [label](On::label), in the order they take priority.
+    ///
+    /// Containers only report the bindings of the elements that key inputs are passed to, so these
+    /// are the bindings that are currently active. This can be used to generate help text.
+    fn bindings(&self, _bindings: &mut Vec<Binding>) {}
 }
 
 macro_rules! implement_element_forwarding {
@@ -225,6 +260,9 @@ macro_rules! implement_element_forwarding {
                 fn title(&self, title: &mut dyn fmt::Write) -> fmt::Result {
                     (**self).title(title)
                 }
+                fn bindings(&self, bindings: &mut Vec<Binding>) {
+                    (**self).bindings(bindings)
+                }
             }
         )*
     }
@@ -271,6 +309,12 @@ impl<L: Element, R: Element<Event = L::Event>> Element for Either<L, R> {
             Self::Right(r) => r.title(title),
         }
     }
+    fn bindings(&self, bindings: &mut Vec<Binding>) {
+        match self {
+            Self::Left(l) => l.bindings(bindings),
+            Self::Right(r) => r.bindings(bindings),
+        }
+    }
 }
 
 /// A terminal cursor.
@@ -280,6 +324,8 @@ pub struct Cursor {
     pub shape: CursorShape,
     /// Whether the cursor blinks.
     pub blinking: bool,
+    /// The color of the cursor. The default color uses the terminal's cursor color.
+    pub color: Color,
     /// The zero-indexed position of the cursor.
     pub pos: Vec2<u16>,
 }
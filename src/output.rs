@@ -1,10 +1,11 @@
 //! Outputs which elements draw to.
 
 use std::fmt::{Display, Write};
+use std::time::Instant;
 
-use unicode_width::UnicodeWidthChar;
+use unicode_segmentation::UnicodeSegmentation;
 
-use crate::{Cursor, Style, Vec2};
+use crate::{width, Cursor, Request, Style, Vec2};
 
 /// An output to which elements draw themselves.
 ///
@@ -35,10 +36,49 @@ pub trait Output {
     /// character.
     fn write_char(&mut self, pos: Vec2<u16>, c: char, style: Style);
 
+    /// Write an extended grapheme cluster to a single cell of the output at a zero-indexed
+    /// position.
+    ///
+    /// This follows the same rules as [`write_char`](Self::write_char), using the
+    /// [width of the cluster](width::grapheme_width). Writing a cluster of a single character is
+    /// the same as writing the character.
+    ///
+    /// By default this writes the first character of the cluster followed by its zero-width
+    /// characters, which can't display clusters such as flags that are wider than their first
+    /// character.
+    fn write_grapheme(&mut self, pos: Vec2<u16>, grapheme: &str, style: Style) {
+        let mut chars = grapheme.chars();
+        if let Some(first) = chars.next() {
+            self.write_char(pos, first, style.clone());
+            for c in chars.filter(|&c| width::char_width(c) == Some(0)) {
+                self.write_char(pos, c, style.clone());
+            }
+        }
+    }
+
     /// Set the cursor of the output, if there is one.
     ///
     /// If this is called multiple times the last one will be used.
     fn set_cursor(&mut self, cursor: Option<Cursor>);
+
+    /// Request that the output is drawn again at the given instant, for example because the
+    /// element is animated.
+    ///
+    /// If this is called multiple times the earliest instant will be used. By default this does
+    /// nothing.
+    fn request_frame_at(&mut self, at: Instant) {
+        let _ = at;
+    }
+
+    /// Ask the terminal to do something once the output has been drawn, such as showing a
+    /// notification.
+    ///
+    /// Elements are drawn on every frame, so the request is made again each time the element
+    /// is drawn. Elements should only make requests when drawing for a single frame, for example
+    /// the first one after a job they display has finished. By default this does nothing.
+    fn request(&mut self, request: Request) {
+        let _ = request;
+    }
 }
 
 impl<'a, O: Output + ?Sized> Output for &'a mut O {
@@ -46,10 +86,19 @@ impl<'a, O: Output + ?Sized> Output for &'a mut O {
         (**self).size()
     }
     fn write_char(&mut self, pos: Vec2<u16>, c: char, style: Style) {
-        (**self).write_char(pos, c, style)
+        (**self).write_char(pos, c, style);
+    }
+    fn write_grapheme(&mut self, pos: Vec2<u16>, grapheme: &str, style: Style) {
+        (**self).write_grapheme(pos, grapheme, style);
     }
     fn set_cursor(&mut self, cursor: Option<Cursor>) {
-        (**self).set_cursor(cursor)
+        (**self).set_cursor(cursor);
+    }
+    fn request_frame_at(&mut self, at: Instant) {
+        (**self).request_frame_at(at);
+    }
+    fn request(&mut self, request: Request) {
+        (**self).request(request);
     }
 }
 
@@ -67,12 +116,30 @@ impl<L: Output, R: Output> Output for either_crate::Either<L, R> {
             Self::Right(r) => r.write_char(pos, c, style),
         }
     }
+    fn write_grapheme(&mut self, pos: Vec2<u16>, grapheme: &str, style: Style) {
+        match self {
+            Self::Left(l) => l.write_grapheme(pos, grapheme, style),
+            Self::Right(r) => r.write_grapheme(pos, grapheme, style),
+        }
+    }
     fn set_cursor(&mut self, cursor: Option<Cursor>) {
         match self {
             Self::Left(l) => l.set_cursor(cursor),
             Self::Right(r) => r.set_cursor(cursor),
         }
     }
+    fn request_frame_at(&mut self, at: Instant) {
+        match self {
+            Self::Left(l) => l.request_frame_at(at),
+            Self::Right(r) => r.request_frame_at(at),
+        }
+    }
+    fn request(&mut self, request: Request) {
+        match self {
+            Self::Left(l) => l.request(request),
+            Self::Right(r) => r.request(request),
+        }
+    }
 }
 
 /// Extension methods for outputs.
@@ -92,28 +159,32 @@ pub trait Ext: Output {
     /// If it overflows the width of the terminal it will be cut off. Control characters will be
     /// ignored.
     fn write(&mut self, pos: impl Into<Vec2<u16>>, value: impl Display, style: Style) {
+        let mut text = String::new();
+        let _ = write!(text, "{value}");
+        self.write_str(pos, &text, style);
+    }
+
+    /// Write a string to the specified position in the output, splitting it into extended
+    /// grapheme clusters that each take up one cell.
+    ///
+    /// If it overflows the width of the terminal it will be cut off. Control characters will be
+    /// ignored.
+    fn write_str(&mut self, pos: impl Into<Vec2<u16>>, text: &str, style: Style) {
         let total_width = self.size().x;
         let mut pos = pos.into();
-        let _ = write!(
-            crate::util::WriteCharsFn(|c| {
-                let width = match c.width() {
-                    Some(width) => width,
-                    None => return Ok(()),
-                } as u16;
-
-                self.write_char(pos, c, style);
-
-                pos.x += width;
-
-                if pos.x >= total_width {
-                    Err(std::fmt::Error)
-                } else {
-                    Ok(())
-                }
-            }),
-            "{}",
-            value,
-        );
+        for grapheme in text.graphemes(true) {
+            let width = match width::grapheme_width(grapheme) {
+                Some(width) => width,
+                None => continue,
+            } as u16;
+
+            self.write_grapheme(pos, grapheme, style.clone());
+
+            pos.x += width;
+            if pos.x >= total_width {
+                break;
+            }
+        }
     }
 
     /// Create an output that draws to the specified area of this output.
@@ -156,26 +227,35 @@ pub struct Area<O> {
     size: Vec2<u16>,
 }
 
-impl<O: Output> Output for Area<O> {
-    fn size(&self) -> Vec2<u16> {
-        self.size
-    }
-    fn write_char(&mut self, pos: Vec2<u16>, c: char, style: Style) {
+impl<O> Area<O> {
+    /// Get the position in the inner output to write something of the given width to, or `None`
+    /// if it doesn't fit in the area.
+    fn inner_pos(&self, pos: Vec2<u16>, width: Option<usize>) -> Option<Vec2<u16>> {
         if pos.x >= self.size.x
             || pos.y >= self.size.y
-            || (pos.x == self.size.x - 1 && c.width() == Some(2))
+            || usize::from(pos.x) + width.unwrap_or(0) > usize::from(self.size.x)
         {
-            return;
+            return None;
         }
-        let pos = match pos
-            .map(i32::from)
+        pos.map(i32::from)
             .checked_add(self.top_left)
             .and_then(|v| v.try_into::<u16>().ok())
-        {
-            Some(pos) => pos,
-            None => return,
-        };
-        self.inner.write_char(pos, c, style);
+    }
+}
+
+impl<O: Output> Output for Area<O> {
+    fn size(&self) -> Vec2<u16> {
+        self.size
+    }
+    fn write_char(&mut self, pos: Vec2<u16>, c: char, style: Style) {
+        if let Some(pos) = self.inner_pos(pos, width::char_width(c)) {
+            self.inner.write_char(pos, c, style);
+        }
+    }
+    fn write_grapheme(&mut self, pos: Vec2<u16>, grapheme: &str, style: Style) {
+        if let Some(pos) = self.inner_pos(pos, width::grapheme_width(grapheme)) {
+            self.inner.write_grapheme(pos, grapheme, style);
+        }
     }
     fn set_cursor(&mut self, cursor: Option<Cursor>) {
         self.inner.set_cursor(
@@ -194,6 +274,12 @@ impl<O: Output> Output for Area<O> {
                 }),
         );
     }
+    fn request_frame_at(&mut self, at: Instant) {
+        self.inner.request_frame_at(at);
+    }
+    fn request(&mut self, request: Request) {
+        self.inner.request(request);
+    }
 }
 
 /// An [`Output`] that calls a callback when its cursor is set, created by the
@@ -212,7 +298,16 @@ impl<O: Output, F: FnMut(&mut O, Option<Cursor>)> Output for OnSetCursor<O, F> {
     fn write_char(&mut self, pos: Vec2<u16>, c: char, style: Style) {
         self.inner.write_char(pos, c, style);
     }
+    fn write_grapheme(&mut self, pos: Vec2<u16>, grapheme: &str, style: Style) {
+        self.inner.write_grapheme(pos, grapheme, style);
+    }
     fn set_cursor(&mut self, cursor: Option<Cursor>) {
         (self.f)(&mut self.inner, cursor);
     }
+    fn request_frame_at(&mut self, at: Instant) {
+        self.inner.request_frame_at(at);
+    }
+    fn request(&mut self, request: Request) {
+        self.inner.request(request);
+    }
 }
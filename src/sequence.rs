@@ -0,0 +1,72 @@
+//! Tracking the inputs of key sequences that are in progress.
+//!
+//! Elements are immutable, so the inputs pressed so far in a sequence like `g g` are kept by the
+//! terminal. While it passes an input to an element, they are made available to the
+//! [`OnSequence`](crate::OnSequence) filters in it, which report back whether the input matched
+//! and continued one of their sequences.
+
+use std::cell::RefCell;
+
+use crate::events::Vector;
+use crate::{Element, Input};
+
+/// The state of key sequences while an input is being handled.
+#[derive(Debug)]
+pub(crate) struct State {
+    /// The inputs of the sequence before the one being handled.
+    pub(crate) pending: Vec<Input>,
+    /// Whether the sequence timed out, so the input being handled can complete sequences but not
+    /// continue them.
+    pub(crate) timed_out: bool,
+    /// Whether a filter has matched the input being handled, either continuing the pending inputs
+    /// or starting a new sequence after abandoning them.
+    pub(crate) matched: bool,
+    /// Whether the input being handled continued a sequence that isn't complete yet.
+    pub(crate) continued: bool,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<State>> = const { RefCell::new(None) };
+}
+
+/// Run a function with the current state of key sequences, if an input is being handled by a
+/// terminal.
+pub(crate) fn with_state<R>(f: impl FnOnce(Option<&mut State>) -> R) -> R {
+    CURRENT.with(|current| f(current.borrow_mut().as_mut()))
+}
+
+/// Pass an input to an element following the pending inputs of a sequence, returning the events
+/// and requests it produced and the resulting state.
+///
+/// The element is only given the input once. Filters whose sequences start with an input that
+/// doesn't continue the pending inputs first pass it to their inner element, and only abandon the
+/// pending inputs if nothing there continued them.
+pub(crate) fn handle<E: Element>(
+    element: E,
+    input: Input,
+    pending: Vec<Input>,
+    timed_out: bool,
+) -> (Vector<E::Event>, State) {
+    /// Restores the previous state when dropped, even if handling the input panics.
+    struct Scope(Option<State>);
+    impl Drop for Scope {
+        fn drop(&mut self) {
+            CURRENT.with(|current| *current.borrow_mut() = self.0.take());
+        }
+    }
+
+    let state = State {
+        pending,
+        timed_out,
+        matched: false,
+        continued: false,
+    };
+    let scope = Scope(CURRENT.with(|current| current.replace(Some(state))));
+
+    let mut events = Vector::new();
+    element.handle(input, &mut events);
+
+    let state = CURRENT.with(|current| current.borrow_mut().take()).unwrap();
+    drop(scope);
+    (events, state)
+}
@@ -1,14 +1,24 @@
 //! Text styling.
 
+use std::cmp::min;
+use std::sync::Arc;
+
+use crate::ColorSupport;
+
 /// How text is written.
-#[derive(Default, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, Hash, PartialEq, Eq)]
 pub struct Style {
     /// The foreground color of the text.
     pub foreground: Color,
     /// The background color of the text.
     pub background: Color,
+    /// The color of the text's underline. The default color uses the foreground color. Not
+    /// widely supported.
+    pub underline_color: Color,
     /// The attributes of the text.
     pub attributes: Attributes,
+    /// The hyperlink the text links to, if any. Not widely supported.
+    pub link: Option<Link>,
 }
 
 impl Style {
@@ -21,13 +31,15 @@ impl Style {
         Self::new(Color::Default, Color::Default, Attributes::new())
     }
 
-    /// Create a style.
+    /// Create a style. The underline color is the default color.
     #[must_use]
     pub const fn new(foreground: Color, background: Color, attributes: Attributes) -> Self {
         Self {
             foreground,
             background,
+            underline_color: Color::Default,
             attributes,
+            link: None,
         }
     }
 }
@@ -63,12 +75,12 @@ macro_rules! color_setters {
 }
 
 macro_rules! attribute_setters {
-    ($(#[doc = $doc:literal] $name:ident($property:ident = $value:expr),)*) => {
+    ($(#[doc = $doc:literal] $name:ident($($property:ident = $value:expr),*),)*) => {
         $(
             #[doc = $doc]
             #[must_use]
             fn $name(mut self) -> Self {
-                self.as_mut().attributes.$property = $value;
+                $(self.as_mut().attributes.$property = $value;)*
                 self
             }
         )*
@@ -90,6 +102,18 @@ pub trait Styled: AsRef<Style> + AsMut<Style> + Sized {
         self.as_mut().background = background.into();
         self
     }
+    /// Set the color of the underline.
+    #[must_use]
+    fn underline_color(mut self, underline_color: impl Into<Color>) -> Self {
+        self.as_mut().underline_color = underline_color.into();
+        self
+    }
+    /// Make the text a hyperlink.
+    #[must_use]
+    fn link(mut self, link: impl Into<Link>) -> Self {
+        self.as_mut().link = Some(link.into());
+        self
+    }
     /// Set the attributes.
     #[must_use]
     fn attributes(mut self, attributes: Attributes) -> Self {
@@ -106,10 +130,24 @@ pub trait Styled: AsRef<Style> + AsMut<Style> + Sized {
         italic(italic = true),
         /// Underline the text.
         underlined(underlined = true),
+        /// Underline the text with a double line.
+        double_underlined(underlined = true, underline_style = UnderlineStyle::Double),
+        /// Underline the text with a curly line.
+        curly_underlined(underlined = true, underline_style = UnderlineStyle::Curly),
+        /// Underline the text with a dotted line.
+        dotted_underlined(underlined = true, underline_style = UnderlineStyle::Dotted),
+        /// Underline the text with a dashed line.
+        dashed_underlined(underlined = true, underline_style = UnderlineStyle::Dashed),
         /// Make the text blink.
         blinking(blinking = true),
         /// Cross out the text.
         crossed_out(crossed_out = true),
+        /// Swap the foreground and background colors.
+        reversed(reversed = true),
+        /// Hide the text.
+        hidden(hidden = true),
+        /// Draw a line over the text.
+        overlined(overlined = true),
     }
 
     color_setters! {
@@ -204,6 +242,16 @@ impl Color {
         }
     }
 
+    /// Get the ANSI value of the color, if it is a named color or an ANSI color.
+    #[must_use]
+    pub fn ansi_value(self) -> Option<u8> {
+        match self {
+            Self::AnsiValue(ansi) => Some(ansi.get()),
+            Self::Default | Self::Rgb(_) => None,
+            named => (0..16).find(|&value| Self::new_ansi(value) == named),
+        }
+    }
+
     /// Darken a color if it is a named color variant.
     #[must_use]
     pub fn darken(self) -> Self {
@@ -220,6 +268,60 @@ impl Color {
             other => other,
         }
     }
+
+    /// Get the RGB value of the color as displayed by xterm, if it isn't the default color.
+    #[must_use]
+    pub fn to_rgb(self) -> Option<Rgb> {
+        Some(match self {
+            Self::Default => return None,
+            Self::Black => Rgb::new(0, 0, 0),
+            Self::DarkRed => Rgb::new(205, 0, 0),
+            Self::DarkGreen => Rgb::new(0, 205, 0),
+            Self::DarkYellow => Rgb::new(205, 205, 0),
+            Self::DarkBlue => Rgb::new(0, 0, 238),
+            Self::DarkMagenta => Rgb::new(205, 0, 205),
+            Self::DarkCyan => Rgb::new(0, 205, 205),
+            Self::LightGray => Rgb::new(229, 229, 229),
+            Self::DarkGray => Rgb::new(127, 127, 127),
+            Self::Red => Rgb::new(255, 0, 0),
+            Self::Green => Rgb::new(0, 255, 0),
+            Self::Yellow => Rgb::new(255, 255, 0),
+            Self::Blue => Rgb::new(92, 92, 255),
+            Self::Magenta => Rgb::new(255, 0, 255),
+            Self::Cyan => Rgb::new(0, 255, 255),
+            Self::White => Rgb::new(255, 255, 255),
+            Self::AnsiValue(ansi) => ansi.to_rgb(),
+            Self::Rgb(rgb) => rgb,
+        })
+    }
+
+    /// Convert the color to the nearest one that can be displayed with the given color support.
+    ///
+    /// RGB colors are converted to the nearest [`AnsiColor`], and RGB and ANSI colors are
+    /// converted to the nearest named color. Without any color support every color becomes the
+    /// default color.
+    #[must_use]
+    pub fn downsample(self, colors: ColorSupport) -> Self {
+        match (self, colors) {
+            (_, ColorSupport::TrueColor) | (Self::Default, _) => self,
+            (_, ColorSupport::Monochrome) => Self::Default,
+            (Self::Rgb(rgb), ColorSupport::Ansi256) => Self::AnsiValue(AnsiColor::nearest(rgb)),
+            (Self::Rgb(rgb), ColorSupport::Ansi16) => (0..16)
+                .map(Self::new_ansi)
+                .min_by_key(|color| color.to_rgb().map(|named| distance(named, rgb)))
+                .unwrap_or(self),
+            (Self::AnsiValue(ansi), ColorSupport::Ansi16) => {
+                Self::Rgb(ansi.to_rgb()).downsample(colors)
+            }
+            (other, _) => other,
+        }
+    }
+}
+
+/// Get the squared distance between two colors.
+fn distance(a: Rgb, b: Rgb) -> u32 {
+    let component = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2) as u32;
+    component(a.r, b.r) + component(a.g, b.g) + component(a.b, b.b)
 }
 
 impl Default for Color {
@@ -290,6 +392,41 @@ impl AnsiColor {
         })
     }
 
+    /// Get the ANSI color nearest to an RGB color.
+    #[must_use]
+    pub fn nearest(rgb: Rgb) -> Self {
+        // The index of the nearest of the cube's levels to a component.
+        let level = |value: u8| match value {
+            0..=47 => 0,
+            48..=114 => 1,
+            _ => (value - 35) / 40,
+        };
+        let cube = Self::new_rgb(level(rgb.r), level(rgb.g), level(rgb.b));
+
+        let average = (u16::from(rgb.r) + u16::from(rgb.g) + u16::from(rgb.b)) / 3;
+        let gray = Self::new_grayscale(min(average.saturating_sub(3) / 10, 23) as u8 + 1);
+
+        if distance(gray.to_rgb(), rgb) < distance(cube.to_rgb(), rgb) {
+            gray
+        } else {
+            cube
+        }
+    }
+
+    /// Get the RGB value of the color as displayed by xterm.
+    #[must_use]
+    pub fn to_rgb(self) -> Rgb {
+        let level = |index: u8| if index == 0 { 0 } else { 55 + index * 40 };
+        match (self.rgb(), self.grayscale()) {
+            (Some((r, g, b)), _) => Rgb::new(level(r), level(g), level(b)),
+            (None, Some(shade)) => {
+                let value = 8 + 10 * (shade - 1);
+                Rgb::new(value, value, value)
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+
     /// Get the ANSI value of this color.
     ///
     /// This value is guaranteed to be >= 16.
@@ -358,6 +495,94 @@ fn test_ansi() {
     assert_eq!(color.get(), 231);
     assert_eq!(color.rgb(), Some((5, 5, 5)));
     assert_eq!(color.grayscale(), Some(25));
+
+    assert_eq!(
+        AnsiColor::new_rgb(2, 3, 4).to_rgb(),
+        Rgb::new(135, 175, 215)
+    );
+    assert_eq!(
+        AnsiColor::new_grayscale(13).to_rgb(),
+        Rgb::new(128, 128, 128)
+    );
+    assert_eq!(
+        AnsiColor::nearest(Rgb::new(130, 180, 210)),
+        AnsiColor::new_rgb(2, 3, 4)
+    );
+    assert_eq!(
+        AnsiColor::nearest(Rgb::new(125, 127, 129)),
+        AnsiColor::new_grayscale(13)
+    );
+    assert_eq!(
+        AnsiColor::nearest(Rgb::new(255, 255, 255)),
+        AnsiColor::new_grayscale(25)
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_styled() {
+    let style = Style::default()
+        .red()
+        .curly_underlined()
+        .underline_color(Color::Blue)
+        .reversed();
+    assert_eq!(style.foreground, Color::Red);
+    assert_eq!(style.underline_color, Color::Blue);
+    assert!(style.attributes.underlined);
+    assert_eq!(style.attributes.underline_style, UnderlineStyle::Curly);
+    assert!(style.attributes.reversed);
+    assert!(!style.attributes.hidden);
+
+    assert_eq!(Color::DarkCyan.ansi_value(), Some(6));
+    assert_eq!(Color::new_ansi(200).ansi_value(), Some(200));
+    assert_eq!(Color::Default.ansi_value(), None);
+}
+
+#[cfg(test)]
+#[test]
+fn test_palette() {
+    let mut palette = Palette::default();
+    assert_eq!(palette.is_dark(), None);
+    assert_eq!(palette.resolve(Color::Default, true), Rgb::new(0, 0, 0));
+    assert_eq!(palette.resolve(Color::Red, false), Rgb::new(255, 0, 0));
+
+    palette.background = Some(Rgb::new(250, 245, 230));
+    palette.ansi[9] = Some(Rgb::new(200, 40, 40));
+    assert_eq!(palette.is_dark(), Some(false));
+    assert_eq!(
+        palette.resolve(Color::Default, true),
+        Rgb::new(250, 245, 230)
+    );
+    assert_eq!(palette.resolve(Color::Red, false), Rgb::new(200, 40, 40));
+    assert_eq!(palette.resolve(Color::DarkRed, false), Rgb::new(205, 0, 0));
+
+    let darker = palette
+        .resolve(Color::Red, false)
+        .blend(Rgb::new(0, 0, 0), 128);
+    assert_eq!(darker, Rgb::new(100, 20, 20));
+}
+
+#[cfg(test)]
+#[test]
+fn test_downsample() {
+    let orange = Color::Rgb(Rgb::new(255, 135, 0));
+    assert_eq!(orange.downsample(ColorSupport::TrueColor), orange);
+    assert_eq!(
+        orange.downsample(ColorSupport::Ansi256),
+        Color::AnsiValue(AnsiColor::new_rgb(5, 2, 0))
+    );
+    assert_eq!(orange.downsample(ColorSupport::Ansi16), Color::DarkYellow);
+    assert_eq!(orange.downsample(ColorSupport::Monochrome), Color::Default);
+
+    let navy = Color::AnsiValue(AnsiColor::new_rgb(0, 0, 2));
+    assert_eq!(navy.downsample(ColorSupport::Ansi256), navy);
+    assert_eq!(navy.downsample(ColorSupport::Ansi16), Color::DarkBlue);
+
+    assert_eq!(Color::Cyan.downsample(ColorSupport::Ansi16), Color::Cyan);
+    assert_eq!(
+        Color::Cyan.downsample(ColorSupport::Monochrome),
+        Color::Default
+    );
 }
 
 impl From<AnsiColor> for u8 {
@@ -392,6 +617,140 @@ impl Rgb {
             b: u8::MAX - self.b,
         }
     }
+    /// Blends this color with another. `amount` is how much of the other color to use, with 0
+    /// being none of it and 255 being all of it.
+    #[must_use]
+    pub fn blend(self, other: Self, amount: u8) -> Self {
+        let blend = |a: u8, b: u8| {
+            let (a, b, amount) = (u16::from(a), u16::from(b), u16::from(amount));
+            ((a * (255 - amount) + b * amount + 127) / 255) as u8
+        };
+        Self {
+            r: blend(self.r, other.r),
+            g: blend(self.g, other.g),
+            b: blend(self.b, other.b),
+        }
+    }
+    /// Gets whether the color is dark, that is, whether light text would be readable on it.
+    #[must_use]
+    pub fn is_dark(self) -> bool {
+        let luma = 299 * u32::from(self.r) + 587 * u32::from(self.g) + 114 * u32::from(self.b);
+        luma < 128 * 1000
+    }
+}
+
+/// The colors a terminal displays, as reported by the terminal.
+///
+/// Any color the terminal didn't report is `None`. See
+/// [`Terminal::palette`](crate::Terminal::palette).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Palette {
+    /// The default foreground color.
+    pub foreground: Option<Rgb>,
+    /// The default background color.
+    pub background: Option<Rgb>,
+    /// The 16 named colors, in the order of their ANSI values (see [`Color::new_ansi`]).
+    pub ansi: [Option<Rgb>; 16],
+}
+
+impl Palette {
+    /// Get the RGB value the terminal displays a color as.
+    ///
+    /// `background` is whether the color is used as a background color, which determines what the
+    /// default color is. Colors that the terminal didn't report fall back to the colors of xterm
+    /// (see [`Color::to_rgb`]), and the default color falls back to black or white.
+    #[must_use]
+    pub fn resolve(&self, color: Color, background: bool) -> Rgb {
+        let reported = match color {
+            Color::Default if background => self.background,
+            Color::Default => self.foreground,
+            Color::AnsiValue(_) | Color::Rgb(_) => None,
+            named => named
+                .ansi_value()
+                .and_then(|value| self.ansi[usize::from(value)]),
+        };
+        reported
+            .or_else(|| color.to_rgb())
+            .unwrap_or(if background {
+                Rgb::new(0, 0, 0)
+            } else {
+                Rgb::new(255, 255, 255)
+            })
+    }
+
+    /// Get whether the terminal has a dark background, if the terminal reported it.
+    ///
+    /// This can be used to choose between a light and a dark theme.
+    #[must_use]
+    pub fn is_dark(&self) -> Option<bool> {
+        self.background.map(Rgb::is_dark)
+    }
+}
+
+/// A hyperlink, which terminals that support OSC 8 let users open by clicking on the text.
+///
+/// Links are cheap to clone: clones share their URL and ID.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct Link {
+    url: Arc<str>,
+    id: Option<Arc<str>>,
+}
+
+impl Link {
+    /// Create a link to a URL.
+    #[must_use]
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.into(),
+            id: None,
+        }
+    }
+
+    /// Set the ID of the link.
+    ///
+    /// By default, terminals treat adjacent text with the same URL as one link. Text with the same
+    /// ID and URL is treated as one link even when it isn't adjacent, for example when a link is
+    /// wrapped onto multiple lines, so that it is highlighted as a whole.
+    #[must_use]
+    pub fn with_id(mut self, id: &str) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Get the URL of the link.
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Get the ID of the link, if it has one.
+    #[must_use]
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+}
+
+impl From<&str> for Link {
+    fn from(url: &str) -> Self {
+        Self::new(url)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_link() {
+    let link = Link::new("https://example.com").with_id("docs");
+    assert_eq!(link.url(), "https://example.com");
+    assert_eq!(link.id(), Some("docs"));
+
+    let same = Link::from(String::from("https://example.com").as_str()).with_id("docs");
+    assert_eq!(link, same);
+    assert!(std::ptr::eq(link.url(), link.clone().url()));
+    assert_ne!(link, Link::new("https://example.com"));
+
+    let style = Style::default().link("https://example.com");
+    assert_eq!(style.link, Some(Link::new("https://example.com")));
 }
 
 /// Attributes of text. Not all of these attributes are supported by all terminals.
@@ -404,10 +763,19 @@ pub struct Attributes {
     pub italic: bool,
     /// Whether the text is underlined.
     pub underlined: bool,
+    /// The style of the underline, if the text is underlined. Styles other than straight are not
+    /// widely supported.
+    pub underline_style: UnderlineStyle,
     /// Whether the text blinks. Not widely supported.
     pub blinking: bool,
     /// Whether the text is crossed out. Not widely supported.
     pub crossed_out: bool,
+    /// Whether the foreground and background colors are swapped.
+    pub reversed: bool,
+    /// Whether the text is hidden. It still takes up space and has a background color.
+    pub hidden: bool,
+    /// Whether there is a line over the text. Not widely supported.
+    pub overlined: bool,
 }
 
 impl Attributes {
@@ -418,12 +786,32 @@ impl Attributes {
             intensity: Intensity::Normal,
             italic: false,
             underlined: false,
+            underline_style: UnderlineStyle::Straight,
             blinking: false,
             crossed_out: false,
+            reversed: false,
+            hidden: false,
+            overlined: false,
         }
     }
 }
 
+/// The style of an underline.
+#[derive(Debug, Default, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum UnderlineStyle {
+    /// A single straight line.
+    #[default]
+    Straight,
+    /// Two straight lines.
+    Double,
+    /// A curly line, often used to mark spelling mistakes.
+    Curly,
+    /// A dotted line.
+    Dotted,
+    /// A dashed line.
+    Dashed,
+}
+
 /// The intensity of text.
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Intensity {
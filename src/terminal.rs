@@ -1,18 +1,31 @@
-use std::cmp::min;
+use std::cmp::{max, min};
 use std::error::Error as StdError;
 use std::fmt::{self, Display, Formatter};
+use std::future::Future;
 use std::io::{self, IoSliceMut, Read};
+use std::ops::Range;
 #[cfg(unix)]
 use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
 #[cfg(windows)]
 use std::os::windows::io::{AsRawHandle, FromRawHandle, IntoRawHandle, RawHandle};
+use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
 
-use os_pipe::PipeReader;
+use os_pipe::{PipeReader, PipeWriter};
 
-use crate::backend::{Backend, Bound, ReadEvents, TerminalEvent, TerminalMouseKind, Tty};
-use crate::buffer::{Buffer, CellKind, Grid};
-use crate::{Color, Element, Input, Intensity, Mouse, MouseButton, MouseKind, Output, Style, Vec2};
+use crate::backend::{escape, Backend, Bound, ReadEvents, TerminalEvent, TerminalMouseKind, Tty};
+use crate::buffer::{Buffer, Cell, CellKind, Grid};
+use crate::output::Ext as _;
+use crate::sequence;
+use crate::timer::Timer;
+use crate::width::{self, WidthTable};
+use crate::{
+    Attributes, Capabilities, Color, Element, Input, Intensity, KeyboardEnhancements, Mouse,
+    MouseButton, MouseKind, Output, Palette, Request, Style, UnderlineStyle, Vec2,
+};
 
 static TERMINAL_EXISTS: AtomicBool = AtomicBool::new(false);
 
@@ -24,31 +37,82 @@ static TERMINAL_EXISTS: AtomicBool = AtomicBool::new(false);
 /// Terminals automatically capture all standard output and standard error while they are alive,
 /// and print it when they are dropped. However, you can also take ownership of it via the
 /// [`take_captured`](Self::take_captured) method.
+///
+/// Terminals created with [`new`](Self::new) take up the whole screen, while terminals created with
+/// [`inline`](Self::inline) draw below the cursor and leave their last frame on the screen.
 #[derive(Debug)]
 pub struct Terminal<B: Backend> {
-    /// Only [`None`] during destruction of the type.
+    /// Only [`None`] during destruction of the type or after the terminal was
+    /// [closed](Error::Closed).
     backend: Option<B::Bound>,
-    /// The previous title of the terminal.
-    title: String,
+    /// The previous title of the terminal, or [`None`] if it needs to be set again.
+    title: Option<String>,
+    /// The title used when the element doesn't have one.
+    fallback_title: String,
+    /// The icon name of the terminal, if it is set separately from the title.
+    icon_name: Option<String>,
+    /// Whether the terminal draws inline below the cursor instead of on the alternate screen.
+    inline: bool,
+    /// The size of the whole screen. When inline the buffers only cover part of it.
+    screen_size: Vec2<u16>,
+    /// The line of the screen that the buffers start at. This is always zero when not inline.
+    top: u16,
+    /// Whether every cell should be drawn on the next frame, because the screen's contents are
+    /// not known.
+    redraw: bool,
     /// Holds the previous frame to diff against.
     old_buffer: Buffer,
     /// Is always a clear buffer, kept around to avoid cloning the buffer each draw.
     buffer: Buffer,
-    /// The current position of the cursor.
+    /// The current position of the cursor, relative to `top`.
     ///
     /// This is the actual position of the cursor, unlike `old_buffer.cursor` which stores the
     /// position of the cursor after drawing.
     cursor_pos: Vec2<u16>,
+    /// Whether `cursor_pos` is known to be correct, allowing the cursor to be moved relative to
+    /// it. Resizing the terminal can move the cursor.
+    cursor_known: bool,
     /// The current style being written with.
     style: Style,
     /// The captured stdout and stderr.
     captured: Option<PipeReader>,
+    /// The write end of the pipe that captures stdout and stderr, kept to capture them again after
+    /// the terminal is released.
+    capture_pipe: Option<PipeWriter>,
     /// The held down mouse button.
     mouse: Option<MouseButton>,
+    /// The last press of a mouse button, used to count clicks in quick succession.
+    last_click: Option<Click>,
+    /// The longest time between the presses of a double or triple click.
+    click_interval: Duration,
+    /// How many cells the mouse can move between the presses of a double or triple click.
+    click_distance: u16,
+    /// Used to wake the terminal when a redraw is requested.
+    redraw_handle: RedrawHandle,
+    /// The minimum time between frames drawn by [`draw`](Self::draw).
+    frame_interval: Duration,
+    /// When the last frame was rendered.
+    last_render: Option<Instant>,
+    /// The widths of characters measured when the terminal was first set up.
+    widths: Option<Arc<WidthTable>>,
+    /// What the terminal supports, which styles are restricted to when drawing.
+    capabilities: Capabilities,
+    /// Requests made by elements while handling input that haven't been carried out yet.
+    requests: Vec<Request>,
+    /// The inputs of the key sequence in progress.
+    pending_sequence: Vec<Input>,
+    /// How long to wait for the next input of a key sequence, or `None` to wait forever.
+    sequence_timeout: Option<Duration>,
+    /// When the key sequence in progress times out.
+    sequence_deadline: Option<Instant>,
+    /// Whether the next input handled is the last input of a key sequence that timed out.
+    sequence_timed_out: bool,
+    /// Whether the key sequence in progress has changed since [`draw`](Self::draw) last returned.
+    sequence_changed: bool,
 }
 
 impl<B: Backend> Terminal<B> {
-    /// Create a new terminal with the given backend.
+    /// Create a new terminal with the given backend that takes up the whole screen.
     ///
     /// # Panics
     ///
@@ -58,21 +122,218 @@ impl<B: Backend> Terminal<B> {
     ///
     /// Fails if setting up the terminal fails.
     pub fn new(backend: B) -> Result<Self, Error<B::Error>> {
+        Self::bind(backend, None)
+    }
+
+    /// Create a new terminal with the given backend that draws inline, in the lines below the
+    /// cursor.
+    ///
+    /// `height` lines are reserved initially, scrolling the screen if there isn't enough space.
+    /// After that the height is adjusted on each draw to fit the element's
+    /// [ideal height](Element::ideal_height), up to the height of the screen. When the terminal is
+    /// cleaned up the last frame is left on the screen and the cursor is placed below it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the backend is not a dummy and a terminal already exists.
+    ///
+    /// # Errors
+    ///
+    /// Fails if setting up the terminal fails.
+    pub fn inline(backend: B, height: u16) -> Result<Self, Error<B::Error>> {
+        Self::bind(backend, Some(height))
+    }
+
+    fn bind(backend: B, inline_height: Option<u16>) -> Result<Self, Error<B::Error>> {
         if !B::is_dummy() && TERMINAL_EXISTS.swap(true, Ordering::Acquire) {
             panic!("Terminal already exists!");
         }
 
-        let (tty, captured) = if B::is_dummy() {
-            (Tty::dummy(), None)
+        let (tty, captured, capture_pipe) = if B::is_dummy() {
+            (Tty::dummy(), None, None)
         } else {
-            let (tty, captured) = Tty::new().map_err(Error::Stdio)?;
-            (tty, Some(captured))
+            let (captured, capture_pipe) = os_pipe::pipe().map_err(Error::Stdio)?;
+            let tty = Tty::new(&capture_pipe).map_err(Error::Stdio)?;
+            (tty, Some(captured), Some(capture_pipe))
         };
 
         let mut backend = backend.bind(tty)?;
+        let capabilities = backend.capabilities()?;
+
+        let mut terminal = Self {
+            backend: Some(backend),
+            title: None,
+            fallback_title: "Toon App".to_owned(),
+            icon_name: None,
+            inline: inline_height.is_some(),
+            screen_size: Vec2::default(),
+            top: 0,
+            redraw: false,
+            old_buffer: Buffer::from(Grid::new(Vec2::default())),
+            buffer: Buffer::from(Grid::new(Vec2::default())),
+            cursor_pos: Vec2::default(),
+            cursor_known: false,
+            style: Style::default(),
+            captured,
+            capture_pipe,
+            mouse: None,
+            last_click: None,
+            click_interval: Duration::from_millis(500),
+            click_distance: 0,
+            redraw_handle: RedrawHandle::default(),
+            frame_interval: Duration::from_secs(1) / 60,
+            last_render: None,
+            widths: None,
+            capabilities,
+            requests: Vec::new(),
+            pending_sequence: Vec::new(),
+            sequence_timeout: Some(Duration::from_secs(1)),
+            sequence_deadline: None,
+            sequence_timed_out: false,
+            sequence_changed: false,
+        };
+        terminal.set_up(inline_height)?;
+
+        Ok(terminal)
+    }
+
+    /// Set up the newly bound or resumed backend and reset the state of the terminal to match it.
+    ///
+    /// `height` is the number of lines to reserve when inline.
+    fn set_up(&mut self, height: Option<u16>) -> Result<(), Error<B::Error>> {
+        let backend = self.backend.as_mut().ok_or(Error::Closed)?;
+        self.screen_size = backend.size()?;
+
+        self.top = if self.inline {
+            // Start on the line after the cursor unless it's at the start of a line.
+            let cursor = backend.cursor_pos()?;
+            if cursor.x == 0 {
+                cursor.y
+            } else {
+                cursor.y + 1
+            }
+        } else {
+            backend.enter_alternate_screen()?;
+            0
+        };
 
         backend.hide_cursor()?;
-        backend.set_cursor_pos(Vec2::default())?;
+        backend.set_cursor_pos(Vec2::new(
+            0,
+            min(self.top, self.screen_size.y.saturating_sub(1)),
+        ))?;
+        self.cursor_pos = Vec2::default();
+        self.cursor_known = true;
+        self.reset_style()?;
+
+        self.title = None;
+        self.redraw = false;
+        self.old_buffer = Buffer::from(Grid::new(Vec2::new(self.screen_size.x, 0)));
+        self.buffer = Buffer::from(Grid::new(Vec2::new(self.screen_size.x, 0)));
+        self.mouse = None;
+        self.last_click = None;
+        self.last_render = None;
+
+        self.grow(height.unwrap_or(self.screen_size.y))?;
+
+        // The cursor is now at the start of a line that will be drawn on, so characters can be
+        // measured there. The widths are kept when the terminal is set up again.
+        if self.widths.is_none() && self.buffer.grid.height() > 0 {
+            let widths = self.bound()?.measure_widths(width::PROBES)?;
+            self.widths = Some(Arc::new(widths));
+        }
+
+        Ok(())
+    }
+
+    /// Temporarily release the terminal to run a function, for example to open an editor or pager
+    /// in a child process.
+    ///
+    /// The backend is reset and standard output and standard error stop being captured while the
+    /// function runs. After it returns the backend is set up again, and the next draw redraws the
+    /// whole screen. When inline, the last frame is left on the screen and drawing continues below
+    /// the cursor.
+    ///
+    /// # Errors
+    ///
+    /// Fails if releasing or setting up the terminal again fails. If that happens the backend is
+    /// reset and the terminal is closed: the function isn't run if releasing failed, and all later
+    /// calls that use the backend fail with [`Error::Closed`].
+    pub fn with_released<T, F: FnOnce() -> T>(&mut self, f: F) -> Result<T, Error<B::Error>> {
+        let height = if self.inline {
+            self.leave_inline()?;
+            Some(self.old_buffer.grid.height())
+        } else {
+            None
+        };
+
+        let res = self.release_with(f).and_then(|res| {
+            self.set_up(height)?;
+            Ok(res)
+        });
+        if res.is_err() {
+            self.close();
+        }
+        res
+    }
+
+    /// Suspend the backend and run the function, then resume the backend.
+    ///
+    /// If the TTY can't be opened again the backend is dropped while suspended.
+    fn release_with<T, F: FnOnce() -> T>(&mut self, f: F) -> Result<T, Error<B::Error>> {
+        let backend = self.backend.as_mut().ok_or(Error::Closed)?;
+        let tty = backend.suspend()?;
+
+        let capture_pipe = &self.capture_pipe;
+        let reopened = tty.cleanup().and_then(|()| {
+            let res = f();
+            let tty = match capture_pipe {
+                Some(pipe) => Tty::new(pipe)?,
+                None => Tty::dummy(),
+            };
+            Ok((res, tty))
+        });
+        let (res, tty) = match reopened {
+            Ok(reopened) => reopened,
+            Err(e) => {
+                // Suspending the backend has already reset the terminal.
+                self.backend = None;
+                return Err(Error::Stdio(e));
+            }
+        };
+        backend.resume(tty)?;
+
+        Ok(res)
+    }
+
+    /// Reset the backend after releasing or setting it up again failed, so that the terminal can
+    /// no longer be used.
+    fn close(&mut self) {
+        if let Some(backend) = self.backend.take() {
+            let _ = backend.reset().map(Tty::cleanup);
+        }
+    }
+
+    /// Suspend the process, as if Ctrl+Z had been pressed in a program that isn't in raw mode.
+    ///
+    /// Since raw mode stops the terminal from sending SIGTSTP itself, call this when your app
+    /// receives Ctrl+Z. The terminal is [released](Self::with_released) until the process is
+    /// continued, for example by the shell's `fg` command.
+    ///
+    /// # Errors
+    ///
+    /// Fails if releasing or setting up the terminal again fails.
+    #[cfg(all(unix, feature = "suspend"))]
+    #[cfg_attr(feature = "doc_cfg", doc(cfg(all(unix, feature = "suspend"))))]
+    pub fn suspend(&mut self) -> Result<(), Error<B::Error>> {
+        self.with_released(|| {
+            unsafe { libc::raise(libc::SIGTSTP) };
+        })
+    }
+
+    /// Reset the style being written with to the default.
+    fn reset_style(&mut self) -> Result<(), Error<B::Error>> {
+        let backend = self.backend.as_mut().ok_or(Error::Closed)?;
         backend.set_foreground(Color::Default)?;
         backend.set_background(Color::Default)?;
         backend.set_intensity(Intensity::Normal)?;
@@ -80,23 +341,55 @@ impl<B: Backend> Terminal<B> {
         backend.set_underlined(false)?;
         backend.set_blinking(false)?;
         backend.set_crossed_out(false)?;
+        backend.set_underline_style(UnderlineStyle::Straight)?;
+        backend.set_underline_color(Color::Default)?;
+        backend.set_reversed(false)?;
+        backend.set_hidden(false)?;
+        backend.set_overlined(false)?;
+        backend.set_link(None)?;
+        self.style = Style::default();
+        Ok(())
+    }
 
-        let buffer = Buffer::from(Grid::new(backend.size()?));
+    /// Increase the height of the buffers, scrolling the screen up if they would go past the
+    /// bottom of it. Heights larger than the screen are clamped.
+    fn grow(&mut self, height: u16) -> Result<(), Error<B::Error>> {
+        let height = min(height, self.screen_size.y);
+        if height <= self.buffer.grid.height() {
+            return Ok(());
+        }
 
-        Ok(Self {
-            backend: Some(backend),
-            title: String::new(),
-            old_buffer: buffer.clone(),
-            buffer,
-            cursor_pos: Vec2::default(),
-            style: Style::default(),
-            captured,
-            mouse: None,
-        })
+        let overflow = (self.top + height).saturating_sub(self.screen_size.y);
+        if overflow > 0 {
+            let bottom = self.screen_size.y - 1;
+            let backend = self.backend.as_mut().ok_or(Error::Closed)?;
+            backend.set_cursor_pos(Vec2::new(0, bottom))?;
+            backend.new_lines(overflow)?;
+
+            // The contents of the screen have moved up along with the buffers.
+            self.top -= overflow;
+            self.cursor_pos = Vec2::new(0, bottom - self.top);
+            self.cursor_known = true;
+        }
+
+        self.buffer.grid.resize_height(height);
+        self.old_buffer.grid.resize_height(height);
+
+        Ok(())
     }
 
     /// Draw an element to the terminal and wait for an event. If multiple events occur they will
-    /// all be returned, but this function will never return an empty vector.
+    /// all be returned.
+    ///
+    /// An empty vector is returned if a redraw was requested through a
+    /// [`RedrawHandle`](Self::redraw_handle), a frame [requested](Output::request_frame_at) by
+    /// the element is due or the [key sequence in progress](Self::pending_sequence) changed, so
+    /// that the element can be rebuilt with fresh state and drawn again.
+    ///
+    /// Frames are drawn at most at the [maximum frame rate](Self::set_max_fps). If this is called
+    /// again too soon drawing is delayed, and inputs that occur in the meantime are handled by the
+    /// new element. This way bursts of inputs such as resizes and mouse movements only cause one
+    /// frame to be drawn.
     ///
     /// The future produced by this function can be dropped, in which case the terminal will stop
     /// reading input.
@@ -105,93 +398,459 @@ impl<B: Backend> Terminal<B> {
     ///
     /// Fails when drawing to the backend fails.
     pub async fn draw<E: Element>(&mut self, element: E) -> Result<Vec<E::Event>, Error<B::Error>> {
+        loop {
+            // Wait until the next frame can be drawn.
+            while let Some(next_render) = self
+                .last_render
+                .map(|last| last + self.frame_interval)
+                .filter(|&next_render| next_render > Instant::now())
+            {
+                match self.read_input(Some(next_render)).await? {
+                    Next::Input(input) => {
+                        let events = self.handle(&element, input);
+                        self.perform_requests()?;
+                        let sequence_changed = std::mem::take(&mut self.sequence_changed);
+                        if !events.is_empty() || sequence_changed {
+                            return Ok(events);
+                        }
+                    }
+                    Next::Resize | Next::Frame => {}
+                    Next::Redraw => return Ok(Vec::new()),
+                }
+            }
+
+            self.render(&element)?;
+
+            loop {
+                match self.read_input(self.old_buffer.next_frame).await? {
+                    Next::Input(input) => {
+                        let events = self.handle(&element, input);
+                        self.perform_requests()?;
+                        let sequence_changed = std::mem::take(&mut self.sequence_changed);
+                        if !events.is_empty() || sequence_changed {
+                            return Ok(events);
+                        }
+                    }
+                    Next::Resize => break,
+                    Next::Redraw | Next::Frame => return Ok(Vec::new()),
+                }
+            }
+        }
+    }
+
+    /// Set the maximum number of frames per second that [`draw`](Self::draw) draws. Zero removes
+    /// the limit.
+    ///
+    /// The default is 60.
+    pub fn set_max_fps(&mut self, max_fps: u16) {
+        self.frame_interval = if max_fps == 0 {
+            Duration::default()
+        } else {
+            Duration::from_secs(1) / u32::from(max_fps)
+        };
+    }
+
+    /// Set how long to wait for the next input of a key sequence before giving up on it, or `None`
+    /// to wait forever.
+    ///
+    /// When the timeout passes, sequences that end with the inputs so far are triggered, and the
+    /// rest are abandoned. See [`OnSequence`](crate::OnSequence) for more.
+    ///
+    /// The default is one second.
+    pub fn set_sequence_timeout(&mut self, timeout: Option<Duration>) {
+        self.sequence_timeout = timeout;
+    }
+
+    /// Set the longest time between the presses of a double or triple click.
+    ///
+    /// Mouse inputs report how many times their button was [clicked](crate::Mouse::clicks) in
+    /// quick succession. The default is 500 milliseconds.
+    pub fn set_click_interval(&mut self, interval: Duration) {
+        self.click_interval = interval;
+    }
+
+    /// Set how many cells the mouse can move in either direction between the presses of a double
+    /// or triple click.
+    ///
+    /// The default is zero, so the presses have to be in the same cell.
+    pub fn set_click_distance(&mut self, distance: u16) {
+        self.click_distance = distance;
+    }
+
+    /// Get the inputs of the key sequence in progress, for example to show a hint of the inputs
+    /// that can follow them.
+    #[must_use]
+    pub fn pending_sequence(&self) -> &[Input] {
+        &self.pending_sequence
+    }
+
+    /// Set the title used when the element being drawn doesn't have a
+    /// [title](Element::title).
+    ///
+    /// The default is "Toon App".
+    pub fn set_fallback_title(&mut self, title: impl Into<String>) {
+        self.fallback_title = title.into();
+        self.title = None;
+    }
+
+    /// Set the icon name of the terminal, which some terminals show in their tab or task bar
+    /// instead of the title, or make it follow the title again with `None`.
+    ///
+    /// By default the icon name follows the title.
+    ///
+    /// # Errors
+    ///
+    /// Fails if writing to the backend fails.
+    pub fn set_icon_name(&mut self, icon_name: Option<&str>) -> Result<(), Error<B::Error>> {
+        self.icon_name = icon_name.map(str::to_owned);
+        match icon_name {
+            Some(icon_name) => self.bound()?.set_icon_name(icon_name)?,
+            // Set the title again, which sets the icon name to it.
+            None => self.title = None,
+        }
+        Ok(())
+    }
+
+    /// Render an element to the terminal without waiting for input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the element returns an error from writing its title to a string.
+    ///
+    /// # Errors
+    ///
+    /// Fails when drawing to the backend fails.
+    pub fn render<E: Element>(&mut self, element: E) -> Result<(), Error<B::Error>> {
+        self.last_render = Some(Instant::now());
+        let _widths = self.widths.clone().map(width::Scope::enter);
+
         // Update title
-        let mut old_title_bytes = self.title.bytes();
-        let title_is_same = !self.title.is_empty()
-            && element
-                .title(&mut crate::util::WriteFn(|s| {
-                    for byte in s.bytes() {
-                        if old_title_bytes.next() != Some(byte) {
-                            return Err(fmt::Error);
+        let title_is_same = match &self.title {
+            Some(old_title) => {
+                let mut old_title_bytes = old_title.bytes();
+                let mut written = false;
+                element
+                    .title(&mut crate::util::WriteFn(|s| {
+                        written |= !s.is_empty();
+                        for byte in s.bytes() {
+                            if old_title_bytes.next() != Some(byte) {
+                                return Err(fmt::Error);
+                            }
                         }
+                        Ok(())
+                    }))
+                    .is_ok()
+                    && if written {
+                        old_title_bytes.len() == 0
+                    } else {
+                        *old_title == self.fallback_title
                     }
-                    Ok(())
-                }))
-                .is_ok()
-            && old_title_bytes.len() == 0;
+            }
+            None => false,
+        };
         if !title_is_same {
-            self.title.clear();
-            element.title(&mut self.title).unwrap();
-            if self.title.is_empty() {
-                self.title.push_str("Toon App");
+            let mut title = self.title.take().unwrap_or_default();
+            title.clear();
+            element.title(&mut title).unwrap();
+            if title.is_empty() {
+                title.push_str(&self.fallback_title);
+            }
+            let backend = self.backend.as_mut().ok_or(Error::Closed)?;
+            backend.set_title(&title)?;
+            // Setting the title may also set the icon name.
+            if let Some(icon_name) = &self.icon_name {
+                backend.set_icon_name(icon_name)?;
             }
-            self.backend.as_mut().unwrap().set_title(&self.title)?;
+            self.title = Some(title);
         }
 
-        loop {
-            element.draw(&mut self.buffer);
+        let inline_height = if self.inline {
+            let height = element.ideal_height(self.screen_size.x, Some(self.screen_size.y));
+            Some(min(height, self.screen_size.y))
+        } else {
+            None
+        };
+
+        match inline_height {
+            Some(height) if height < self.buffer.grid.height() => {
+                // Draw into the top of the buffer so the lines below it are cleared.
+                let size = Vec2::new(self.screen_size.x, height);
+                element.draw(&mut (&mut self.buffer).area((0, 0), size));
+            }
+            Some(height) => {
+                self.grow(height)?;
+                element.draw(&mut self.buffer);
+            }
+            None => element.draw(&mut self.buffer),
+        }
 
-            self.diff()?;
-            self.backend_mut().flush()?;
+        self.diff()?;
+        self.bound()?.flush()?;
 
-            self.old_buffer.reset();
-            std::mem::swap(&mut self.old_buffer, &mut self.buffer);
+        self.requests.append(&mut self.buffer.requests);
+        self.perform_requests()?;
 
-            loop {
-                let input = match self.backend_mut().read_event().await? {
-                    TerminalEvent::Key(key) => Input::Key(key),
-                    TerminalEvent::Mouse(mouse) => Input::Mouse(Mouse {
-                        kind: match mouse.kind {
-                            TerminalMouseKind::Press(button) => {
-                                self.mouse = Some(button);
-                                MouseKind::Press(button)
-                            }
-                            TerminalMouseKind::Release => match self.mouse.take() {
-                                Some(button) => MouseKind::Release(button),
-                                None => continue,
-                            },
-                            TerminalMouseKind::Move => match self.mouse {
-                                Some(button) => MouseKind::Drag(button),
-                                None => MouseKind::Move,
-                            },
-                            TerminalMouseKind::ScrollUp => MouseKind::ScrollUp,
-                            TerminalMouseKind::ScrollDown => MouseKind::ScrollDown,
-                        },
-                        at: mouse.at,
-                        size: self.buffer.size(),
-                        modifiers: mouse.modifiers,
-                    }),
-                    TerminalEvent::Resize(size) if size == self.buffer.grid.size() => continue,
-                    TerminalEvent::Resize(size) => {
-                        self.buffer.grid.resize_width(size.x);
-                        self.old_buffer.grid.resize_width(size.x);
-
-                        self.buffer
-                            .grid
-                            .resize_height_with_anchor(size.y, self.cursor_pos.y);
-                        self.old_buffer
-                            .grid
-                            .resize_height_with_anchor(size.y, self.cursor_pos.y);
-
-                        self.cursor_pos.x = min(self.cursor_pos.x, size.x - 1);
-                        self.cursor_pos.y = min(self.cursor_pos.y, size.y - 1);
-
-                        break;
+        self.old_buffer.reset();
+        std::mem::swap(&mut self.old_buffer, &mut self.buffer);
+
+        if let Some(height) = inline_height {
+            self.buffer.grid.resize_height(height);
+            self.old_buffer.grid.resize_height(height);
+        }
+
+        Ok(())
+    }
+
+    /// Wait for the next input to the terminal.
+    ///
+    /// This returns [`None`] when the terminal needs to be [rendered](Self::render) again, either
+    /// because it was resized, because a redraw was requested through a
+    /// [`RedrawHandle`](Self::redraw_handle) or because a frame
+    /// [requested](Output::request_frame_at) by the last rendered element is due.
+    ///
+    /// The future produced by this function can be dropped, in which case the terminal will stop
+    /// reading input.
+    ///
+    /// # Errors
+    ///
+    /// Fails when reading from the backend fails.
+    pub async fn next_input(&mut self) -> Result<Option<Input>, Error<B::Error>> {
+        Ok(match self.read_input(self.old_buffer.next_frame).await? {
+            Next::Input(input) => Some(input),
+            Next::Resize | Next::Redraw | Next::Frame => None,
+        })
+    }
+
+    /// Pass an input to an element, returning the events it produced.
+    ///
+    /// [Requests](Request) the element makes are carried out the next time the terminal
+    /// [renders](Self::render), while [`draw`](Self::draw) carries them out as soon as it has
+    /// handled the input. If the input continues a [key sequence](crate::OnSequence), it is kept as
+    /// part of the [sequence in progress](Self::pending_sequence).
+    pub fn handle<E: Element>(&mut self, element: E, input: Input) -> Vec<E::Event> {
+        let _widths = self.widths.clone().map(width::Scope::enter);
+        let timed_out = std::mem::take(&mut self.sequence_timed_out);
+        let pending = std::mem::take(&mut self.pending_sequence);
+        let was_pending = !pending.is_empty();
+
+        let (mut events, state) = sequence::handle(&element, input.clone(), pending, timed_out);
+        self.requests.append(&mut events.requests);
+
+        let mut pending = state.pending;
+        if state.continued {
+            pending.push(input);
+        } else {
+            pending.clear();
+        }
+        self.sequence_deadline = match self.sequence_timeout {
+            Some(timeout) if !pending.is_empty() => Some(Instant::now() + timeout),
+            _ => None,
+        };
+        self.sequence_changed |= was_pending || timed_out || !pending.is_empty();
+        self.pending_sequence = pending;
+
+        events.events
+    }
+
+    /// Carry out the requests made by elements.
+    fn perform_requests(&mut self) -> Result<(), Error<B::Error>> {
+        if self.requests.is_empty() {
+            return Ok(());
+        }
+        for request in std::mem::take(&mut self.requests) {
+            let backend = self.bound()?;
+            match request {
+                Request::SetClipboard(text) => backend.set_clipboard(&text)?,
+                Request::Bell => backend.bell()?,
+                Request::Notify { title, body } => backend.notify(&title, &body)?,
+            }
+        }
+        self.bound()?.flush()?;
+        Ok(())
+    }
+
+    /// Get a handle that can be used to make the terminal render again from other tasks or threads.
+    #[must_use]
+    pub fn redraw_handle(&self) -> RedrawHandle {
+        self.redraw_handle.clone()
+    }
+
+    /// Read the next input, stopping early if a redraw is requested or the deadline is reached.
+    async fn read_input(&mut self, deadline: Option<Instant>) -> Result<Next, Error<B::Error>> {
+        let deadline = match (deadline, self.sequence_deadline) {
+            (Some(deadline), Some(sequence_deadline)) => Some(min(deadline, sequence_deadline)),
+            (deadline, sequence_deadline) => deadline.or(sequence_deadline),
+        };
+        loop {
+            let event = ReadEvent {
+                event: Box::pin(self.backend.as_mut().ok_or(Error::Closed)?.read_event()),
+                redraw: &self.redraw_handle.0,
+                deadline: deadline.map(Timer::at),
+            };
+            let event = match event.await {
+                Wake::Event(event) => event?,
+                Wake::Redraw => return Ok(Next::Redraw),
+                Wake::Deadline => {
+                    if self.sequence_deadline.map_or(false, |sequence_deadline| {
+                        sequence_deadline <= Instant::now()
+                    }) {
+                        // Handle the last input of the sequence again, so that it can complete
+                        // sequences that were waiting to see if a longer one would continue.
+                        self.sequence_deadline = None;
+                        if let Some(input) = self.pending_sequence.pop() {
+                            self.sequence_timed_out = true;
+                            return Ok(Next::Input(input));
+                        }
                     }
-                };
+                    return Ok(Next::Frame);
+                }
+            };
+
+            return Ok(Next::Input(match event {
+                TerminalEvent::Key(key) => Input::Key(key),
+                TerminalEvent::Paste(text) => Input::Paste(text),
+                TerminalEvent::Focus(focused) => Input::Focus(focused),
+                TerminalEvent::Mouse(mouse) => {
+                    let kind = match mouse.kind {
+                        TerminalMouseKind::Press(button) => {
+                            self.mouse = Some(button);
+                            self.click(button, mouse.at);
+                            MouseKind::Press(button)
+                        }
+                        TerminalMouseKind::Release => match self.mouse.take() {
+                            Some(button) => MouseKind::Release(button),
+                            None => continue,
+                        },
+                        TerminalMouseKind::Move => match self.mouse {
+                            Some(button) => MouseKind::Drag(button),
+                            None => MouseKind::Move,
+                        },
+                        TerminalMouseKind::ScrollUp => MouseKind::ScrollUp,
+                        TerminalMouseKind::ScrollDown => MouseKind::ScrollDown,
+                    };
 
-                let mut events = crate::events::Vector(Vec::new());
-                element.handle(input, &mut events);
-                if !events.0.is_empty() {
-                    return Ok(events.0);
+                    // Ignore mouse events outside of the drawn area when inline.
+                    let size = self.buffer.size();
+                    let y = match mouse.at.y.checked_sub(self.top) {
+                        Some(y) if y < size.y => y,
+                        _ => continue,
+                    };
+
+                    let clicks = match kind {
+                        MouseKind::Press(_) | MouseKind::Release(_) | MouseKind::Drag(_) => {
+                            self.last_click.map_or(1, |click| click.count)
+                        }
+                        MouseKind::Move | MouseKind::ScrollDown | MouseKind::ScrollUp => 0,
+                    };
+
+                    Input::Mouse(Mouse {
+                        kind,
+                        at: Vec2::new(mouse.at.x, y),
+                        size,
+                        modifiers: mouse.modifiers,
+                        clicks,
+                    })
                 }
+                TerminalEvent::Resize(size) if size == self.screen_size => continue,
+                TerminalEvent::Resize(size) => {
+                    self.resize(size);
+                    return Ok(Next::Resize);
+                }
+            }));
+        }
+    }
+
+    /// Count a press of a mouse button as a click, following the last one if it was close enough in
+    /// time and space.
+    fn click(&mut self, button: MouseButton, at: Vec2<u16>) {
+        let now = Instant::now();
+        let distance = |a: u16, b: u16| max(a, b) - min(a, b);
+
+        let count = match self.last_click {
+            Some(click)
+                if click.button == button
+                    && now.duration_since(click.time) <= self.click_interval
+                    && distance(click.at.x, at.x) <= self.click_distance
+                    && distance(click.at.y, at.y) <= self.click_distance =>
+            {
+                click.count.saturating_add(1)
             }
+            _ => 1,
+        };
+
+        self.last_click = Some(Click {
+            button,
+            at,
+            time: now,
+            count,
+        });
+    }
+
+    /// Handle the screen being resized.
+    fn resize(&mut self, size: Vec2<u16>) {
+        self.screen_size = size;
+
+        self.buffer.grid.resize_width(size.x);
+        self.old_buffer.grid.resize_width(size.x);
+
+        if self.inline {
+            // Terminals reflow their contents differently when resized, so keep the buffers on
+            // the screen and redraw everything.
+            let height = min(self.buffer.grid.height(), size.y);
+            self.buffer.grid.resize_height(height);
+            self.old_buffer.grid.resize_height(height);
+            self.top = min(self.top, size.y.saturating_sub(height));
+            self.redraw = true;
+        } else {
+            self.buffer
+                .grid
+                .resize_height_with_anchor(size.y, self.cursor_pos.y);
+            self.old_buffer
+                .grid
+                .resize_height_with_anchor(size.y, self.cursor_pos.y);
         }
+
+        self.cursor_pos.x = min(self.cursor_pos.x, size.x.saturating_sub(1));
+        self.cursor_pos.y = min(self.cursor_pos.y, size.y.saturating_sub(1));
+        self.cursor_known = false;
     }
 
     /// Diffs `old_buffer` and `new_buffer` and draws them to the backend.
+    #[allow(clippy::too_many_lines)]
     fn diff(&mut self) -> Result<(), Error<B::Error>> {
-        let backend = self.backend.as_mut().unwrap();
+        let backend = self.backend.as_mut().ok_or(Error::Closed)?;
+        let top = Vec2::new(0, self.top);
+
+        // Stop the terminal from displaying partially drawn frames.
+        backend.begin_synchronized_update()?;
+
+        // Scroll lines that have moved instead of drawing them again. The background color is
+        // always the default between frames, so the revealed lines are cleared with it.
+        if !self.redraw {
+            if let Some((region, lines)) = find_scroll(&self.old_buffer.grid, &self.buffer.grid) {
+                let screen_region = region.start + self.top..region.end + self.top;
+                if backend.scroll(screen_region, lines)? {
+                    self.old_buffer.grid.scroll(region, lines);
+                    backend.set_cursor_pos(self.cursor_pos + top)?;
+                    self.cursor_known = true;
+                }
+            }
+        }
+
+        let redraw = self.redraw;
+        let width = self.buffer.grid.width();
+
+        // Text that is yet to be written at the cursor in the current style, so that contiguous
+        // cells are written together.
+        let mut text = String::new();
+        macro_rules! flush_text {
+            () => {
+                if !text.is_empty() {
+                    backend.write(&text)?;
+                    text.clear();
+                }
+            };
+        }
 
         for (y, (old_line, new_line)) in self
             .old_buffer
@@ -201,68 +860,145 @@ impl<B: Backend> Terminal<B> {
             .zip(self.buffer.grid.lines())
             .enumerate()
         {
-            for (x, (old_cell, new_cell)) in
-                old_line.cells().iter().zip(new_line.cells()).enumerate()
-            {
-                if new_cell == old_cell {
-                    continue;
-                }
-
-                let pos = Vec2::new(x as u16, y as u16);
+            let (old_cells, new_cells) = (old_line.cells(), new_line.cells());
+            let changed = |x: usize| redraw || old_cells[x] != new_cells[x];
 
-                let (new_contents, new_contents_double, new_style) = match new_cell.kind() {
+            let mut x = 0;
+            while x < new_cells.len() {
+                let (new_contents, new_contents_double, new_style) = match new_cells[x].kind() {
                     CellKind::Char {
                         contents,
                         double,
                         style,
-                    } => (contents, double, style),
-                    CellKind::Continuation => continue,
+                    } if changed(x) => (contents, double, style),
+                    _ => {
+                        x += 1;
+                        continue;
+                    }
                 };
+                let new_style = self.capabilities.restrict(new_style.clone());
 
-                macro_rules! diff_styles {
-                    ($($(.$path:ident)+ => $set_style:ident,)*) => {
-                        $(
-                            if self.style$(.$path)+ != new_style$(.$path)+ {
-                                backend.$set_style(new_style$(.$path)+)?;
-                            }
-                        )*
+                let pos = Vec2::new(x as u16, y as u16);
+
+                if self.cursor_pos != pos || !self.cursor_known {
+                    let (from, to) = (self.cursor_pos + top, pos + top);
+                    let move_cost = escape::move_cursor_cost(from, to);
+
+                    // Writing the cells in between again can be shorter than moving over them.
+                    let gap = if self.cursor_known && self.cursor_pos.y == pos.y && from.x < to.x {
+                        redrawable_text(
+                            &new_cells[usize::from(self.cursor_pos.x)..x],
+                            &self.style,
+                            self.capabilities,
+                            move_cost,
+                        )
+                    } else {
+                        None
+                    };
+
+                    if let Some(gap) = gap {
+                        text.push_str(&gap);
+                    } else {
+                        flush_text!();
+                        if self.cursor_known {
+                            backend.move_cursor(from, to)?;
+                        } else {
+                            backend.set_cursor_pos(to)?;
+                            self.cursor_known = true;
+                        }
                     }
+                    self.cursor_pos = pos;
                 }
-                diff_styles! {
-                    .foreground => set_foreground,
-                    .background => set_background,
-                    .attributes.intensity => set_intensity,
-                    .attributes.italic => set_italic,
-                    .attributes.underlined => set_underlined,
-                    .attributes.blinking => set_blinking,
-                    .attributes.crossed_out => set_crossed_out,
-                }
 
-                if self.cursor_pos != pos {
-                    backend.set_cursor_pos(pos)?;
+                if self.style != new_style {
+                    flush_text!();
+
+                    macro_rules! diff_styles {
+                        ($($(.$path:ident)+ => $set_style:ident,)*) => {
+                            $(
+                                if self.style$(.$path)+ != new_style$(.$path)+ {
+                                    backend.$set_style(new_style$(.$path)+)?;
+                                }
+                            )*
+                        }
+                    }
+                    diff_styles! {
+                        .foreground => set_foreground,
+                        .background => set_background,
+                        .underline_color => set_underline_color,
+                        .attributes.intensity => set_intensity,
+                        .attributes.italic => set_italic,
+                        .attributes.underlined => set_underlined,
+                        .attributes.underline_style => set_underline_style,
+                        .attributes.blinking => set_blinking,
+                        .attributes.crossed_out => set_crossed_out,
+                        .attributes.reversed => set_reversed,
+                        .attributes.hidden => set_hidden,
+                        .attributes.overlined => set_overlined,
+                    }
+                    if self.style.link != new_style.link {
+                        backend.set_link(new_style.link.clone())?;
+                    }
+
+                    self.style = new_style.clone();
                 }
 
-                backend.write(&new_contents)?;
+                // Runs of the same character can be repeated or erased instead of written out.
+                let mut chars = new_contents.chars();
+                if let (Some(c), None, false) = (chars.next(), chars.next(), new_contents_double) {
+                    let run = new_cells[x..]
+                        .iter()
+                        .take_while(|cell| **cell == new_cells[x])
+                        .count()
+                        .min(usize::from(u16::MAX));
+                    let literal_cost = new_contents.len() * run;
+
+                    // Erasing doesn't move the cursor, so only do it when nothing after the run
+                    // has changed.
+                    if c == ' '
+                        && new_style.attributes == Attributes::default()
+                        && new_style.link.is_none()
+                        && escape::csi_len(run as u16) < literal_cost
+                        && (x + run..new_cells.len()).all(|x| !changed(x))
+                    {
+                        flush_text!();
+                        if backend.erase_chars(run as u16)? {
+                            x += run;
+                            continue;
+                        }
+                    }
+
+                    if new_contents.len() + escape::csi_len(run as u16 - 1) < literal_cost {
+                        flush_text!();
+                        backend.write_repeated(c, run as u16)?;
+                        self.cursor_pos.x = min(pos.x + run as u16, width - 1);
+                        x += run;
+                        continue;
+                    }
+                }
 
-                self.style = new_style;
+                text.push_str(new_contents);
 
                 self.cursor_pos = Vec2::new(
-                    min(
-                        // TODO: The terminal's idea of how wide a character is doesn't always line
-                        // up with unicode-width. For example in iTerm2 the family emoji, which is
-                        // 8 wide in Unicode displays as 2 wide.
-                        pos.x + if new_contents_double { 2 } else { 1 },
-                        self.buffer.grid.width() - 1,
-                    ),
+                    min(pos.x + if new_contents_double { 2 } else { 1 }, width - 1),
                     pos.y,
                 );
+                x += 1;
             }
         }
 
+        flush_text!();
+
         // Some terminals use the background color of the cursor to fill in space created by a
         // resize, so reset it.
         backend.set_background(Color::Default)?;
         self.style.background = Color::Default;
+        // Close any open hyperlink so that nothing else written to the terminal is part of it.
+        if self.style.link.is_some() {
+            backend.set_link(None)?;
+            self.style.link = None;
+        }
+        self.redraw = false;
 
         if let Some(new_cursor) = self.buffer.cursor {
             if self.old_buffer.cursor.is_none() {
@@ -283,28 +1019,182 @@ impl<B: Backend> Terminal<B> {
             {
                 backend.set_cursor_blinking(new_cursor.blinking)?;
             }
-            if self.cursor_pos != new_cursor.pos {
-                backend.set_cursor_pos(new_cursor.pos)?;
+            if self
+                .old_buffer
+                .cursor
+                .map_or(true, |c| c.color != new_cursor.color)
+            {
+                backend.set_cursor_color(new_cursor.color)?;
+            }
+            if self.cursor_pos != new_cursor.pos || !self.cursor_known {
+                if self.cursor_known {
+                    backend.move_cursor(self.cursor_pos + top, new_cursor.pos + top)?;
+                } else {
+                    backend.set_cursor_pos(new_cursor.pos + top)?;
+                    self.cursor_known = true;
+                }
+                self.cursor_pos = new_cursor.pos;
             }
         } else if self.old_buffer.cursor.is_some() {
             backend.hide_cursor()?;
         }
 
+        backend.end_synchronized_update()?;
+
         Ok(())
     }
 
-    /// Get a reference to the terminal's backend.
+    /// Get what the terminal supports.
+    ///
+    /// These are the [capabilities of the backend](Bound::capabilities) unless they have been
+    /// overridden with [`set_capabilities`](Self::set_capabilities).
     #[must_use]
-    pub fn backend(&self) -> &B::Bound {
-        self.backend.as_ref().unwrap()
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Override what the terminal supports, for example to let users force colors on or off.
+    ///
+    /// The whole screen is drawn again on the next frame.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+        self.redraw = true;
+    }
+
+    /// Query the colors the terminal displays, for example to choose between a light and a dark
+    /// theme with [`Palette::is_dark`].
+    ///
+    /// This waits for the terminal to respond, which takes up to a second if it doesn't support
+    /// being queried, so the result should be kept instead of querying on every frame.
+    ///
+    /// # Errors
+    ///
+    /// Fails if querying the backend fails.
+    pub fn palette(&mut self) -> Result<Palette, Error<B::Error>> {
+        Ok(self.bound()?.query_palette()?)
+    }
+
+    /// Copy text to the system clipboard.
+    ///
+    /// Elements can do this while handling input by making a [`Request::SetClipboard`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if writing to the backend fails.
+    pub fn set_clipboard(&mut self, text: &str) -> Result<(), Error<B::Error>> {
+        let backend = self.bound()?;
+        backend.set_clipboard(text)?;
+        backend.flush()?;
+        Ok(())
+    }
+
+    /// Ring the terminal's bell.
+    ///
+    /// Depending on how it is configured the terminal plays a sound, flashes the screen or, when
+    /// it is in the background, marks its window as needing attention.
+    ///
+    /// Elements can do this by making a [`Request::Bell`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if writing to the backend fails.
+    pub fn bell(&mut self) -> Result<(), Error<B::Error>> {
+        let backend = self.bound()?;
+        backend.bell()?;
+        backend.flush()?;
+        Ok(())
+    }
+
+    /// Show a desktop notification, for example to tell the user that a job has finished while
+    /// the terminal is in the background. Terminals that don't support notifications ignore this,
+    /// so ring the [bell](Self::bell) as well for them to mark their window as needing attention.
+    ///
+    /// Elements can do this by making a [`Request::Notify`].
+    ///
+    /// # Errors
+    ///
+    /// Fails if writing to the backend fails.
+    pub fn notify(&mut self, title: &str, body: &str) -> Result<(), Error<B::Error>> {
+        let backend = self.bound()?;
+        backend.notify(title, body)?;
+        backend.flush()?;
+        Ok(())
+    }
+
+    /// Read the text in the system clipboard, or `None` if the terminal doesn't allow it.
+    ///
+    /// Like [`palette`](Self::palette) this waits for the terminal to respond, which takes up to a
+    /// second if it doesn't.
+    ///
+    /// # Errors
+    ///
+    /// Fails if querying the backend fails.
+    pub fn clipboard(&mut self) -> Result<Option<String>, Error<B::Error>> {
+        Ok(self.bound()?.clipboard()?)
+    }
+
+    /// Set how the terminal reports keys. By default no enhancements are enabled, so terminals
+    /// report keys the legacy way.
+    ///
+    /// Enabling them lets elements see key repeats and releases, keys that legacy input can't
+    /// distinguish and more modifiers, on terminals that support the kitty keyboard protocol.
+    ///
+    /// # Errors
+    ///
+    /// Fails if writing to the backend fails.
+    pub fn set_keyboard_enhancements(
+        &mut self,
+        enhancements: KeyboardEnhancements,
+    ) -> Result<(), Error<B::Error>> {
+        let backend = self.bound()?;
+        backend.set_keyboard_enhancements(enhancements)?;
+        backend.flush()?;
+        Ok(())
+    }
+
+    /// Get a reference to the terminal's backend.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the terminal was [closed](Error::Closed).
+    #[must_use]
+    pub fn backend(&self) -> &B::Bound {
+        self.backend.as_ref().unwrap()
     }
 
     /// Get a mutable reference to the terminal's backend.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the terminal was [closed](Error::Closed).
     #[must_use]
     pub fn backend_mut(&mut self) -> &mut B::Bound {
         self.backend.as_mut().unwrap()
     }
 
+    /// Get the terminal's backend, failing if the terminal was closed.
+    fn bound(&mut self) -> Result<&mut B::Bound, Error<B::Error>> {
+        self.backend.as_mut().ok_or(Error::Closed)
+    }
+
+    /// Move the cursor below the last drawn frame, so that it is left on the screen.
+    fn leave_inline(&mut self) -> Result<(), Error<B::Error>> {
+        self.reset_style()?;
+
+        let height = self.old_buffer.grid.height();
+        let backend = self.backend.as_mut().ok_or(Error::Closed)?;
+        if height == 0 {
+            backend.set_cursor_pos(Vec2::new(0, self.top))?;
+        } else {
+            backend.set_cursor_pos(Vec2::new(0, self.top + height - 1))?;
+            backend.new_lines(1)?;
+        }
+        backend.show_cursor()?;
+        backend.flush()?;
+
+        Ok(())
+    }
+
     /// Take the captured standard output and standard error from the terminal.
     ///
     /// The terminal will no longer print all captured data to the standard output when the program
@@ -330,10 +1220,19 @@ impl<B: Backend> Terminal<B> {
     }
 
     fn cleanup_inner(&mut self) -> Result<(), Error<B::Error>> {
+        let res = if self.inline && self.backend.is_some() {
+            self.leave_inline()
+        } else {
+            Ok(())
+        };
+
         if let Some(backend) = self.backend.take() {
             backend.reset()?.cleanup().map_err(Error::Stdio)?;
         }
+        res?;
 
+        // Close the pipe so that reading the captured output reaches the end.
+        self.capture_pipe = None;
         if let Some(mut captured) = self.captured.take() {
             io::copy(&mut captured, &mut io::stdout()).map_err(Error::Stdio)?;
         }
@@ -352,6 +1251,170 @@ impl<B: Backend> Drop for Terminal<B> {
     }
 }
 
+/// Get the text of cells that are all in the given style, so that writing it moves the cursor over
+/// them without changing them. Returns `None` if this isn't possible or the text is longer than
+/// `max_len`.
+///
+/// `style` is the style being written with, which the cells' styles are restricted to the
+/// terminal's capabilities before being compared with.
+fn redrawable_text(
+    cells: &[Cell],
+    style: &Style,
+    capabilities: Capabilities,
+    max_len: usize,
+) -> Option<String> {
+    let mut text = String::new();
+    for (i, cell) in cells.iter().enumerate() {
+        match cell.kind() {
+            CellKind::Char {
+                contents,
+                style: cell_style,
+                ..
+            } if capabilities.restrict(cell_style.clone()) == *style => text.push_str(contents),
+            // The character before the first cell has already been written.
+            CellKind::Continuation if i != 0 => {}
+            _ => return None,
+        }
+        if text.len() > max_len {
+            return None;
+        }
+    }
+    Some(text)
+}
+
+/// Find the region of the old grid to scroll and how far to scroll it up (or down if negative) so
+/// that the most lines that have changed match the new grid.
+///
+/// Scrolling is only worth it if it saves drawing at least two lines.
+fn find_scroll(old: &Grid, new: &Grid) -> Option<(Range<u16>, i16)> {
+    let height = i32::from(min(old.height(), new.height()));
+    let old = old.lines();
+    let new = new.lines();
+
+    let mut best = None;
+    let mut best_saved = 1;
+
+    for shift in 1 - height..height {
+        if shift == 0 {
+            continue;
+        }
+
+        // Find runs of lines in the new grid that match the old grid shifted by `shift` lines.
+        let mut run_start = None;
+        let mut saved = 0;
+        let ys = (-shift).max(0)..height.min(height - shift);
+        for y in ys.clone().chain(std::iter::once(ys.end)) {
+            let matches = y < ys.end && new[y as usize] == old[(y + shift) as usize];
+
+            if matches {
+                run_start.get_or_insert(y);
+                if new[y as usize] != old[y as usize] {
+                    saved += 1;
+                }
+            } else if let Some(start) = run_start.take() {
+                if saved > best_saved {
+                    best_saved = saved;
+                    // The region covers both where the lines were and where they are now.
+                    let region = if shift > 0 {
+                        start..y + shift
+                    } else {
+                        start + shift..y
+                    };
+                    best = Some((region.start as u16..region.end as u16, shift as i16));
+                }
+                saved = 0;
+            }
+        }
+    }
+
+    best
+}
+
+/// What happened while waiting for input.
+enum Next {
+    Input(Input),
+    Resize,
+    Redraw,
+    /// The deadline passed to `read_input` was reached.
+    Frame,
+}
+
+/// Reads an event from the backend, unless a redraw is requested or the deadline is reached
+/// first.
+struct ReadEvent<'a, F> {
+    event: Pin<Box<F>>,
+    redraw: &'a RedrawState,
+    deadline: Option<Timer>,
+}
+
+enum Wake<T> {
+    Event(T),
+    Redraw,
+    Deadline,
+}
+
+impl<F: Future> Future for ReadEvent<'_, F> {
+    type Output = Wake<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Store the waker before checking so that a request made in between isn't missed.
+        *self.redraw.waker.lock().unwrap() = Some(cx.waker().clone());
+        if self.redraw.requested.swap(false, Ordering::AcqRel) {
+            return Poll::Ready(Wake::Redraw);
+        }
+        if let Poll::Ready(event) = self.event.as_mut().poll(cx) {
+            return Poll::Ready(Wake::Event(event));
+        }
+        match &mut self.deadline {
+            Some(deadline) => Pin::new(deadline).poll(cx).map(|()| Wake::Deadline),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// A press of a mouse button, kept to count clicks in quick succession.
+#[derive(Debug, Clone, Copy)]
+struct Click {
+    button: MouseButton,
+    /// Where the press occurred, on the screen.
+    at: Vec2<u16>,
+    /// When the press occurred.
+    time: Instant,
+    /// How many clicks in quick succession this press was.
+    count: u8,
+}
+
+/// A handle that makes a [`Terminal`] render again, created by
+/// [`Terminal::redraw_handle`].
+///
+/// This can be cloned and sent to other tasks and threads, for example ones that update the state
+/// being displayed in the background.
+#[derive(Debug, Clone, Default)]
+pub struct RedrawHandle(Arc<RedrawState>);
+
+#[derive(Debug, Default)]
+struct RedrawState {
+    requested: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+impl RedrawHandle {
+    /// Request that the terminal renders again.
+    ///
+    /// The terminal's current or next call to [`draw`](Terminal::draw) will return an empty
+    /// vector of events, and to [`next_input`](Terminal::next_input) will return [`None`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if another thread panicked while requesting a redraw.
+    pub fn redraw(&self) {
+        self.0.requested.store(true, Ordering::Release);
+        if let Some(waker) = self.0.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+}
+
 /// An error in Toon.
 #[derive(Debug)]
 #[non_exhaustive]
@@ -360,6 +1423,9 @@ pub enum Error<B> {
     Backend(B),
     /// An error overriding the standard I/O streams.
     Stdio(io::Error),
+    /// The terminal was closed because it couldn't be set up again after being
+    /// [released](Terminal::with_released).
+    Closed,
 }
 
 impl<B> From<B> for Error<B> {
@@ -372,6 +1438,7 @@ impl<B: Display> Display for Error<B> {
         match self {
             Self::Backend(e) => e.fmt(f),
             Self::Stdio(e) => e.fmt(f),
+            Self::Closed => f.write_str("the terminal was closed"),
         }
     }
 }
@@ -380,6 +1447,7 @@ impl<B: StdError + 'static> StdError for Error<B> {
         match self {
             Self::Backend(e) => Some(e),
             Self::Stdio(e) => Some(e),
+            Self::Closed => None,
         }
     }
 }
@@ -484,9 +1552,9 @@ fn test_diff_grid() {
         },
     );
 
-    new_grid.write((15, 2), "abcd", style);
+    new_grid.write((15, 2), "abcd", style.clone());
     style.foreground = Color::Green;
-    new_grid.write((1, 5), "foo", style);
+    new_grid.write((1, 5), "foo", style.clone());
     new_grid.write((4, 6), "😃", style);
 
     let new_grid = new_grid;
@@ -517,22 +1585,21 @@ fn test_diff_grid() {
     assert_eq!(
         terminal.backend().operations,
         &[
+            Operation::BeginSynchronizedUpdate,
+            Operation::SetCursorPos(Vec2::new(15, 2)),
             Operation::SetForeground(Color::Red),
             Operation::SetBackground(Color::Blue),
             Operation::SetIntensity(Intensity::Bold),
             Operation::SetUnderlined(true),
-            Operation::SetCursorPos(Vec2::new(15, 2)),
             Operation::Write("a".to_owned()),
-            Operation::SetForeground(Color::Green),
             Operation::SetCursorPos(Vec2::new(1, 5)),
-            Operation::Write("f".to_owned()),
-            Operation::Write("o".to_owned()),
-            Operation::Write("o".to_owned()),
+            Operation::SetForeground(Color::Green),
+            Operation::Write("foo".to_owned()),
+            Operation::SetCursorPos(Vec2::new(3, 6)),
             Operation::SetForeground(Color::Default),
             Operation::SetBackground(Color::Default),
             Operation::SetIntensity(Intensity::Normal),
             Operation::SetUnderlined(false),
-            Operation::SetCursorPos(Vec2::new(3, 6)),
             Operation::Write(" ".to_owned()),
             Operation::SetForeground(Color::Green),
             Operation::SetBackground(Color::Blue),
@@ -540,6 +1607,785 @@ fn test_diff_grid() {
             Operation::SetUnderlined(true),
             Operation::Write("😃".to_owned()),
             Operation::SetBackground(Color::Default),
+            Operation::EndSynchronizedUpdate,
+        ],
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_diff_runs() {
+    use crate::backend::{Dummy, Operation};
+    use crate::output::Ext as _;
+
+    let mut old_grid = Grid::new(Vec2::new(20, 3));
+    old_grid.write((0, 0), "abcdefghij", Style::default());
+    old_grid.write((0, 2), "0123456789", Style::default());
+    let old_grid = old_grid;
+
+    let mut new_grid = old_grid.clone();
+    new_grid.write((0, 0), "A", Style::default());
+    new_grid.write((2, 0), "C", Style::default());
+    new_grid.write((0, 1), "-".repeat(20), Style::default());
+    new_grid.write((2, 2), " ".repeat(8), Style::default());
+    let new_grid = new_grid;
+
+    let mut backend = Dummy::new(old_grid.size());
+    backend.buffer.grid = old_grid.clone();
+
+    let mut terminal: Terminal<Dummy> = Terminal::new(backend).unwrap();
+    terminal.backend_mut().operations.clear();
+    terminal.old_buffer = Buffer::from(old_grid);
+    terminal.buffer = Buffer::from(new_grid.clone());
+    terminal.diff().unwrap();
+
+    assert_eq!(terminal.backend().buffer.grid, new_grid);
+
+    assert_eq!(
+        terminal.backend().operations,
+        &[
+            Operation::BeginSynchronizedUpdate,
+            // The unchanged "b" is written again instead of moving over it.
+            Operation::Write("AbC".to_owned()),
+            Operation::SetCursorPos(Vec2::new(0, 1)),
+            Operation::WriteRepeated('-', 20),
+            Operation::SetCursorPos(Vec2::new(2, 2)),
+            Operation::EraseChars(18),
+            Operation::SetBackground(Color::Default),
+            Operation::EndSynchronizedUpdate,
         ],
     );
 }
+
+#[cfg(test)]
+#[test]
+fn test_inline() {
+    use crate::backend::{Dummy, Operation};
+    use crate::{ElementExt as _, KeyPress};
+
+    let q = TerminalEvent::Key(KeyPress::from('q'));
+
+    let mut backend = Dummy::new(Vec2::new(6, 5));
+    backend.buffer.write((0, 0), "$ a", Style::default());
+    backend.buffer.write((0, 1), "$ b", Style::default());
+    backend.cursor_pos = Vec2::new(3, 1);
+
+    let mut terminal = Terminal::inline(backend, 2).unwrap();
+    terminal.set_max_fps(0);
+    assert_eq!(terminal.top, 2);
+    assert_eq!(terminal.buffer.grid.size(), Vec2::new(6, 2));
+
+    // Growing past the bottom of the screen scrolls it up.
+    terminal.backend_mut().events.push_back(q.clone());
+    let lines = crate::column(
+        crate::Static,
+        (
+            crate::span("1"),
+            crate::span("2"),
+            crate::span("3"),
+            crate::span("4"),
+        ),
+    );
+    let events = futures_lite::future::block_on(terminal.draw(lines.on('q', |_| ()))).unwrap();
+    assert_eq!(events, [()]);
+    assert_eq!(terminal.top, 1);
+    assert_eq!(
+        terminal.backend().buffer.grid.contents(),
+        ["$ b   ", "1     ", "2     ", "3     ", "4     "],
+    );
+
+    // Shrinking clears the lines that are no longer used.
+    terminal.backend_mut().events.push_back(q);
+    let line = crate::span("5").on('q', |_| ());
+    futures_lite::future::block_on(terminal.draw(line)).unwrap();
+    assert_eq!(terminal.top, 1);
+    assert_eq!(terminal.buffer.grid.size(), Vec2::new(6, 1));
+    assert_eq!(
+        terminal.backend().buffer.grid.contents(),
+        ["$ b   ", "5     ", "      ", "      ", "      "],
+    );
+
+    terminal.leave_inline().unwrap();
+    assert_eq!(terminal.backend().cursor_pos, Vec2::new(0, 2));
+
+    assert!(!terminal
+        .backend()
+        .operations
+        .contains(&Operation::EnterAlternateScreen));
+}
+
+#[cfg(test)]
+#[test]
+fn test_widths() {
+    use crate::backend::{Dummy, Operation};
+
+    let mut backend = Dummy::new(Vec2::new(4, 1));
+    backend.widths.insert("☀", 2);
+
+    let mut terminal = Terminal::new(backend).unwrap();
+    assert!(terminal
+        .backend()
+        .operations
+        .contains(&Operation::MeasureWidths));
+
+    // The measured widths are only used while the terminal is drawing.
+    let element = crate::span::<_, ()>("☀x");
+    assert_eq!(element.width(), 2);
+
+    let mut expected = Grid::new(Vec2::new(4, 1));
+    {
+        let _widths = width::Scope::enter(terminal.widths.clone().unwrap());
+        assert_eq!(element.width(), 3);
+        expected.write((0, 0), "☀x", Style::default());
+    }
+    assert_eq!(
+        expected.lines()[0].cells()[1].kind(),
+        CellKind::Continuation
+    );
+
+    terminal.render(element).unwrap();
+    assert_eq!(terminal.backend().buffer.grid, expected);
+    assert_eq!(terminal.backend().cursor_pos, Vec2::new(3, 0));
+}
+
+#[cfg(test)]
+#[test]
+fn test_capabilities() {
+    use crate::backend::{Dummy, Operation};
+    use crate::{ColorSupport, Rgb, Styled as _};
+
+    let mut backend = Dummy::new(Vec2::new(4, 1));
+    backend.capabilities.colors = ColorSupport::Ansi256;
+    backend.capabilities.italic = false;
+    backend.capabilities.undercurl = false;
+
+    let mut terminal = Terminal::new(backend).unwrap();
+    assert_eq!(terminal.capabilities().colors, ColorSupport::Ansi256);
+
+    let element = crate::span::<_, ()>("ab")
+        .foreground(Rgb::new(255, 135, 0))
+        .italic()
+        .curly_underlined();
+
+    terminal.backend_mut().operations.clear();
+    terminal.render(&element).unwrap();
+    let operations = &terminal.backend().operations;
+    assert!(
+        operations.contains(&Operation::SetForeground(Color::AnsiValue(
+            crate::AnsiColor::new_rgb(5, 2, 0)
+        )))
+    );
+    assert!(!operations.contains(&Operation::SetItalic(true)));
+    assert!(operations.contains(&Operation::SetUnderlined(true)));
+    assert!(!operations.contains(&Operation::SetUnderlineStyle(UnderlineStyle::Curly)));
+
+    // Overriding the capabilities draws everything again with the new colors.
+    let mut capabilities = terminal.capabilities();
+    capabilities.colors = ColorSupport::Monochrome;
+    terminal.set_capabilities(capabilities);
+
+    terminal.backend_mut().operations.clear();
+    terminal.render(&element).unwrap();
+    assert_eq!(
+        terminal.backend().operations[1..4],
+        [
+            Operation::SetCursorPos(Vec2::new(0, 0)),
+            Operation::SetForeground(Color::Default),
+            Operation::Write("ab".to_owned()),
+        ]
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_extended_attributes() {
+    use crate::backend::{Dummy, Operation};
+    use crate::Styled as _;
+
+    let mut terminal = Terminal::new(Dummy::new(Vec2::new(4, 1))).unwrap();
+    let element = crate::span::<_, ()>("ab")
+        .dotted_underlined()
+        .underline_color(Color::Red)
+        .reversed()
+        .overlined();
+
+    terminal.backend_mut().operations.clear();
+    terminal.render(&element).unwrap();
+    assert_eq!(
+        terminal.backend().operations[2..7],
+        [
+            Operation::SetUnderlineColor(Color::Red),
+            Operation::SetUnderlined(true),
+            Operation::SetUnderlineStyle(UnderlineStyle::Dotted),
+            Operation::SetReversed(true),
+            Operation::SetOverlined(true),
+        ]
+    );
+    assert_eq!(
+        terminal.backend().buffer.grid.lines()[0].cells()[0].style(),
+        Some(&element.style)
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_links() {
+    use crate::backend::{Dummy, Operation};
+    use crate::{Link, Styled as _};
+
+    let mut terminal = Terminal::new(Dummy::new(Vec2::new(8, 1))).unwrap();
+    let link = Link::new("https://example.com");
+    let element = crate::row(
+        crate::Static,
+        (
+            crate::span::<_, ()>("docs").link(link.clone()),
+            crate::span(" "),
+            crate::span("x").link(link.clone()),
+        ),
+    );
+
+    terminal.backend_mut().operations.clear();
+    terminal.render(element).unwrap();
+    assert_eq!(
+        terminal.backend().operations[2..8],
+        [
+            Operation::SetLink(Some(link.clone())),
+            Operation::Write("docs".to_owned()),
+            Operation::SetCursorPos(Vec2::new(5, 0)),
+            Operation::Write("x".to_owned()),
+            Operation::SetBackground(Color::Default),
+            Operation::SetLink(None),
+        ]
+    );
+    assert_eq!(
+        terminal.backend().buffer.grid.lines()[0].cells()[1].style(),
+        Some(&Style::default().link(link))
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_palette() {
+    use crate::backend::{Dummy, Operation};
+    use crate::Rgb;
+
+    let mut backend = Dummy::new(Vec2::new(4, 1));
+    backend.palette.background = Some(Rgb::new(30, 32, 42));
+
+    let mut terminal = Terminal::new(backend).unwrap();
+    assert!(!terminal
+        .backend()
+        .operations
+        .contains(&Operation::QueryPalette));
+
+    let palette = terminal.palette().unwrap();
+    assert_eq!(palette.is_dark(), Some(true));
+    assert_eq!(
+        palette.resolve(Color::Default, false),
+        Rgb::new(255, 255, 255)
+    );
+    assert_eq!(
+        terminal.backend().operations.last(),
+        Some(&Operation::QueryPalette)
+    );
+}
+
+/// A filter used in tests that makes a request whenever the element handles input.
+#[cfg(test)]
+struct RequestOnInput(Request);
+
+#[cfg(test)]
+impl<Event> crate::Filter<Event> for RequestOnInput {
+    fn handle<E: Element<Event = Event>>(
+        &self,
+        element: E,
+        input: Input,
+        events: &mut dyn crate::Events<Event>,
+    ) {
+        events.request(self.0.clone());
+        element.handle(input, events);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_clipboard() {
+    use crate::backend::{Dummy, Operation};
+    use crate::{ElementExt as _, KeyPress};
+
+    let mut backend = Dummy::new(Vec2::new(3, 1));
+    backend
+        .events
+        .push_back(TerminalEvent::Key(KeyPress::from('c')));
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.backend_mut().operations.clear();
+    terminal.set_clipboard("text").unwrap();
+    assert_eq!(terminal.clipboard().unwrap().as_deref(), Some("text"));
+    assert_eq!(
+        terminal.backend().operations,
+        [
+            Operation::SetClipboard("text".to_owned()),
+            Operation::Flush,
+            Operation::QueryClipboard
+        ]
+    );
+
+    let element = crate::span("x")
+        .on('c', |_| ())
+        .filter(RequestOnInput(Request::SetClipboard("copied".to_owned())));
+    let events = futures_lite::future::block_on(terminal.draw(&element)).unwrap();
+    assert_eq!(events, [()]);
+    assert_eq!(terminal.backend().clipboard.as_deref(), Some("copied"));
+}
+
+/// A filter used in tests that calls a function with the output after the element is drawn.
+#[cfg(test)]
+struct AfterDraw<F>(F);
+
+#[cfg(test)]
+impl<F: Fn(&mut dyn Output), Event> crate::Filter<Event> for AfterDraw<F> {
+    fn draw<E: Element>(&self, element: E, output: &mut dyn Output) {
+        element.draw(output);
+        (self.0)(output);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_notify() {
+    use crate::backend::{Dummy, Operation};
+    use crate::ElementExt as _;
+
+    let mut terminal = Terminal::new(Dummy::new(Vec2::new(3, 1))).unwrap();
+
+    terminal.backend_mut().operations.clear();
+    terminal.bell().unwrap();
+    terminal.notify("title", "body").unwrap();
+    assert_eq!(
+        terminal.backend().operations,
+        [
+            Operation::Bell,
+            Operation::Flush,
+            Operation::Notify {
+                title: "title".to_owned(),
+                body: "body".to_owned(),
+            },
+            Operation::Flush,
+        ]
+    );
+
+    // Requests made while drawing are carried out after the frame is drawn.
+    let finished = crate::span::<_, ()>("x").filter(AfterDraw(|output: &mut dyn Output| {
+        output.request(Request::Notify {
+            title: "Build".to_owned(),
+            body: "Finished".to_owned(),
+        });
+    }));
+    terminal.backend_mut().operations.clear();
+    terminal.render(&finished).unwrap();
+    let operations = &terminal.backend().operations;
+    assert_eq!(
+        operations[operations.len() - 2..],
+        [
+            Operation::Notify {
+                title: "Build".to_owned(),
+                body: "Finished".to_owned(),
+            },
+            Operation::Flush,
+        ]
+    );
+
+    // Requests made when handling input directly are carried out after the next render.
+    let ring = crate::span::<_, ()>("x").filter(RequestOnInput(Request::Bell));
+    terminal.backend_mut().operations.clear();
+    terminal.handle(&ring, Input::Focus(true));
+    assert!(terminal.backend().operations.is_empty());
+    terminal.render(&ring).unwrap();
+    let operations = &terminal.backend().operations;
+    assert_eq!(
+        operations[operations.len() - 2..],
+        [Operation::Bell, Operation::Flush]
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn test_title() {
+    use crate::backend::{Dummy, Operation};
+    use crate::ElementExt as _;
+
+    let mut terminal = Terminal::new(Dummy::new(Vec2::new(3, 1))).unwrap();
+    terminal.render(crate::span::<_, ()>("x")).unwrap();
+    assert_eq!(terminal.backend().title, "Toon App");
+
+    // The title isn't set again when it hasn't changed.
+    terminal.backend_mut().operations.clear();
+    terminal.render(crate::span::<_, ()>("x")).unwrap();
+    assert!(!terminal
+        .backend()
+        .operations
+        .iter()
+        .any(|operation| matches!(operation, Operation::SetTitle(_))));
+
+    terminal.set_fallback_title("App");
+    terminal.render(crate::span::<_, ()>("x")).unwrap();
+    assert_eq!(terminal.backend().title, "App");
+
+    terminal.set_icon_name(Some("icon")).unwrap();
+    terminal
+        .render(crate::span::<_, ()>("x").title("Title"))
+        .unwrap();
+    assert_eq!(terminal.backend().title, "Title");
+    assert_eq!(terminal.backend().icon_name, "icon");
+
+    terminal.set_icon_name(None).unwrap();
+    terminal
+        .render(crate::span::<_, ()>("x").title("Title"))
+        .unwrap();
+    assert_eq!(terminal.backend().icon_name, "Title");
+}
+
+#[cfg(test)]
+#[test]
+fn test_cursor() {
+    use crate::backend::{Dummy, Operation};
+    use crate::{Cursor, CursorShape, ElementExt as _};
+
+    let editor = |cursor: Cursor| {
+        crate::span::<_, ()>("").filter(AfterDraw(move |output: &mut dyn Output| {
+            output.set_cursor(Some(cursor));
+        }))
+    };
+
+    let mut cursor = Cursor {
+        shape: CursorShape::Block,
+        blinking: false,
+        color: Color::Default,
+        pos: Vec2::new(1, 0),
+    };
+    let mut terminal = Terminal::new(Dummy::new(Vec2::new(3, 1))).unwrap();
+    terminal.render(editor(cursor)).unwrap();
+
+    cursor.shape = CursorShape::Bar;
+    cursor.color = Color::Red;
+    terminal.backend_mut().operations.clear();
+    terminal.render(editor(cursor)).unwrap();
+    assert_eq!(
+        terminal.backend().operations[2..4],
+        [
+            Operation::SetCursorShape(CursorShape::Bar),
+            Operation::SetCursorColor(Color::Red),
+        ]
+    );
+
+    cursor.color = Color::Default;
+    terminal.backend_mut().operations.clear();
+    terminal.render(editor(cursor)).unwrap();
+    assert_eq!(
+        terminal.backend().operations[2],
+        Operation::SetCursorColor(Color::Default)
+    );
+    assert_eq!(terminal.backend().buffer.cursor, Some(cursor));
+}
+
+#[cfg(test)]
+#[test]
+fn test_focus() {
+    use crate::backend::Dummy;
+    use crate::ElementExt as _;
+
+    let mut backend = Dummy::new(Vec2::new(5, 1));
+    backend.events.push_back(TerminalEvent::Focus(false));
+    backend.events.push_back(TerminalEvent::Focus(true));
+
+    let mut terminal = Terminal::new(backend).unwrap();
+    let element = crate::span("x").on(crate::input!(Focus), |input| input.focus().unwrap());
+
+    let events = futures_lite::future::block_on(terminal.draw(&element)).unwrap();
+    assert_eq!(events, [false]);
+    let events = futures_lite::future::block_on(terminal.draw(&element)).unwrap();
+    assert_eq!(events, [true]);
+}
+
+#[cfg(test)]
+#[test]
+fn test_clicks() {
+    use crate::backend::{Dummy, TerminalMouse};
+    use crate::ElementExt as _;
+
+    let mouse = |kind, x| {
+        TerminalEvent::Mouse(TerminalMouse {
+            kind,
+            at: Vec2::new(x, 0),
+            modifiers: crate::Modifiers::NONE,
+        })
+    };
+    let press = |x| mouse(TerminalMouseKind::Press(MouseButton::Left), x);
+    let release = mouse(TerminalMouseKind::Release, 0);
+
+    let mut backend = Dummy::new(Vec2::new(5, 1));
+    backend.events.extend(vec![
+        press(1),
+        release.clone(),
+        press(1),
+        mouse(TerminalMouseKind::Move, 2),
+        release.clone(),
+        mouse(TerminalMouseKind::Move, 1),
+        press(1),
+        release.clone(),
+        // Too far away.
+        press(2),
+        release.clone(),
+        // A different button.
+        mouse(TerminalMouseKind::Press(MouseButton::Right), 2),
+        release,
+    ]);
+
+    let mut terminal = Terminal::new(backend).unwrap();
+    let element = crate::span("x").on(crate::input!(Mouse), |input| {
+        let mouse = input.mouse().unwrap();
+        (mouse.kind, mouse.clicks)
+    });
+    let mut clicks = Vec::new();
+    for _ in 0..12 {
+        clicks.extend(futures_lite::future::block_on(terminal.draw(&element)).unwrap());
+    }
+
+    let left = MouseButton::Left;
+    assert_eq!(
+        clicks,
+        [
+            (MouseKind::Press(left), 1),
+            (MouseKind::Release(left), 1),
+            (MouseKind::Press(left), 2),
+            (MouseKind::Drag(left), 2),
+            (MouseKind::Release(left), 2),
+            (MouseKind::Move, 0),
+            (MouseKind::Press(left), 3),
+            (MouseKind::Release(left), 3),
+            (MouseKind::Press(left), 1),
+            (MouseKind::Release(left), 1),
+            (MouseKind::Press(MouseButton::Right), 1),
+            (MouseKind::Release(MouseButton::Right), 1),
+        ]
+    );
+
+    let click = |terminal: &mut Terminal<Dummy>, x| {
+        terminal.backend_mut().events.push_back(press(x));
+        let events = futures_lite::future::block_on(terminal.draw(&element)).unwrap();
+        assert_eq!(events.len(), 1);
+        events[0].1
+    };
+
+    terminal.set_click_distance(1);
+    assert_eq!(click(&mut terminal, 1), 1);
+    assert_eq!(click(&mut terminal, 2), 2);
+
+    // Clicks further apart than the interval aren't counted together.
+    terminal.set_click_interval(Duration::from_secs(0));
+    std::thread::sleep(Duration::from_millis(1));
+    assert_eq!(click(&mut terminal, 2), 1);
+}
+
+#[cfg(test)]
+#[test]
+fn test_sequences() {
+    use crate::backend::Dummy;
+    use crate::{ElementExt as _, KeyPress};
+
+    let mut backend = Dummy::new(Vec2::new(3, 1));
+    for c in "gggagddgxg".chars() {
+        backend
+            .events
+            .push_back(TerminalEvent::Key(KeyPress::from(c)));
+    }
+
+    let mut terminal = Terminal::new(backend).unwrap();
+    terminal.set_sequence_timeout(Some(Duration::from_millis(10)));
+    let handled = std::cell::Cell::new(0);
+    let element = crate::span("x")
+        .on('a', |_| {
+            handled.set(handled.get() + 1);
+            "a"
+        })
+        .on_sequence(crate::input_seq!(Key(d), Key(d)), |_| "dd")
+        .on_sequence(crate::input_seq!(Key(g), Key(x)), |_| "gx")
+        .on_sequence(crate::input_seq!(Key(g), Key(g)), |_| "gg")
+        .on_sequence(crate::input_seq!(Key(g)), |_| "g");
+    let draw = |terminal: &mut Terminal<Dummy>| {
+        futures_lite::future::block_on(terminal.draw(&element)).unwrap()
+    };
+
+    assert_eq!(draw(&mut terminal), [] as [&str; 0]);
+    assert_eq!(draw(&mut terminal), ["gg"]);
+    // Inputs that don't continue the sequence abandon it.
+    assert_eq!(draw(&mut terminal), [] as [&str; 0]);
+    assert_eq!(draw(&mut terminal), ["a"]);
+    assert_eq!(handled.get(), 1);
+    // Inputs that start another sequence abandon it for that one.
+    assert_eq!(draw(&mut terminal), [] as [&str; 0]);
+    assert_eq!(draw(&mut terminal), [] as [&str; 0]);
+    assert_eq!(terminal.pending_sequence(), ['d']);
+    assert_eq!(draw(&mut terminal), ["dd"]);
+    assert_eq!(draw(&mut terminal), [] as [&str; 0]);
+    assert_eq!(draw(&mut terminal), ["gx"]);
+    assert_eq!(draw(&mut terminal), [] as [&str; 0]);
+    assert_eq!(terminal.pending_sequence(), ['g']);
+
+    // The sequence times out, completing the shorter one.
+    assert_eq!(draw(&mut terminal), ["g"]);
+    assert!(terminal.pending_sequence().is_empty());
+}
+
+#[cfg(test)]
+#[test]
+fn test_with_released() {
+    use crate::backend::{Dummy, Operation};
+    use crate::{ElementExt as _, KeyPress};
+
+    let mut backend = Dummy::new(Vec2::new(3, 1));
+    let key = TerminalEvent::Key(KeyPress::from('a'));
+    backend.events.push_back(key.clone());
+    backend.events.push_back(key);
+
+    let mut terminal = Terminal::new(backend).unwrap();
+    let element = crate::span("x").on('a', |_| ());
+
+    futures_lite::future::block_on(terminal.draw(&element)).unwrap();
+
+    terminal.backend_mut().operations.clear();
+    assert_eq!(terminal.with_released(|| 5).unwrap(), 5);
+    assert_eq!(
+        terminal.backend().operations[..3],
+        [
+            Operation::Suspend,
+            Operation::Resume,
+            Operation::EnterAlternateScreen,
+        ]
+    );
+
+    // The whole frame is drawn again, even though it hasn't changed.
+    terminal.backend_mut().operations.clear();
+    futures_lite::future::block_on(terminal.draw(&element)).unwrap();
+    let operations = &terminal.backend().operations;
+    assert!(operations.contains(&Operation::SetTitle("Toon App".to_owned())));
+    assert!(operations.contains(&Operation::Write("x".to_owned())));
+
+    // Once closed, the terminal fails instead of panicking.
+    terminal.close();
+    let mut ran = false;
+    assert!(matches!(
+        terminal.with_released(|| ran = true),
+        Err(Error::Closed)
+    ));
+    assert!(!ran);
+    assert!(matches!(terminal.render(element), Err(Error::Closed)));
+}
+
+#[cfg(test)]
+#[test]
+fn test_redraw_handle() {
+    use crate::backend::Dummy;
+    use crate::{ElementExt as _, KeyPress};
+
+    let mut backend = Dummy::new(Vec2::new(3, 1));
+    backend
+        .events
+        .push_back(TerminalEvent::Key(KeyPress::from('a')));
+
+    let mut terminal = Terminal::new(backend).unwrap();
+    let element = crate::span("x").on('a', |_| ());
+
+    let handle = terminal.redraw_handle();
+    handle.redraw();
+    let events = futures_lite::future::block_on(terminal.draw(&element)).unwrap();
+    assert!(events.is_empty());
+
+    // The request is only handled once.
+    let events = futures_lite::future::block_on(terminal.draw(&element)).unwrap();
+    assert_eq!(events, [()]);
+
+    terminal.render(element).unwrap();
+    handle.redraw();
+    let input = futures_lite::future::block_on(terminal.next_input()).unwrap();
+    assert_eq!(input, None);
+}
+
+#[cfg(test)]
+#[test]
+fn test_frame_pacing() {
+    use crate::backend::{Dummy, Operation};
+    use crate::{ElementExt as _, KeyPress};
+
+    let mut terminal = Terminal::new(Dummy::new(Vec2::new(1, 1))).unwrap();
+    terminal.set_max_fps(20);
+
+    // Requests a frame as soon as it is drawn.
+    let element = crate::span("x")
+        .animate(Duration::default())
+        .on('a', |_| "a");
+
+    // A frame was just drawn, so the input is handled without drawing another.
+    terminal.last_render = Some(Instant::now());
+    terminal.backend_mut().operations.clear();
+    let key = TerminalEvent::Key(KeyPress::from('a'));
+    terminal.backend_mut().events.push_back(key);
+    let events = futures_lite::future::block_on(terminal.draw(&element)).unwrap();
+    assert_eq!(events, ["a"]);
+    assert!(!terminal
+        .backend()
+        .operations
+        .contains(&Operation::Write("x".to_owned())));
+
+    // Once the frame interval has passed the next frame is drawn straight away.
+    terminal.last_render = Instant::now().checked_sub(terminal.frame_interval);
+    let events = futures_lite::future::block_on(terminal.draw(&element)).unwrap();
+    assert!(events.is_empty());
+    assert!(terminal
+        .backend()
+        .operations
+        .contains(&Operation::Write("x".to_owned())));
+}
+
+#[cfg(test)]
+#[test]
+fn test_scroll() {
+    use crate::backend::{Dummy, Operation};
+    use crate::output::Ext as _;
+
+    let mut old_grid = Grid::new(Vec2::new(2, 6));
+    for i in 0..5 {
+        old_grid.write((0, i), i, Style::default());
+    }
+    old_grid.write((0, 5), "--", Style::default());
+
+    // Scroll the first five lines up by two, keeping the last line in place.
+    let mut new_grid = Grid::new(Vec2::new(2, 6));
+    for i in 0..5 {
+        new_grid.write((0, i), i + 2, Style::default());
+    }
+    new_grid.write((0, 5), "--", Style::default());
+
+    let mut backend = Dummy::new(old_grid.size());
+    backend.buffer.grid = old_grid.clone();
+
+    let mut terminal: Terminal<Dummy> = Terminal::new(backend).unwrap();
+    terminal.backend_mut().operations.clear();
+    terminal.old_buffer = Buffer::from(old_grid);
+    terminal.buffer = Buffer::from(new_grid.clone());
+    terminal.diff().unwrap();
+
+    assert_eq!(terminal.backend().buffer.grid, new_grid);
+
+    let operations = &terminal.backend().operations;
+    assert_eq!(operations[1], Operation::Scroll(0..5, 2));
+    let written: Vec<_> = operations
+        .iter()
+        .filter_map(|operation| match operation {
+            Operation::Write(text) => Some(&**text),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(written, ["5", "6"]);
+}
@@ -0,0 +1,147 @@
+//! A timer that doesn't depend on any async runtime.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, Once};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Instant;
+
+/// A future that completes at an instant.
+///
+/// All timers are driven by a single background thread, which is started the first time a timer
+/// has to wait.
+#[derive(Debug)]
+pub(crate) struct Timer {
+    at: Instant,
+    /// The key of the timer's waker in [`TIMERS`], if it has been registered.
+    key: Option<usize>,
+}
+
+impl Timer {
+    pub(crate) fn at(at: Instant) -> Self {
+        Self { at, key: None }
+    }
+}
+
+impl Future for Timer {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if Instant::now() >= self.at {
+            return Poll::Ready(());
+        }
+
+        START.call_once(|| {
+            thread::Builder::new()
+                .name("toon-timer".to_owned())
+                .spawn(run)
+                .expect("failed to spawn timer thread");
+        });
+
+        let mut wakers = TIMERS.wakers.lock().unwrap();
+        let registered = self
+            .key
+            .and_then(|key| wakers.iter_mut().find(|timer| timer.key == key));
+        if let Some(timer) = registered {
+            timer.waker.clone_from(cx.waker());
+        } else {
+            let key = NEXT_KEY.fetch_add(1, Ordering::Relaxed);
+            self.key = Some(key);
+            wakers.push(Waiting {
+                key,
+                at: self.at,
+                waker: cx.waker().clone(),
+            });
+            TIMERS.changed.notify_one();
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            TIMERS
+                .wakers
+                .lock()
+                .unwrap()
+                .retain(|timer| timer.key != key);
+        }
+    }
+}
+
+/// A timer registered with the timer thread.
+#[derive(Debug)]
+struct Waiting {
+    key: usize,
+    at: Instant,
+    waker: Waker,
+}
+
+struct Timers {
+    /// The timers that are waiting. Timers are removed when they expire or are dropped.
+    wakers: Mutex<Vec<Waiting>>,
+    /// Notified when a timer is added.
+    changed: Condvar,
+}
+
+static TIMERS: Timers = Timers {
+    wakers: Mutex::new(Vec::new()),
+    changed: Condvar::new(),
+};
+
+static NEXT_KEY: AtomicUsize = AtomicUsize::new(0);
+
+static START: Once = Once::new();
+
+/// The body of the timer thread.
+fn run() {
+    let mut wakers = TIMERS.wakers.lock().unwrap();
+    loop {
+        let now = Instant::now();
+        wakers.retain(|timer| {
+            let expired = timer.at <= now;
+            if expired {
+                timer.waker.wake_by_ref();
+            }
+            !expired
+        });
+
+        wakers = match wakers.iter().map(|timer| timer.at).min() {
+            Some(next) => TIMERS.changed.wait_timeout(wakers, next - now).unwrap().0,
+            None => TIMERS.changed.wait(wakers).unwrap(),
+        };
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn test_registration() {
+    use futures_lite::future;
+    use std::time::Duration;
+
+    let registered = |key: Option<usize>| {
+        TIMERS
+            .wakers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|timer| Some(timer.key) == key)
+            .count()
+    };
+
+    // Polling a timer again updates its registration instead of adding another.
+    let mut timer = Timer::at(Instant::now() + Duration::from_secs(3600));
+    for _ in 0..3 {
+        assert_eq!(future::block_on(future::poll_once(&mut timer)), None);
+    }
+    assert_eq!(registered(timer.key), 1);
+
+    // Dropping the timer removes its registration.
+    let key = timer.key;
+    drop(timer);
+    assert_eq!(registered(key), 0);
+}
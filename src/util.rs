@@ -8,20 +8,6 @@ impl<F: FnMut(&str) -> fmt::Result> fmt::Write for WriteFn<F> {
     }
 }
 
-pub(crate) struct WriteCharsFn<F: FnMut(char) -> fmt::Result>(pub(crate) F);
-
-impl<F: FnMut(char) -> fmt::Result> fmt::Write for WriteCharsFn<F> {
-    fn write_str(&mut self, s: &str) -> fmt::Result {
-        for c in s.chars() {
-            self.0(c)?;
-        }
-        Ok(())
-    }
-    fn write_char(&mut self, c: char) -> fmt::Result {
-        self.0(c)
-    }
-}
-
 // pub(crate) struct DisplayFn<F: Fn(&mut Formatter<'_>) -> fmt::Result>(pub(crate) F);
 //
 // impl<F: Fn(&mut Formatter<'_>) -> fmt::Result> Display for DisplayFn<F> {
@@ -0,0 +1,253 @@
+//! Measuring how wide terminals display characters.
+//!
+//! By default Toon uses [`unicode_width`] to get the number of cells a character takes up, but
+//! terminals don't always agree with it: emoji, characters of ambiguous width and emoji sequences
+//! are displayed differently by different terminals. The only reliable way to know is to measure,
+//! so when a terminal is created its backend writes each of the [`PROBES`] and reads back the
+//! position of the cursor. While the terminal is drawing an element and passing it input, the
+//! resulting [`WidthTable`] is used by [`char_width`], [`grapheme_width`] and everything built on
+//! them, such as [`Grid`](crate::Grid) and [`Span::width`](crate::Span::width).
+
+use std::cell::RefCell;
+use std::cmp::min;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+/// The strings measured when a terminal is created.
+pub const PROBES: &[&str] = &[
+    // Characters of ambiguous width, which are wide in East Asian contexts.
+    "·",
+    "…",
+    "→",
+    "○",
+    "α",
+    "Ω",
+    // Characters that are text by default, but that some terminals display as emoji.
+    "☀",
+    "☺",
+    "❤",
+    "⚠",
+    // Emoji and emoji sequences.
+    "😃",
+    "❤\u{fe0f}",
+    "🇬🇧",
+    "👨\u{200d}👩\u{200d}👧",
+];
+
+/// A table of how wide a terminal displays strings.
+///
+/// Strings that aren't in the table are measured with [`unicode_width`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WidthTable {
+    widths: HashMap<String, u16>,
+}
+
+impl WidthTable {
+    /// Create a new empty width table.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the width of a string in the table.
+    pub fn insert(&mut self, text: impl Into<String>, width: u16) {
+        self.widths.insert(text.into(), width);
+    }
+
+    /// Get the width of a string in the table, if it is there.
+    #[must_use]
+    pub fn get(&self, text: &str) -> Option<u16> {
+        self.widths.get(text).copied()
+    }
+
+    /// Get the width of a character, or `None` if it is a control character.
+    ///
+    /// Characters that aren't in the table are measured with [`unicode_width`]. Widths are
+    /// clamped to 2, the widest a single character can be displayed in a cell.
+    #[must_use]
+    pub fn char_width(&self, c: char) -> Option<usize> {
+        if c.is_ascii() || self.widths.is_empty() {
+            return c.width();
+        }
+        match self.get(c.encode_utf8(&mut [0; 4])) {
+            Some(width) if !c.is_control() => Some(min(width, 2).into()),
+            _ => c.width(),
+        }
+    }
+
+    /// Get the width of an extended grapheme cluster, or `None` if it starts with a control
+    /// character.
+    ///
+    /// Clusters that aren't in the table are as wide as their first character, unless they are
+    /// displayed as emoji because they contain an emoji presentation selector or are a flag, in
+    /// which case they are double-width.
+    ///
+    /// Clusters can be measured wider than the two columns a cell can be, for example when the
+    /// terminal doesn't support an emoji sequence and displays each emoji in it separately.
+    /// [`Grid`](crate::Grid) writes those clusters as their [parts](split_cluster), so that they
+    /// take up the measured number of columns. Clusters that consist of only one part are clamped
+    /// to 2.
+    #[must_use]
+    pub fn grapheme_width(&self, grapheme: &str) -> Option<usize> {
+        let mut chars = grapheme.chars();
+        let first = chars.next()?;
+        let second = match chars.next() {
+            Some(second) => second,
+            None => return self.char_width(first),
+        };
+        let width = self.char_width(first)?;
+
+        Some(match self.get(grapheme) {
+            Some(width) if split_cluster(grapheme).nth(1).is_none() => min(width, 2).into(),
+            Some(width) => width.into(),
+            None if grapheme.contains('\u{fe0f}') => 2,
+            None if is_regional_indicator(first) && is_regional_indicator(second) => 2,
+            None => width,
+        })
+    }
+
+    /// Get the width of a string, splitting it into extended grapheme clusters. Clusters that start
+    /// with control characters are zero-width.
+    #[must_use]
+    pub fn str_width(&self, text: &str) -> usize {
+        text.graphemes(true)
+            .filter_map(|grapheme| self.grapheme_width(grapheme))
+            .sum()
+    }
+}
+
+/// Split an extended grapheme cluster into parts that each start with a character that isn't
+/// zero-width, followed by the zero-width characters after it.
+///
+/// These are how a terminal that doesn't support a cluster displays it.
+pub(crate) fn split_cluster(grapheme: &str) -> impl Iterator<Item = &str> {
+    let mut rest = grapheme;
+    std::iter::from_fn(move || {
+        let mut chars = rest.char_indices();
+        chars.next()?;
+        let end = chars
+            .find(|&(_, c)| c.width() != Some(0))
+            .map_or(rest.len(), |(i, _)| i);
+        let (part, after) = rest.split_at(end);
+        rest = after;
+        Some(part)
+    })
+}
+
+/// Regional indicators are the letters that pairs of make up flag emoji.
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1f1e6}'..='\u{1f1ff}').contains(&c)
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Arc<WidthTable>>> = const { RefCell::new(None) };
+}
+
+/// Get the width of a character, or `None` if it is a control character.
+///
+/// This uses the width table of the terminal drawing on this thread if there is one, and
+/// [`unicode_width`] otherwise.
+#[must_use]
+pub fn char_width(c: char) -> Option<usize> {
+    if c.is_ascii() {
+        return c.width();
+    }
+    with_table(|table| table.char_width(c))
+}
+
+/// Get the width of an extended grapheme cluster, or `None` if it starts with a control character.
+///
+/// This uses the width table of the terminal drawing on this thread if there is one. See
+/// [`WidthTable::grapheme_width`] for how clusters are measured.
+#[must_use]
+pub fn grapheme_width(grapheme: &str) -> Option<usize> {
+    with_table(|table| table.grapheme_width(grapheme))
+}
+
+/// Get the width of a string, splitting it into extended grapheme clusters.
+///
+/// This uses the width table of the terminal drawing on this thread if there is one.
+#[must_use]
+pub fn str_width(text: &str) -> usize {
+    if text.is_ascii() {
+        return text.chars().filter_map(UnicodeWidthChar::width).sum();
+    }
+    with_table(|table| table.str_width(text))
+}
+
+/// Run a function with the current width table, or an empty one if there isn't one.
+fn with_table<R>(f: impl FnOnce(&WidthTable) -> R) -> R {
+    CURRENT.with(|current| match &*current.borrow() {
+        Some(table) => f(table),
+        None => f(&WidthTable::new()),
+    })
+}
+
+/// Makes a width table the one used on this thread until it is dropped.
+#[derive(Debug)]
+pub(crate) struct Scope {
+    previous: Option<Arc<WidthTable>>,
+}
+
+impl Scope {
+    pub(crate) fn enter(table: Arc<WidthTable>) -> Self {
+        Self {
+            previous: CURRENT.with(|current| current.replace(Some(table))),
+        }
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        CURRENT.with(|current| *current.borrow_mut() = self.previous.take());
+    }
+}
+
+#[test]
+fn test_width_table() {
+    let mut table = WidthTable::new();
+    table.insert("☀", 2);
+    table.insert("😃", 1);
+
+    assert_eq!(table.char_width('☀'), Some(2));
+    assert_eq!(table.char_width('😃'), Some(1));
+    assert_eq!(table.char_width('→'), Some(1));
+    assert_eq!(table.char_width('a'), Some(1));
+    assert_eq!(table.char_width('\n'), None);
+
+    assert_eq!(char_width('☀'), Some(1));
+    {
+        let _scope = Scope::enter(Arc::new(table));
+        assert_eq!(char_width('☀'), Some(2));
+        assert_eq!(char_width('😃'), Some(1));
+    }
+    assert_eq!(char_width('☀'), Some(1));
+}
+
+#[test]
+fn test_grapheme_width() {
+    let mut table = WidthTable::new();
+    assert_eq!(table.grapheme_width("a"), Some(1));
+    assert_eq!(table.grapheme_width("e\u{301}"), Some(1));
+    assert_eq!(table.grapheme_width("❤\u{fe0f}"), Some(2));
+    assert_eq!(table.grapheme_width("🇬🇧"), Some(2));
+    assert_eq!(table.grapheme_width("👨\u{200d}👩\u{200d}👧"), Some(2));
+    assert_eq!(table.grapheme_width("\r\n"), None);
+    assert_eq!(table.grapheme_width(""), None);
+
+    table.insert("👨\u{200d}👩\u{200d}👧", 6);
+    assert_eq!(table.grapheme_width("👨\u{200d}👩\u{200d}👧"), Some(6));
+    table.insert("🇬🇧", 1);
+    assert_eq!(table.grapheme_width("🇬🇧"), Some(1));
+    // A cluster of one part can't be wider than a cell.
+    table.insert("❤\u{fe0f}", 3);
+    assert_eq!(table.grapheme_width("❤\u{fe0f}"), Some(2));
+
+    assert_eq!(table.str_width("a🇬🇧❤\u{fe0f}\n"), 4);
+
+    let parts: Vec<_> = split_cluster("👨\u{200d}👩\u{200d}👧").collect();
+    assert_eq!(parts, ["👨\u{200d}", "👩\u{200d}", "👧"]);
+}